@@ -0,0 +1,315 @@
+use std::borrow::Cow;
+
+use encoding_rs::{Encoding, UTF_8, UTF_16BE, UTF_16LE, WINDOWS_1252, X_USER_DEFINED};
+
+/// The number of bytes the prescan looks at, see
+/// [the spec](https://html.spec.whatwg.org/multipage/parsing.html#prescan-a-byte-stream-to-determine-its-encoding).
+const PRESCAN_LEN: usize = 1024;
+
+/// Decode an HTML document, following the
+/// [encoding sniffing algorithm](https://html.spec.whatwg.org/multipage/parsing.html#encoding-sniffing-algorithm):
+/// a BOM wins over the transport layer hint (a charset label or a
+/// `Content-Type` header value), which wins over `<meta charset>` in the first
+/// 1024 bytes. Without any of them, the bytes are decoded as UTF-8 if they are
+/// valid UTF-8, as windows-1252 otherwise. Malformed sequences are replaced
+/// with U+FFFD.
+pub(crate) fn decode_html<'a>(bytes: &'a [u8], transport_hint: Option<&str>) -> Cow<'a, str> {
+    let encoding = transport_hint
+        .and_then(encoding_from_hint)
+        .or_else(|| prescan(bytes))
+        .unwrap_or_else(|| {
+            if std::str::from_utf8(bytes).is_ok() {
+                UTF_8
+            } else {
+                WINDOWS_1252
+            }
+        });
+    // `decode()` sniffs the BOM itself, which takes precedence.
+    let (text, _, _) = encoding.decode(bytes);
+    text
+}
+
+fn encoding_from_hint(hint: &str) -> Option<&'static Encoding> {
+    // Either a plain label like `shift_jis`, or `text/html; charset=shift_jis`
+    if hint.contains(['=', ';']) {
+        extract_charset_from_content(hint.as_bytes())
+    } else {
+        Encoding::for_label(hint.trim().as_bytes())
+    }
+}
+
+fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, b'\t' | b'\n' | b'\x0C' | b'\r' | b' ')
+}
+
+/// [Prescan](https://html.spec.whatwg.org/multipage/parsing.html#prescan-a-byte-stream-to-determine-its-encoding)
+/// the start of the document for a `<meta>` declaring the encoding.
+fn prescan(bytes: &[u8]) -> Option<&'static Encoding> {
+    let bytes = &bytes[..bytes.len().min(PRESCAN_LEN)];
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let rest = &bytes[pos..];
+        if rest.starts_with(b"<!--") {
+            // `<!-->` is a complete comment.
+            pos += 2 + find(&rest[2..], b"-->")? + 3;
+        } else if starts_with_ignore_case(rest, b"<meta")
+            && rest.get(5).is_some_and(|&b| is_whitespace(b) || b == b'/')
+        {
+            pos += 5;
+            if let Some(encoding) = parse_meta(bytes, &mut pos) {
+                return Some(encoding);
+            }
+        } else if rest.len() >= 2
+            && rest[0] == b'<'
+            && (rest[1].is_ascii_alphabetic()
+                || (rest[1] == b'/' && rest.get(2).is_some_and(u8::is_ascii_alphabetic)))
+        {
+            // Skip the tag name and the attributes of other tags.
+            pos += rest
+                .iter()
+                .position(|&b| is_whitespace(b) || b == b'>')
+                .unwrap_or(rest.len());
+            while get_attribute(bytes, &mut pos).is_some() {}
+        } else if rest.starts_with(b"<!") || rest.starts_with(b"</") || rest.starts_with(b"<?") {
+            pos += find(rest, b">")? + 1;
+        } else {
+            pos += 1;
+        }
+    }
+    None
+}
+
+/// Parse the attributes of a `<meta>` tag, `pos` is after `<meta`.
+fn parse_meta(bytes: &[u8], pos: &mut usize) -> Option<&'static Encoding> {
+    let mut names: Vec<Vec<u8>> = vec![];
+    let mut got_pragma = false;
+    let mut need_pragma = None;
+    let mut charset = None;
+
+    while let Some((name, value)) = get_attribute(bytes, pos) {
+        if names.contains(&name) {
+            continue;
+        }
+        match name.as_slice() {
+            b"http-equiv" => got_pragma |= value == b"content-type",
+            b"content" if charset.is_none() => {
+                if let Some(encoding) = extract_charset_from_content(&value) {
+                    charset = Some(encoding);
+                    need_pragma = Some(true);
+                }
+            }
+            b"charset" => {
+                charset = Encoding::for_label(&value);
+                need_pragma = Some(false);
+            }
+            _ => {}
+        }
+        names.push(name);
+    }
+
+    if need_pragma.is_none() || (need_pragma == Some(true) && !got_pragma) {
+        return None;
+    }
+    let charset = charset?;
+    if charset == UTF_16BE || charset == UTF_16LE {
+        Some(UTF_8)
+    } else if charset == X_USER_DEFINED {
+        Some(WINDOWS_1252)
+    } else {
+        Some(charset)
+    }
+}
+
+/// [Get an attribute](https://html.spec.whatwg.org/multipage/parsing.html#concept-get-attributes-when-sniffing),
+/// with the name and value lowercased. Returns `None` at the end of the tag.
+fn get_attribute(bytes: &[u8], pos: &mut usize) -> Option<(Vec<u8>, Vec<u8>)> {
+    let byte_at = |pos: usize| bytes.get(pos).copied();
+
+    while byte_at(*pos).is_some_and(|b| is_whitespace(b) || b == b'/') {
+        *pos += 1;
+    }
+    if byte_at(*pos)? == b'>' {
+        return None;
+    }
+
+    let mut name = vec![];
+    let mut value = vec![];
+    // The name
+    loop {
+        let byte = byte_at(*pos)?;
+        match byte {
+            b'=' if !name.is_empty() => {
+                *pos += 1;
+                break;
+            }
+            _ if is_whitespace(byte) => {
+                while byte_at(*pos).is_some_and(is_whitespace) {
+                    *pos += 1;
+                }
+                if byte_at(*pos)? != b'=' {
+                    return Some((name, value));
+                }
+                *pos += 1;
+                break;
+            }
+            b'/' | b'>' => return Some((name, value)),
+            _ => name.push(byte.to_ascii_lowercase()),
+        }
+        *pos += 1;
+    }
+
+    // The value
+    while byte_at(*pos).is_some_and(is_whitespace) {
+        *pos += 1;
+    }
+    let byte = byte_at(*pos)?;
+    if byte == b'"' || byte == b'\'' {
+        *pos += 1;
+        loop {
+            let next = byte_at(*pos)?;
+            *pos += 1;
+            if next == byte {
+                return Some((name, value));
+            }
+            value.push(next.to_ascii_lowercase());
+        }
+    }
+    if byte == b'>' {
+        return Some((name, value));
+    }
+    loop {
+        let byte = byte_at(*pos)?;
+        if is_whitespace(byte) || byte == b'>' {
+            return Some((name, value));
+        }
+        value.push(byte.to_ascii_lowercase());
+        *pos += 1;
+    }
+}
+
+/// [Extract a character encoding from a meta element](https://html.spec.whatwg.org/multipage/urls-and-fetching.html#algorithm-for-extracting-a-character-encoding-from-a-meta-element),
+/// e.g. from `text/html; charset=utf-8`.
+fn extract_charset_from_content(content: &[u8]) -> Option<&'static Encoding> {
+    let mut pos = 0;
+    loop {
+        pos += find_ignore_case(&content[pos..], b"charset")? + b"charset".len();
+        while content.get(pos).copied().is_some_and(is_whitespace) {
+            pos += 1;
+        }
+        if content.get(pos) == Some(&b'=') {
+            pos += 1;
+            break;
+        }
+    }
+    while content.get(pos).copied().is_some_and(is_whitespace) {
+        pos += 1;
+    }
+    let rest = &content[pos..];
+    let label = match rest.first()? {
+        &quote @ (b'"' | b'\'') => {
+            let end = rest[1..].iter().position(|&b| b == quote)?;
+            &rest[1..end + 1]
+        }
+        _ => {
+            let end = rest
+                .iter()
+                .position(|&b| is_whitespace(b) || b == b';')
+                .unwrap_or(rest.len());
+            &rest[..end]
+        }
+    };
+    Encoding::for_label(label)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn find_ignore_case(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle))
+}
+
+fn starts_with_ignore_case(bytes: &[u8], prefix: &[u8]) -> bool {
+    bytes
+        .get(..prefix.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use encoding_rs::{SHIFT_JIS, UTF_8, WINDOWS_1252};
+
+    use super::{decode_html, extract_charset_from_content, prescan};
+
+    #[test]
+    fn test_prescan_meta_charset() {
+        assert_eq!(Some(SHIFT_JIS), prescan(b"<meta charset=\"Shift_JIS\">"));
+        assert_eq!(Some(SHIFT_JIS), prescan(b"<META CHARSET=sjis />"));
+        assert_eq!(
+            Some(SHIFT_JIS),
+            prescan(
+                b"<!doctype html><html><head>\
+                <!-- <meta charset=utf-8> -->\
+                <title>x</title>\
+                <meta http-equiv=\"Content-Type\" content=\"text/html; charset=shift_jis\">"
+            )
+        );
+        // `content` needs `http-equiv`
+        assert_eq!(
+            None,
+            prescan(b"<meta content=\"text/html; charset=shift_jis\">")
+        );
+        // Attributes of other tags are skipped
+        assert_eq!(
+            Some(UTF_8),
+            prescan(b"<div title='<meta charset=sjis>'><meta charset=utf-8>")
+        );
+        // UTF-16 can't be declared in an ASCII compatible prefix
+        assert_eq!(Some(UTF_8), prescan(b"<meta charset=utf-16le>"));
+        assert_eq!(None, prescan(b"<p>No meta</p>"));
+    }
+
+    #[test]
+    fn test_extract_charset_from_content() {
+        assert_eq!(
+            Some(SHIFT_JIS),
+            extract_charset_from_content(b"text/html; charset = \"shift_jis\"")
+        );
+        assert_eq!(
+            Some(WINDOWS_1252),
+            extract_charset_from_content(b"text/html;charset=latin1;foo")
+        );
+        assert_eq!(None, extract_charset_from_content(b"text/html; charset"));
+    }
+
+    #[test]
+    fn test_decode_html() {
+        // こんにちは in Shift_JIS
+        let sjis = b"\x82\xb1\x82\xf1\x82\xc9\x82\xbf\x82\xcd";
+        let mut html = b"<meta charset=shift_jis><p>".to_vec();
+        html.extend_from_slice(sjis);
+        assert_eq!(
+            "<meta charset=shift_jis><p>こんにちは",
+            decode_html(&html, None)
+        );
+        // The transport layer wins over <meta>
+        assert_eq!(
+            "<p>\u{fffd}",
+            decode_html(b"<p>\x82", Some("text/html; charset=utf-8"))
+        );
+        assert_eq!("<p>\u{201a}", decode_html(b"<p>\x82", Some("windows-1252")));
+        // The BOM wins over everything
+        assert_eq!(
+            "caf\u{e9}",
+            decode_html(b"\xEF\xBB\xBFcaf\xC3\xA9", Some("windows-1252"))
+        );
+        assert_eq!("hi", decode_html(b"\xFF\xFEh\0i\0", None));
+        // Without declarations
+        assert_eq!("caf\u{e9}", decode_html(b"caf\xC3\xA9", None));
+        assert_eq!("caf\u{e9}", decode_html(b"caf\xE9", None));
+    }
+}
@@ -0,0 +1,378 @@
+//! Splitting of the Markdown into chunks, see
+//! [`HtmlToMarkdown::convert_to_chunks`](crate::HtmlToMarkdown::convert_to_chunks).
+
+use std::{borrow::Cow, ops::Range};
+
+use crate::source_map::{LINE_PREFIX, MappedBlock};
+
+/// A part of the Markdown of a document.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Chunk {
+    /// The Markdown of the chunk.
+    pub markdown: String,
+    /// The text of the headings the chunk is nested in, from the top-level
+    /// heading to the closest one.
+    pub breadcrumb: Vec<String>,
+}
+
+/// A part of the Markdown that is never split.
+struct Block {
+    range: Range<usize>,
+    kind: BlockKind,
+}
+
+#[derive(PartialEq)]
+enum BlockKind {
+    Heading { level: usize, text: String },
+    ListItem,
+    Other,
+}
+
+/// Split `markdown` into chunks of at most `max_size`, as measured by `size`,
+/// at the boundaries of the `mapped_blocks` recorded while converting it. Chunks
+/// start at headings and end at block boundaries, so tables and code blocks
+/// are never cut, and lists are only cut between items. Blockquotes and list
+/// items larger than `max_size` are cut between the blocks in them. A block
+/// larger than `max_size` gets a chunk of its own.
+pub(crate) fn chunk_markdown(
+    markdown: &str,
+    mapped_blocks: &[MappedBlock],
+    max_size: usize,
+    size: &dyn Fn(&str) -> usize,
+) -> Vec<Chunk> {
+    let mut splitter = Splitter {
+        markdown,
+        max_size,
+        size,
+        blocks: vec![],
+        items: vec![],
+    };
+    splitter.split(0..markdown.len(), mapped_blocks);
+    let Splitter { blocks, items, .. } = splitter;
+    let mut chunker = Chunker {
+        markdown,
+        items: &items,
+        chunks: vec![],
+        breadcrumb: vec![],
+        range: None,
+        size: 0,
+        has_content: false,
+    };
+
+    let mut index = 0;
+    while index < blocks.len() {
+        let block = &blocks[index];
+        if let BlockKind::Heading { level, text } = &block.kind {
+            // Headings stay with the content that follows them, including
+            // deeper headings.
+            let is_deeper = chunker.breadcrumb.last().is_none_or(|(it, _)| *it < *level);
+            if chunker.has_content || !is_deeper {
+                chunker.flush();
+            }
+            while chunker
+                .breadcrumb
+                .last()
+                .is_some_and(|(it, _)| *it >= *level)
+            {
+                chunker.breadcrumb.pop();
+            }
+            chunker.breadcrumb.push((*level, text.clone()));
+            let block_size = size(&chunker.text(block.range.clone()));
+            let separator_size = chunker.separator_size(block.range.start, size);
+            chunker.push(block.range.clone(), block_size, separator_size);
+            chunker.has_content = false;
+            index += 1;
+            continue;
+        }
+
+        // Keep the items of a list together if the list fits in a chunk.
+        let mut end = index + 1;
+        if block.kind == BlockKind::ListItem {
+            while blocks
+                .get(end)
+                .is_some_and(|it| it.kind == BlockKind::ListItem)
+            {
+                end += 1;
+            }
+        }
+        let sizes: Vec<usize> = blocks[index..end]
+            .iter()
+            .map(|it| size(&chunker.text(it.range.clone())))
+            .collect();
+        let group_size = match sizes.len() {
+            1 => sizes[0],
+            _ => size(&chunker.text(block.range.start..blocks[end - 1].range.end)),
+        };
+        let separator_size = chunker.separator_size(block.range.start, size);
+        if group_size <= max_size && !chunker.fits(group_size, separator_size, max_size) {
+            chunker.flush();
+        }
+        for (block, block_size) in blocks[index..end].iter().zip(sizes) {
+            let separator_size = chunker.separator_size(block.range.start, size);
+            if !chunker.fits(block_size, separator_size, max_size) {
+                chunker.flush();
+            }
+            chunker.push(block.range.clone(), block_size, separator_size);
+            chunker.has_content = true;
+        }
+        index = end;
+    }
+    chunker.flush();
+    chunker.chunks
+}
+
+struct Chunker<'a> {
+    markdown: &'a str,
+    /// The list items that were cut, see [`Splitter::items`].
+    items: &'a [(Range<usize>, usize)],
+    chunks: Vec<Chunk>,
+    breadcrumb: Vec<(usize, String)>,
+    /// The range of the blocks of the current chunk in the Markdown.
+    range: Option<Range<usize>>,
+    /// The size of the current chunk.
+    size: usize,
+    /// Whether the current chunk has more than headings.
+    has_content: bool,
+}
+
+impl<'a> Chunker<'a> {
+    /// Whether a block of `block_size` can be added to the current chunk.
+    fn fits(&self, block_size: usize, separator_size: usize, max_size: usize) -> bool {
+        // Headings alone are never flushed.
+        !self.has_content || self.size + separator_size + block_size <= max_size
+    }
+
+    /// The size of the Markdown between the current chunk and a block at
+    /// `start`, e.g. a blank line, or an empty line of a blockquote.
+    fn separator_size(&self, start: usize, size: &dyn Fn(&str) -> usize) -> usize {
+        self.range
+            .as_ref()
+            .map_or(0, |range| size(&self.text(range.end..start)))
+    }
+
+    fn push(&mut self, block: Range<usize>, block_size: usize, separator_size: usize) {
+        match self.range.as_mut() {
+            Some(range) => {
+                self.size += separator_size;
+                range.end = block.end;
+            }
+            None => self.range = Some(block),
+        }
+        self.size += block_size;
+    }
+
+    fn flush(&mut self) {
+        let Some(range) = self.range.take() else {
+            return;
+        };
+        self.chunks.push(Chunk {
+            markdown: self.text(range).into_owned(),
+            breadcrumb: self
+                .breadcrumb
+                .iter()
+                .map(|(_, text)| text.clone())
+                .collect(),
+        });
+        self.size = 0;
+        self.has_content = false;
+    }
+
+    /// The Markdown in `range`, without the indentation of the lines in the
+    /// list items that were cut, which now stand on their own.
+    fn text(&self, range: Range<usize>) -> Cow<'a, str> {
+        let markdown = &self.markdown[range.clone()];
+        if !self
+            .items
+            .iter()
+            .any(|(item, _)| item.start < range.end && range.start < item.end)
+        {
+            return Cow::Borrowed(markdown);
+        }
+        let mut text = String::with_capacity(markdown.len());
+        let mut line_start = range.start;
+        for line in markdown.split_inclusive('\n') {
+            // The line with the marker of an item is not indented by it.
+            let indent: usize = self
+                .items
+                .iter()
+                .filter(|(item, _)| item.start < line_start && line_start < item.end)
+                .map(|(_, indent)| indent)
+                .sum();
+            dedent(line, indent, &mut text);
+            line_start += line.len();
+        }
+        Cow::Owned(text)
+    }
+}
+
+/// Push `line` to `text` without up to `indent` spaces of the indentation and
+/// quote markers at its start.
+fn dedent(line: &str, indent: usize, text: &mut String) {
+    let prefix_len = line.len() - line.trim_start_matches(LINE_PREFIX).len();
+    let mut removed = 0;
+    for ch in line[..prefix_len].chars() {
+        if ch == ' ' && removed < indent {
+            removed += 1;
+        } else {
+            text.push(ch);
+        }
+    }
+    text.push_str(&line[prefix_len..]);
+}
+
+/// The blocks the Markdown is split at.
+struct Splitter<'a> {
+    markdown: &'a str,
+    max_size: usize,
+    size: &'a dyn Fn(&str) -> usize,
+    blocks: Vec<Block>,
+    /// The list items that were cut between their blocks, with the width of
+    /// their markers, by which the lines after the first one are indented.
+    items: Vec<(Range<usize>, usize)>,
+}
+
+impl Splitter<'_> {
+    /// Split the Markdown in `range` at the boundaries of the `blocks` in
+    /// it, which are sorted by their start, with parents first. The Markdown
+    /// between the blocks, e.g. the front matter or inline content, is a
+    /// block too.
+    fn split(&mut self, range: Range<usize>, blocks: &[MappedBlock]) {
+        let mut position = range.start;
+        for (block, children) in top_level(blocks) {
+            self.push_other(position..block.range.start);
+            position = block.range.end;
+
+            let items: Vec<_> = top_level(children)
+                .filter(|(it, _)| it.tag == "li")
+                .collect();
+            if let Some(text) = &block.heading_text {
+                let level = block.tag[1..].parse().unwrap_or(1);
+                self.push(
+                    block.range.clone(),
+                    BlockKind::Heading {
+                        level,
+                        text: text.clone(),
+                    },
+                );
+            } else if matches!(block.tag.as_str(), "ul" | "ol") && !items.is_empty() {
+                let mut item_position = block.range.start;
+                for (item, item_children) in items {
+                    self.push_other(item_position..item.range.start);
+                    item_position = item.range.end;
+                    if self.is_oversized(item, item_children) {
+                        self.split_item(item, item_children);
+                    } else {
+                        self.push(item.range.clone(), BlockKind::ListItem);
+                    }
+                }
+                self.push_other(item_position..block.range.end);
+            } else if is_container(self.markdown, block, children)
+                || block.tag == "blockquote" && self.is_oversized(block, children)
+            {
+                self.split(block.range.clone(), children);
+            } else {
+                self.push_other(block.range.clone());
+            }
+        }
+        self.push_other(position..range.end);
+    }
+
+    /// Split a list item at the blocks in it. Its marker stays with the
+    /// first one.
+    fn split_item(&mut self, item: &MappedBlock, children: &[MappedBlock]) {
+        let text = &self.markdown[item.range.clone()];
+        let marker = text.split(' ').next().unwrap_or_default();
+        let indent = text.len() - text[marker.len()..].trim_start_matches(' ').len();
+        self.items.push((item.range.clone(), indent));
+        let first = self.blocks.len();
+        self.split(item.range.start + indent..item.range.end, children);
+        if let Some(block) = self.blocks.get_mut(first) {
+            block.range.start = line_start(self.markdown, item.range.start);
+            block.kind = BlockKind::ListItem;
+        }
+    }
+
+    /// Whether `block` is larger than a chunk, and has blocks in it to split
+    /// it at.
+    fn is_oversized(&self, block: &MappedBlock, children: &[MappedBlock]) -> bool {
+        !children.is_empty() && (self.size)(&self.markdown[block.range.clone()]) > self.max_size
+    }
+
+    /// Push a block, with the quote markers and indentation before it on its
+    /// first line.
+    fn push(&mut self, range: Range<usize>, kind: BlockKind) {
+        self.blocks.push(Block {
+            range: line_start(self.markdown, range.start)..range.end,
+            kind,
+        });
+    }
+
+    /// Push the Markdown in `range` as a block, unless it is only whitespace
+    /// and the quote markers of empty lines.
+    fn push_other(&mut self, range: Range<usize>) {
+        let text = &self.markdown[range.clone()];
+        let trimmed = text
+            .trim_start_matches(|ch: char| ch.is_whitespace() || ch == '>')
+            .trim_end();
+        if !trimmed.is_empty() {
+            let start = range.start + text.len() - text.trim_start_matches(LINE_PREFIX).len();
+            self.push(
+                start..range.end - (text.len() - text.trim_end().len()),
+                BlockKind::Other,
+            );
+        }
+    }
+}
+
+/// The start of the line of `position` if there are only indentation and
+/// quote markers before it, otherwise `position`.
+fn line_start(markdown: &str, position: usize) -> usize {
+    let start = markdown[..position].rfind('\n').map_or(0, |it| it + 1);
+    if markdown[start..position]
+        .trim_start_matches(LINE_PREFIX)
+        .is_empty()
+    {
+        start
+    } else {
+        position
+    }
+}
+
+/// The blocks in `blocks` that are not nested in another one, with the
+/// blocks nested in them.
+fn top_level(blocks: &[MappedBlock]) -> impl Iterator<Item = (&MappedBlock, &[MappedBlock])> {
+    let mut index = 0;
+    std::iter::from_fn(move || {
+        let block = blocks.get(index)?;
+        let children = &blocks[index + 1..];
+        let end = children
+            .iter()
+            .take_while(|it| it.range.end <= block.range.end)
+            .count();
+        index += 1 + end;
+        Some((block, &children[..end]))
+    })
+}
+
+/// Whether the Markdown of `block` is made of the Markdown of the blocks
+/// nested in it, like for a `<div>` of paragraphs, and content separated from
+/// them by blank lines.
+fn is_container(markdown: &str, block: &MappedBlock, children: &[MappedBlock]) -> bool {
+    let mut position = block.range.start;
+    let is_separated = |start: usize, end: usize| {
+        let gap = &markdown[start..end];
+        let leading = &gap[..gap.len() - gap.trim_start().len()];
+        let trailing = &gap[gap.trim_end().len()..];
+        gap.trim().is_empty()
+            || (start == block.range.start || leading.contains("\n\n"))
+                && (end == block.range.end || trailing.contains("\n\n"))
+    };
+    for (child, _) in top_level(children) {
+        if !is_separated(position, child.range.start) {
+            return false;
+        }
+        position = child.range.end;
+    }
+    position > block.range.start && is_separated(position, block.range.end)
+}
@@ -1,12 +1,21 @@
-use html5ever::tendril::{Tendril, fmt::UTF8};
+use html5ever::{
+    Attribute,
+    tendril::{Tendril, fmt::UTF8},
+};
 use markup5ever_rcdom::{Node, NodeData};
 use phf::phf_set;
 use std::{borrow::Cow, cell::RefCell, rc::Rc};
 
-use crate::element_handler::ElementHandlers;
+use crate::{
+    element_handler::{ConversionContext, Handlers, li::is_ordered_item},
+    mdast::TreeState,
+    node_util::{get_node_tag_name, get_parent_node},
+    report::{ConversionReport, DropReason, DroppedElement},
+    source_map::SourceMapState,
+};
 
 use super::{
-    options::TranslationMode,
+    options::{FrontMatterStyle, MarkdownFlavor, TranslationMode},
     text_util::{
         TrimDocumentWhitespace, compress_whitespace, index_of_markdown_ordered_item_dot,
         is_markdown_atx_heading,
@@ -16,12 +25,15 @@ use super::{
 pub(crate) fn walk_node(
     node: &Rc<Node>,
     output: &mut String,
-    handlers: &ElementHandlers,
+    handlers: &ConversionContext,
     parent_tag: Option<&str>,
     trim_leading_spaces: bool,
     is_pre: bool,
 ) -> bool {
     let mut markdown_translated = true;
+    if handlers.is_aborted() {
+        return markdown_translated;
+    }
     match node.data {
         NodeData::Document => {
             let _ = walk_children(node, output, handlers, true, false);
@@ -31,6 +43,12 @@ pub(crate) fn walk_node(
         NodeData::Text { ref contents } => {
             let text = contents.borrow();
             let text = text.as_ref();
+            if handlers.reporting {
+                ConversionReport::count_words(handlers, text);
+            }
+            if handlers.source_mapping {
+                SourceMapState::push_text(handlers, text);
+            }
             if is_pre {
                 // Handle pre and code
                 let text = if parent_tag.is_some_and(|t| t == "pre") {
@@ -38,10 +56,15 @@ pub(crate) fn walk_node(
                 } else {
                     Cow::Borrowed(text)
                 };
+                let start = output.len();
                 output.push_str(text.as_ref());
+                if handlers.mdast {
+                    TreeState::push_text(handlers, &contents.borrow(), output, start);
+                }
             } else {
                 let last_ends_with_space = output.ends_with(' ');
-                if is_plain_text(text) {
+                let is_gfm = handlers.options().flavor == MarkdownFlavor::Gfm;
+                if is_plain_text(text, is_gfm) {
                     let text =
                         if trim_leading_spaces || (text.starts_with(' ') && last_ends_with_space) {
                             text.trim_start_matches(' ')
@@ -49,18 +72,23 @@ pub(crate) fn walk_node(
                             text
                         };
                     if !text.is_empty() {
+                        let start = output.len();
                         output.push_str(text);
+                        if handlers.mdast {
+                            TreeState::push_text(handlers, text, output, start);
+                        }
                     }
                     return markdown_translated;
                 }
 
                 // Handle other elements or texts
-                let text = escape_if_needed(Cow::Borrowed(text));
+                let raw_text = text;
+                let text = escape_if_needed(Cow::Borrowed(text), is_gfm);
                 let text = compress_whitespace(text.as_ref());
 
-                let to_add = if trim_leading_spaces
-                    || (text.chars().next().is_some_and(|ch| ch == ' ') && last_ends_with_space)
-                {
+                let is_trimmed = trim_leading_spaces
+                    || (text.chars().next().is_some_and(|ch| ch == ' ') && last_ends_with_space);
+                let to_add = if is_trimmed {
                     // We can't compress spaces between two text blocks/elements, so we
                     // compress them here by trimming the leading space of current text
                     // content.
@@ -69,7 +97,18 @@ pub(crate) fn walk_node(
                     text.as_ref()
                 };
                 if !to_add.is_empty() {
+                    let start = output.len();
                     output.push_str(to_add);
+                    if handlers.mdast {
+                        // The nodes hold the text before it is escaped.
+                        let value = compress_whitespace(raw_text);
+                        let value = if is_trimmed {
+                            value.trim_start_matches(' ')
+                        } else {
+                            value.as_ref()
+                        };
+                        TreeState::push_text(handlers, value, output, start);
+                    }
                 }
             }
         }
@@ -83,6 +122,37 @@ pub(crate) fn walk_node(
             let tag = &*name.local;
             let is_head = tag == "head";
 
+            let dropped = if handlers.is_excluded(node) {
+                Some(DropReason::Excluded)
+            } else if handlers.options().skip_hidden_content
+                && is_hidden(tag, &attrs.borrow(), handlers.handlers.scripting_enabled)
+            {
+                Some(DropReason::Hidden)
+            } else {
+                None
+            };
+            if let Some(reason) = dropped {
+                if handlers.reporting {
+                    report_dropped(handlers, tag, reason);
+                }
+                return markdown_translated;
+            }
+            // The metadata of the head is written as front matter instead.
+            if is_head && handlers.options().front_matter_style != FrontMatterStyle::Disabled {
+                return markdown_translated;
+            }
+            if !handlers.enter_element(tag) {
+                return markdown_translated;
+            }
+            // Words don't continue across blocks and line breaks.
+            let is_block = handlers.reporting && (is_block_element(tag) || tag == "br");
+            if is_block {
+                ConversionReport::break_word(handlers);
+            }
+            let is_mapped = handlers.source_mapping && SourceMapState::enter_element(handlers, tag);
+            if handlers.mdast {
+                TreeState::enter_element(handlers);
+            }
             let res = handlers.handle(
                 node,
                 tag,
@@ -90,30 +160,100 @@ pub(crate) fn walk_node(
                 true, // Default to true, handler will update
                 0,
             );
+            handlers.leave_element(tag);
+            if is_block {
+                ConversionReport::break_word(handlers);
+            }
+            let blocks = if handlers.source_mapping {
+                let content = res.as_ref().map(|res| res.content.as_str());
+                SourceMapState::leave_element(handlers, node, tag, is_mapped, content)
+            } else {
+                vec![]
+            };
 
             if let Some(res) = res {
                 markdown_translated = res.markdown_translated;
                 if !res.content.is_empty() || !is_head {
+                    let nodes = handlers.mdast.then(|| {
+                        let is_ordered_item = tag == "li" && is_ordered_item(node);
+                        TreeState::check_element(handlers, &res.content, is_ordered_item)
+                    });
+                    let start = output.len();
+                    let len = res.content.len();
                     append_normalized_content(output, res.content, is_pre);
+                    if !blocks.is_empty() {
+                        SourceMapState::appended(handlers, blocks, len, output.len());
+                    }
+                    if let Some(nodes) = nodes {
+                        TreeState::push_element(handlers, nodes, output, start);
+                    }
                 }
+            } else if handlers.reporting && handlers.handlers.has_custom_handler(tag) {
+                // Built-in handlers return no content for empty elements,
+                // which are not dropped.
+                report_dropped(handlers, tag, DropReason::Skipped);
             }
         }
 
         NodeData::Comment { ref contents } => {
-            if handlers.options.translation_mode == TranslationMode::Faithful {
+            if handlers.options().translation_mode == TranslationMode::Faithful {
+                let start = output.len();
                 output.push_str("<!--");
                 output.push_str(contents);
                 output.push_str("-->");
+                if handlers.mdast {
+                    TreeState::push_html(handlers, output, start);
+                }
             }
         }
         NodeData::Doctype { .. } => {}
         NodeData::ProcessingInstruction { .. } => unreachable!(),
     }
 
+    // Blank lines around blocks are trimmed from the final output.
+    handlers.check_output_len(output.trim_matches('\n').len());
     markdown_translated
 }
 
-fn is_plain_text(text: &str) -> bool {
+fn report_dropped(handlers: &ConversionContext, tag: &str, reason: DropReason) {
+    ConversionReport::record(handlers, |conversion| {
+        conversion.dropped_elements.push(DroppedElement {
+            tag: tag.to_string(),
+            reason,
+        })
+    });
+}
+
+/// Whether an element and its content are not rendered, see
+/// [`Options::skip_hidden_content`](crate::options::Options::skip_hidden_content).
+fn is_hidden(tag: &str, attrs: &[Attribute], scripting_enabled: bool) -> bool {
+    match tag {
+        "head" | "template" | "script" | "style" => return true,
+        "noscript" if scripting_enabled => return true,
+        _ => {}
+    }
+    attrs.iter().any(|attr| match &*attr.name.local {
+        // `until-found` content is revealed by find in page.
+        "hidden" => !attr.value.eq_ignore_ascii_case("until-found"),
+        "aria-hidden" => attr.value.trim().eq_ignore_ascii_case("true"),
+        "style" => attr.value.split(';').any(|declaration| {
+            let Some((property, value)) = declaration.split_once(':') else {
+                return false;
+            };
+            let value = value.trim().trim_end_matches("!important").trim_end();
+            match property.trim().to_ascii_lowercase().as_str() {
+                "display" => value.eq_ignore_ascii_case("none"),
+                "visibility" => {
+                    value.eq_ignore_ascii_case("hidden") || value.eq_ignore_ascii_case("collapse")
+                }
+                _ => false,
+            }
+        }),
+        _ => false,
+    })
+}
+
+fn is_plain_text(text: &str, is_gfm: bool) -> bool {
     let bytes = text.as_bytes();
     let Some(&first) = bytes.first() else {
         return true;
@@ -127,6 +267,7 @@ fn is_plain_text(text: &str) -> bool {
     for &byte in bytes {
         match byte {
             b'\\' | b'*' | b'_' | b'`' | b'[' | b']' | b'<' => return false,
+            b'~' | b'|' if is_gfm => return false,
             b' ' => {
                 if previous_was_space {
                     return false;
@@ -141,36 +282,134 @@ fn is_plain_text(text: &str) -> bool {
     true
 }
 
+/// Walk the document `node` like [`walk_node`], passing the Markdown in
+/// `output` to `flush` between the top-level blocks, so that it can be written
+/// out before the rest of the document is converted. `flush` must leave the
+/// trailing whitespace of `output`, which is normalized with the next blocks.
+///
+/// The `<html>` and `<body>` of the document are walked in place instead of
+/// being converted by their handlers, which only separate their content from
+/// the surrounding blocks.
+pub(crate) fn stream_node(
+    node: &Rc<Node>,
+    output: &mut String,
+    handlers: &ConversionContext,
+    flush: &mut dyn FnMut(&mut String),
+) {
+    if handlers.is_aborted() {
+        return;
+    }
+    match node.data {
+        NodeData::Document => {
+            stream_children(node, output, handlers, flush);
+            trim_output_end(output);
+        }
+        NodeData::Element { .. } if is_streamed_element(node, handlers) => {
+            stream_element(node, output, handlers, flush);
+        }
+        _ => {
+            walk_node(node, output, handlers, None, true, false);
+        }
+    }
+}
+
+/// Walk the children of a document, `<html>` or `<body>` like
+/// [`walk_children`], flushing the output between them.
+fn stream_children(
+    node: &Rc<Node>,
+    output: &mut String,
+    handlers: &ConversionContext,
+    flush: &mut dyn FnMut(&mut String),
+) {
+    combine_children(node);
+    let tag = match &node.data {
+        NodeData::Document => Some("html"),
+        NodeData::Element { name, .. } => Some(name.local.as_ref()),
+        _ => None,
+    };
+    let mut trim_leading_spaces = true;
+    for child in node.children.borrow().iter() {
+        if handlers.is_aborted() {
+            return;
+        }
+        flush(output);
+        let is_block = match &child.data {
+            NodeData::Element { name, .. } => is_block_element(&name.local),
+            _ => false,
+        };
+        if is_block {
+            trim_output_end_spaces(output);
+        }
+        if is_streamed_element(child, handlers) {
+            stream_element(child, output, handlers, flush);
+            trim_leading_spaces = true;
+            continue;
+        }
+        let output_len = output.len();
+        walk_node(child, output, handlers, tag, trim_leading_spaces, false);
+        if output.len() > output_len {
+            trim_leading_spaces = is_block;
+        }
+    }
+}
+
+/// Walk an `<html>` or `<body>` element in place, like its handler converts
+/// it: its content is separated from the surrounding blocks by blank lines.
+fn stream_element(
+    node: &Rc<Node>,
+    output: &mut String,
+    handlers: &ConversionContext,
+    flush: &mut dyn FnMut(&mut String),
+) {
+    let NodeData::Element { name, .. } = &node.data else {
+        return;
+    };
+    let tag = &*name.local;
+    if !handlers.enter_element(tag) {
+        return;
+    }
+    append_normalized_content(output, "\n\n".to_string(), false);
+    stream_children(node, output, handlers, flush);
+    handlers.leave_element(tag);
+    output.truncate(output.trim_end_matches('\n').len());
+    append_normalized_content(output, "\n\n".to_string(), false);
+}
+
+/// Whether `node` is the `<html>` of the document or its `<body>`, converted
+/// by the built-in handlers.
+fn is_streamed_element(node: &Rc<Node>, handlers: &ConversionContext) -> bool {
+    let NodeData::Element {
+        ref name,
+        ref attrs,
+        ..
+    } = node.data
+    else {
+        return false;
+    };
+    let tag = &*name.local;
+    let parent = get_parent_node(node);
+    let is_root = match tag {
+        "html" => parent.is_some_and(|parent| matches!(parent.data, NodeData::Document)),
+        "body" => parent.is_some_and(|parent| get_node_tag_name(&parent) == Some("html")),
+        _ => false,
+    };
+    let is_hidden = handlers.options().skip_hidden_content
+        && is_hidden(tag, &attrs.borrow(), handlers.handlers.scripting_enabled);
+    is_root
+        && !is_hidden
+        && !handlers.handlers.has_custom_handler(tag)
+        && !handlers.is_excluded(node)
+}
+
 pub(crate) fn walk_children(
     node: &Rc<Node>,
     output: &mut String,
-    handlers: &ElementHandlers,
+    handlers: &ConversionContext,
     is_parent_block_element: bool,
     is_pre: bool,
     // Return value: `markdown_translated`.
 ) -> bool {
-    if node.children.borrow().len() > 1 {
-        // Combine similar adjacent blocks.
-        let mut children = node.children.borrow_mut();
-        let mut index = 1;
-        while index < children.len() {
-            if let Some(text) = can_combine(&children[index - 1], &children[index]) {
-                // Combine the text from `chidren[index]` with `children[index -
-                // 1]`, then remove `children[index]`.
-                children.remove(index);
-                index -= 1;
-                let children_of_index = children.get(index).unwrap().children.borrow();
-                let text_data = &children_of_index.first().unwrap().data;
-                let NodeData::Text { contents } = text_data else {
-                    panic!("")
-                };
-                let mut inner_contents = contents.clone().into_inner();
-                inner_contents.push_tendril(&text.take());
-                contents.replace(inner_contents);
-            }
-            index += 1;
-        }
-    }
+    combine_children(node);
 
     // Trim leading spaces of the first element/text in block elements (except pre/code)
     let mut trim_leading_spaces = !is_pre && is_parent_block_element;
@@ -204,6 +443,32 @@ pub(crate) fn walk_children(
     markdown_translated
 }
 
+/// Combine similar adjacent inline children of `node`, e.g. `<b>a</b><b>b</b>`.
+fn combine_children(node: &Rc<Node>) {
+    if node.children.borrow().len() > 1 {
+        // Combine similar adjacent blocks.
+        let mut children = node.children.borrow_mut();
+        let mut index = 1;
+        while index < children.len() {
+            if let Some(text) = can_combine(&children[index - 1], &children[index]) {
+                // Combine the text from `chidren[index]` with `children[index -
+                // 1]`, then remove `children[index]`.
+                children.remove(index);
+                index -= 1;
+                let children_of_index = children.get(index).unwrap().children.borrow();
+                let text_data = &children_of_index.first().unwrap().data;
+                let NodeData::Text { contents } = text_data else {
+                    panic!("")
+                };
+                let mut inner_contents = contents.clone().into_inner();
+                inner_contents.push_tendril(&text.take());
+                contents.replace(inner_contents);
+            }
+            index += 1;
+        }
+    }
+}
+
 // Determine if the two nodes are similar, and should therefore be combined. If
 // so, return the text of the second node to simplify the combining process.
 fn can_combine(n1: &Node, n2: &Node) -> Option<RefCell<Tendril<UTF8>>> {
@@ -269,7 +534,7 @@ fn can_combine(n1: &Node, n2: &Node) -> Option<RefCell<Tendril<UTF8>>> {
 /// Normalizes content before adding to output by:
 /// 1. Collapsing excessive newlines (max 2 consecutive newlines)
 /// 2. Collapsing adjacent spaces between inline elements (when not in pre context)
-fn append_normalized_content(output: &mut String, mut content: String, is_pre: bool) {
+pub(crate) fn append_normalized_content(output: &mut String, mut content: String, is_pre: bool) {
     if output.is_empty() {
         output.push_str(&content);
         return;
@@ -303,7 +568,7 @@ fn trim_output_end(output: &mut String) {
     output.truncate(trimmed_len);
 }
 
-fn trim_output_end_spaces(output: &mut String) {
+pub(crate) fn trim_output_end_spaces(output: &mut String) {
     let trimmed_len = output.trim_end_matches(' ').len();
     output.truncate(trimmed_len);
 }
@@ -319,7 +584,11 @@ fn trim_output_end_spaces(output: &mut String) {
 /// '- Item'   -> '\\- Item'   // unordered list item
 /// '+ Item'   -> '\\+ Item'   // unordered list item
 /// '> Quote'  -> '\\> Quote'  // quote
-fn escape_if_needed(text: Cow<'_, str>) -> Cow<'_, str> {
+///
+/// GFM only:
+/// '~~Text~~' -> '\~\~Text\~\~' // strikethrough
+/// 'a | b'    -> 'a \| b'        // table cell delimiter
+pub(crate) fn escape_if_needed(text: Cow<'_, str>, is_gfm: bool) -> Cow<'_, str> {
     let Some(first) = text.chars().next() else {
         return text;
     };
@@ -327,9 +596,15 @@ fn escape_if_needed(text: Cow<'_, str>) -> Cow<'_, str> {
     let mut need_escape = matches!(first, '=' | '~' | '>' | '-' | '+' | '#' | '0'..='9');
 
     if !need_escape {
-        need_escape = text
-            .chars()
-            .any(|c| c == '\\' || c == '*' || c == '_' || c == '`' || c == '[' || c == ']');
+        need_escape = text.chars().any(|c| {
+            c == '\\'
+                || c == '*'
+                || c == '_'
+                || c == '`'
+                || c == '['
+                || c == ']'
+                || (is_gfm && (c == '~' || c == '|'))
+        });
     }
 
     if !need_escape {
@@ -345,23 +620,25 @@ fn escape_if_needed(text: Cow<'_, str>) -> Cow<'_, str> {
             '`' => escaped.push_str("\\`"),
             '[' => escaped.push_str("\\["),
             ']' => escaped.push_str("\\]"),
+            '~' | '|' if is_gfm => {
+                escaped.push('\\');
+                escaped.push(ch);
+            }
             _ => escaped.push(ch),
         }
     }
 
     match first {
+        // In GFM, a leading `~` has already been escaped above.
+        '~' if is_gfm => {}
         '=' | '~' | '>' => {
             escaped.insert(0, '\\');
         }
-        '-' | '+' => {
-            if escaped.chars().nth(1).is_some_and(|ch| ch == ' ') {
-                escaped.insert(0, '\\');
-            }
+        '-' | '+' if escaped.chars().nth(1).is_some_and(|ch| ch == ' ') => {
+            escaped.insert(0, '\\');
         }
-        '#' => {
-            if is_markdown_atx_heading(&escaped) {
-                escaped.insert(0, '\\');
-            }
+        '#' if is_markdown_atx_heading(&escaped) => {
+            escaped.insert(0, '\\');
         }
         '0'..='9' => {
             if let Some(dot_idx) = index_of_markdown_ordered_item_dot(&escaped) {
@@ -1,41 +1,35 @@
-use std::cell::RefCell;
+use std::collections::HashMap;
 
 use crate::{
     Element, ElementHandler,
-    element_handler::{HandlerResult, Handlers},
-    options::{LinkReferenceStyle, LinkStyle},
+    element_handler::{HandlerResult, Handlers, UrlKind},
+    mdast::{self, Node},
+    options::{LinkReferenceStyle, LinkStyle, MarkdownFlavor, Options},
+    report::{ConversionReport, Link},
     serialize_if_faithful,
     text_util::{StripWhitespace, TrimDocumentWhitespace, concat_strings},
 };
 
 pub(super) struct AnchorElementHandler {}
 
-impl AnchorElementHandler {
-    thread_local! {
-        static LINKS: RefCell<Vec<String>> = const { RefCell::new(vec![]) };
-    }
+/// The link reference definitions of the current conversion.
+#[derive(Default)]
+pub(crate) struct LinkReferences {
+    definitions: Vec<String>,
+    /// The reference number of each `(link, title)`, for the `Full` style.
+    numbers: HashMap<(String, String), usize>,
+    /// The `(link, title)` of each normalized label, for the `Collapsed` and
+    /// `Shortcut` styles.
+    labels: HashMap<String, (String, String)>,
 }
 
 impl ElementHandler for AnchorElementHandler {
-    fn append(&self) -> Option<String> {
-        AnchorElementHandler::LINKS.with(|links| {
-            let mut links = links.borrow_mut();
-            if links.is_empty() {
-                return None;
-            }
-            let content_len: usize = links.iter().map(String::len).sum();
-            let mut result = String::with_capacity(content_len + links.len().saturating_add(1));
-            result.push_str("\n\n");
-            for (index, link) in links.iter().enumerate() {
-                if index > 0 {
-                    result.push('\n');
-                }
-                result.push_str(link);
-            }
-            result.push_str("\n\n");
-            links.clear();
-            Some(result)
-        })
+    fn append_with(&self, handlers: &dyn Handlers) -> Option<String> {
+        handlers
+            .state()
+            .get::<LinkReferences>()
+            .borrow()
+            .definitions()
     }
 
     fn handle(&self, handlers: &dyn Handlers, element: Element) -> Option<HandlerResult> {
@@ -57,27 +51,36 @@ impl ElementHandler for AnchorElementHandler {
             return Some(handlers.walk_children(element.node));
         };
 
-        // Handle new lines in title
-        let title = title.map(|text| process_title(&text));
-
-        let link = escape_link_destination(&link);
+        let link = handlers.resolve_url(element.node, &link);
+        let Some(link) = handlers.rewrite_url(UrlKind::Link, link, &element) else {
+            // Dropped by the URL rewriting callback, keep the content only.
+            return Some(handlers.walk_children(element.node));
+        };
 
         let content = handlers.walk_children(element.node).content;
-        let md = match handlers.options().link_style {
-            LinkStyle::Inlined => {
-                self.build_inlined_anchor(&content, &link, title.as_deref(), false)
-            }
-            LinkStyle::InlinedPreferAutolinks => {
-                self.build_inlined_anchor(&content, &link, title.as_deref(), true)
-            }
-            LinkStyle::Referenced => self.build_referenced_anchor(
-                &content,
-                link,
-                title,
-                &handlers.options().link_reference_style,
-            ),
-        };
+        ConversionReport::record(handlers, |conversion| {
+            conversion.links.push(Link {
+                url: link.clone(),
+                text: content.trim_document_whitespace().to_string(),
+                title: title.clone(),
+            })
+        });
 
+        let links = handlers.state().get::<LinkReferences>();
+        let md = format_link(
+            handlers.options(),
+            &mut links.borrow_mut(),
+            &content,
+            &link,
+            title.as_deref(),
+        );
+        mdast::map_nodes(handlers, |children| {
+            vec![Node::Link {
+                url: link,
+                title,
+                children,
+            }]
+        });
         Some(md.into())
     }
 }
@@ -86,85 +89,174 @@ impl AnchorElementHandler {
     pub(super) fn new() -> Self {
         Self {}
     }
+}
 
-    fn build_inlined_anchor(
-        &self,
+impl LinkReferences {
+    /// The definitions of the referenced links, to append to the document.
+    pub(crate) fn definitions(&self) -> Option<String> {
+        let links = &self.definitions;
+        if links.is_empty() {
+            return None;
+        }
+        let content_len: usize = links.iter().map(String::len).sum();
+        let mut result = String::with_capacity(content_len + links.len().saturating_add(1));
+        result.push_str("\n\n");
+        for (index, link) in links.iter().enumerate() {
+            if index > 0 {
+                result.push('\n');
+            }
+            result.push_str(link);
+        }
+        result.push_str("\n\n");
+        Some(result)
+    }
+
+    /// Format a reference to `link`, adding its definition if needed.
+    fn reference(
+        &mut self,
         content: &str,
-        link: &str,
-        title: Option<&str>,
-        prefer_autolinks: bool,
+        link: String,
+        title: Option<String>,
+        style: LinkReferenceStyle,
     ) -> String {
-        if prefer_autolinks && content == link {
-            let mut result = String::with_capacity(link.len() + 2);
-            result.push('<');
-            result.push_str(link);
-            result.push('>');
-            return result;
+        let title = title
+            .as_deref()
+            .map_or(String::new(), |t| format!(" \"{t}\""));
+        let target = (link, title);
+
+        if style == LinkReferenceStyle::Full {
+            // Reuse the number of an earlier link with the same destination.
+            if let Some(number) = self.numbers.get(&target) {
+                return concat_strings!("[", content, "][", number.to_string(), "]");
+            }
+            let number = self.numbers.len() + 1;
+            let (link, title) = &target;
+            let definition = concat_strings!("[", number.to_string(), "]: ", link, title);
+            self.definitions.push(definition);
+            self.numbers.insert(target, number);
+            return concat_strings!("[", content, "][", number.to_string(), "]");
         }
 
-        let has_spaces_in_link = link.contains(' ');
-        let (content, _) = content.strip_leading_document_whitespace();
-        let (content, trailing_whitespace) = content.strip_trailing_document_whitespace();
-        let title_len = title.map_or(0, |t| t.len() + 3);
-        let trailing_len = trailing_whitespace.map_or(0, str::len);
-        let wrapper_len = if has_spaces_in_link { 2 } else { 0 };
-        let mut result = String::with_capacity(
-            content.len() + link.len() + title_len + trailing_len + wrapper_len + 4,
-        );
-        result.push('[');
-        result.push_str(content);
-        result.push_str("](");
-        if has_spaces_in_link {
-            result.push('<');
+        // Labels match case-insensitively and ignoring whitespace differences,
+        // so a label used for another destination gets a numeric suffix.
+        let mut label = content.to_string();
+        let mut suffix = 1;
+        loop {
+            match self.labels.get(&normalize_label(&label)) {
+                Some(existing) if *existing == target => break,
+                Some(_) => {
+                    suffix += 1;
+                    label = concat_strings!(content, " ", suffix.to_string());
+                }
+                None => {
+                    let (link, title) = &target;
+                    let definition = concat_strings!("[", label, "]: ", link, title);
+                    self.definitions.push(definition);
+                    self.labels.insert(normalize_label(&label), target);
+                    break;
+                }
+            }
         }
-        result.push_str(link);
-        if has_spaces_in_link {
-            result.push('>');
+
+        if suffix > 1 {
+            concat_strings!("[", content, "][", label, "]")
+        } else if style == LinkReferenceStyle::Collapsed {
+            concat_strings!("[", content, "][]")
+        } else {
+            concat_strings!("[", content, "]")
         }
-        if let Some(title) = title {
-            result.push_str(" \"");
-            result.push_str(title);
-            result.push('"');
+    }
+}
+
+/// Format a link to the resolved and rewritten URL `link` in the link style of
+/// `options`. Referenced links are added to `references`.
+pub(crate) fn format_link(
+    options: &Options,
+    references: &mut LinkReferences,
+    content: &str,
+    link: &str,
+    title: Option<&str>,
+) -> String {
+    // Handle new lines in title
+    let title = title.map(process_title);
+    let link = escape_link_destination(link);
+    match options.link_style {
+        LinkStyle::Inlined => {
+            // GFM always prefers autolinks.
+            let prefer_autolinks = options.flavor == MarkdownFlavor::Gfm;
+            build_inlined_anchor(content, &link, title.as_deref(), prefer_autolinks)
+        }
+        LinkStyle::InlinedPreferAutolinks => {
+            build_inlined_anchor(content, &link, title.as_deref(), true)
         }
-        result.push(')');
-        if let Some(trailing_whitespace) = trailing_whitespace {
-            result.push_str(trailing_whitespace);
+        LinkStyle::Referenced => {
+            references.reference(content, link, title, options.link_reference_style)
         }
-        result
     }
+}
 
-    fn build_referenced_anchor(
-        &self,
-        content: &str,
-        link: String,
-        title: Option<String>,
-        style: &LinkReferenceStyle,
-    ) -> String {
-        AnchorElementHandler::LINKS.with(|links| {
-            let title = title
-                .as_deref()
-                .map_or(String::new(), |t| format!(" \"{t}\""));
-            let (current, append) = match style {
-                LinkReferenceStyle::Full => {
-                    let index = links.borrow().len() + 1;
-                    (
-                        concat_strings!("[", content, "][", index.to_string(), "]"),
-                        concat_strings!("[", index.to_string(), "]: ", link, title),
-                    )
-                }
-                LinkReferenceStyle::Collapsed => (
-                    concat_strings!("[", content, "][]"),
-                    concat_strings!("[", content, "]: ", link, title),
-                ),
-                LinkReferenceStyle::Shortcut => (
-                    concat_strings!("[", content, "]"),
-                    concat_strings!("[", content, "]: ", link, title),
-                ),
-            };
-            links.borrow_mut().push(append);
-            current
-        })
+fn build_inlined_anchor(
+    content: &str,
+    link: &str,
+    title: Option<&str>,
+    prefer_autolinks: bool,
+) -> String {
+    if prefer_autolinks && content == link {
+        let mut result = String::with_capacity(link.len() + 2);
+        result.push('<');
+        result.push_str(link);
+        result.push('>');
+        return result;
+    }
+    if prefer_autolinks
+        && !content.is_empty()
+        && link
+            .strip_prefix("mailto:")
+            .is_some_and(|email| email == content)
+    {
+        // Email autolink: <foo@example.com>
+        return concat_strings!("<", content, ">");
+    }
+
+    let has_spaces_in_link = link.contains(' ');
+    let (content, _) = content.strip_leading_document_whitespace();
+    let (content, trailing_whitespace) = content.strip_trailing_document_whitespace();
+    let title_len = title.map_or(0, |t| t.len() + 3);
+    let trailing_len = trailing_whitespace.map_or(0, str::len);
+    let wrapper_len = if has_spaces_in_link { 2 } else { 0 };
+    let mut result = String::with_capacity(
+        content.len() + link.len() + title_len + trailing_len + wrapper_len + 4,
+    );
+    result.push('[');
+    result.push_str(content);
+    result.push_str("](");
+    if has_spaces_in_link {
+        result.push('<');
     }
+    result.push_str(link);
+    if has_spaces_in_link {
+        result.push('>');
+    }
+    if let Some(title) = title {
+        result.push_str(" \"");
+        result.push_str(title);
+        result.push('"');
+    }
+    result.push(')');
+    if let Some(trailing_whitespace) = trailing_whitespace {
+        result.push_str(trailing_whitespace);
+    }
+    result
+}
+
+/// Normalize a link label for matching, see
+/// [CommonMark](https://spec.commonmark.org/0.31.2/#matches).
+fn normalize_label(label: &str) -> String {
+    label
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
 }
 
 fn escape_link_destination(link: &str) -> String {
@@ -1,6 +1,7 @@
 use crate::{
     Element,
     element_handler::{HandlerResult, Handlers},
+    mdast::{self, Node},
     serialize_if_faithful,
     text_util::{JoinOnStringIterator, TrimDocumentWhitespace, concat_strings},
 };
@@ -10,12 +11,17 @@ pub(super) fn blockquote_handler(
     element: Element,
 ) -> Option<HandlerResult> {
     serialize_if_faithful!(handlers, element, 0);
-    let content = handlers.walk_children(element.node).content;
-    let content = content.trim_start_matches('\n');
+    let result = handlers.walk_children(element.node);
+    let content = result.content.trim_start_matches('\n');
     let content = content
         .trim_end_document_whitespace()
         .lines()
         .map(|line| concat_strings!("> ", line))
         .join("\n");
+    mdast::map_nodes(handlers, |children| {
+        vec![Node::Blockquote {
+            children: mdast::flow(children),
+        }]
+    });
     Some(concat_strings!("\n\n", content, "\n\n").into())
 }
@@ -1,6 +1,7 @@
 use crate::{
     Element,
     element_handler::{HandlerResult, Handlers},
+    mdast::{self, Node},
     options::BrStyle,
     serialize_if_faithful,
 };
@@ -8,8 +9,10 @@ use crate::{
 pub(super) fn br_handler(handlers: &dyn Handlers, element: Element) -> Option<HandlerResult> {
     serialize_if_faithful!(handlers, element, 0);
 
-    match handlers.options().br_style {
-        BrStyle::TwoSpaces => Some("  \n".into()),
-        BrStyle::Backslash => Some("\\\n".into()),
-    }
+    let content = match handlers.options().br_style {
+        BrStyle::TwoSpaces => "  \n",
+        BrStyle::Backslash => "\\\n",
+    };
+    mdast::set_nodes(handlers, || vec![Node::Break]);
+    Some(content.into())
 }
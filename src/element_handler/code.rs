@@ -6,8 +6,9 @@ use markup5ever_rcdom::{Node, NodeData};
 use crate::{
     Element,
     element_handler::{HandlerResult, Handlers, serialize_element},
+    mdast,
     node_util::{get_node_tag_name, get_parent_node},
-    options::{CodeBlockFence, CodeBlockStyle, TranslationMode},
+    options::{CodeBlockFence, CodeBlockStyle, Options, TranslationMode},
     serialize_if_faithful,
     text_util::{JoinOnStringIterator, TrimDocumentWhitespace, concat_strings},
 };
@@ -50,11 +51,6 @@ fn handle_code_block(
     let content = handlers.walk_children(element.node).content;
     let content = content.strip_suffix('\n').unwrap_or(&content);
     if handlers.options().code_block_style == CodeBlockStyle::Fenced {
-        let fence = if handlers.options().code_block_fence == CodeBlockFence::Tildes {
-            get_code_fence_marker("~", content)
-        } else {
-            get_code_fence_marker("`", content)
-        };
         let language = find_language_from_attrs(element.attrs).or_else(|| {
             if let NodeData::Element { ref attrs, .. } = parent.data {
                 find_language_from_attrs(&attrs.borrow())
@@ -63,22 +59,52 @@ fn handle_code_block(
             }
         });
         serialize_if_faithful!(handlers, element, if language.is_none() { 0 } else { 1 });
+        let markdown = format_code_block(handlers.options(), content, language.as_deref());
+        set_code_nodes(handlers, content, language);
+        Some(markdown.into())
+    } else {
+        serialize_if_faithful!(handlers, element, 0);
+        let markdown = format_code_block(handlers.options(), content, None);
+        set_code_nodes(handlers, content, None);
+        Some(markdown.into())
+    }
+}
+
+fn set_code_nodes(handlers: &dyn Handlers, content: &str, lang: Option<String>) {
+    mdast::set_nodes(handlers, || {
+        vec![mdast::Node::Code {
+            lang,
+            value: content.to_string(),
+        }]
+    });
+}
+
+/// Format a code block in the code block style of `options`.
+pub(crate) fn format_code_block(
+    options: &Options,
+    content: &str,
+    language: Option<&str>,
+) -> String {
+    if options.code_block_style == CodeBlockStyle::Fenced {
+        let fence = if options.code_block_fence == CodeBlockFence::Tildes {
+            get_code_fence_marker("~", content)
+        } else {
+            get_code_fence_marker("`", content)
+        };
         let mut result = String::from(&fence);
-        if let Some(ref lang) = language {
+        if let Some(lang) = language {
             result.push_str(lang);
         }
         result.push('\n');
         result.push_str(content);
         result.push('\n');
         result.push_str(&fence);
-        Some(result.into())
+        result
     } else {
-        serialize_if_faithful!(handlers, element, 0);
-        let code = content
+        content
             .lines()
             .map(|line| concat_strings!("    ", line))
-            .join("\n");
-        Some(code.into())
+            .join("\n")
     }
 }
 
@@ -96,7 +122,7 @@ fn get_code_fence_marker(symbol: &str, content: &str) -> String {
     }
 }
 
-fn find_language_from_attrs(attrs: &[Attribute]) -> Option<String> {
+pub(crate) fn find_language_from_attrs(attrs: &[Attribute]) -> Option<String> {
     attrs
         .iter()
         .find(|attr| &attr.name.local == "class")
@@ -111,13 +137,23 @@ fn find_language_from_attrs(attrs: &[Attribute]) -> Option<String> {
 
 fn handle_inline_code(handlers: &dyn Handlers, element: Element) -> Option<HandlerResult> {
     serialize_if_faithful!(handlers, element, 0);
+    let content = handlers.walk_children(element.node).content;
+    let markdown = format_inline_code(handlers.options(), &content);
+    mdast::set_nodes(handlers, || {
+        vec![mdast::Node::InlineCode { value: content }]
+    });
+    Some(markdown.into())
+}
+
+/// Format inline code, with the whitespace preserved if
+/// [`Options::preformatted_code`] is set.
+pub(crate) fn format_inline_code(options: &Options, content: &str) -> String {
     // Case: <code>There is a literal backtick (`) here</code>
     //   to: ``There is a literal backtick (`) here``
     let mut use_double_backticks = false;
     // Case: <code>`starting with a backtick</code>
     //   to: `` `starting with a backtick ``
     let mut surround_with_spaces = false;
-    let content = handlers.walk_children(element.node).content;
     let chars = content.chars().collect::<Vec<char>>();
     let len = chars.len();
     for (idx, c) in chars.iter().enumerate() {
@@ -131,19 +167,19 @@ fn handle_inline_code(handlers: &dyn Handlers, element: Element) -> Option<Handl
             }
         }
     }
-    let content = if handlers.options().preformatted_code {
-        handle_preformatted_code(&content)
+    let content = if options.preformatted_code {
+        handle_preformatted_code(content)
     } else {
         content.trim_document_whitespace().to_string()
     };
     if use_double_backticks {
         if surround_with_spaces {
-            Some(concat_strings!("`` ", content, " ``").into())
+            concat_strings!("`` ", content, " ``")
         } else {
-            Some(concat_strings!("``", content, "``").into())
+            concat_strings!("``", content, "``")
         }
     } else {
-        Some(concat_strings!("`", content, "`").into())
+        concat_strings!("`", content, "`")
     }
 }
 
@@ -2,6 +2,7 @@ use crate::{
     Element,
     dom_walker::is_block_element,
     element_handler::{HandlerResult, Handlers},
+    error::{ConversionError, Error},
     node_util::parent_tag_name_equals,
     options::TranslationMode,
     text_util::concat_strings,
@@ -172,7 +173,11 @@ pub(crate) fn serialize_element(handlers: &dyn Handlers, element: &Element) -> S
     };
     match f() {
         Ok(s) => s,
-        Err(err) => err.to_string(),
+        Err(source) => {
+            let tag = element.tag.to_string();
+            ConversionError::record(handlers, Error::Serialize { tag, source });
+            String::new()
+        }
     }
 }
 
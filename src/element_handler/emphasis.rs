@@ -1,6 +1,7 @@
 use crate::{
     Element,
     element_handler::{HandlerResult, Handlers},
+    mdast::{self, Node},
     serialize_if_faithful,
     text_util::{StripWhitespace, concat_strings},
 };
@@ -29,5 +30,12 @@ pub(super) fn emphasis_handler(
         marker,
         trailing_whitespace.unwrap_or("")
     );
+    mdast::map_nodes(handlers, |children| {
+        vec![match marker {
+            "*" => Node::Emphasis { children },
+            "**" => Node::Strong { children },
+            _ => Node::Delete { children },
+        }]
+    });
     Some(content.into())
 }
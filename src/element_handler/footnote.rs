@@ -0,0 +1,224 @@
+use std::rc::Rc;
+
+use markup5ever_rcdom::{Node, NodeData};
+
+use crate::{
+    Element, ElementHandler,
+    element_handler::{HandlerResult, Handlers},
+    mdast::{self, TreeState},
+    node_util::{get_node_attr, get_node_tag_name, node_has_class},
+    options::FootnoteStyle,
+    text_util::{TrimDocumentWhitespace, concat_strings, indent_text_except_first_line},
+};
+
+/// Converts footnotes generated by Pandoc, Wikipedia and DPUB-ARIA markup to
+/// Markdown footnotes when [`FootnoteStyle::Markdown`] is enabled:
+///
+/// - References: `a.footnote-ref`, `a[role=doc-noteref]`, `sup.reference`
+/// - Definitions: `section.footnotes`, `[role=doc-endnotes]`,
+///   `aside[role=doc-footnote]`, `ol.references`
+/// - Back links, which are dropped: `a.footnote-back`, `a[role=doc-backlink]`,
+///   `span.mw-cite-backlink`
+pub(super) struct FootnoteElementHandler {}
+
+/// The footnotes of the current conversion.
+#[derive(Default)]
+struct Footnotes {
+    /// The ids of the referenced notes, the index + 1 is the footnote number.
+    referenced: Vec<String>,
+    /// The converted content of each note and its syntax tree, by id.
+    definitions: Vec<(String, String, Option<Vec<mdast::Node>>)>,
+}
+
+impl Footnotes {
+    fn number(&mut self, id: &str) -> usize {
+        match self.referenced.iter().position(|it| it == id) {
+            Some(index) => index + 1,
+            None => {
+                self.referenced.push(id.to_string());
+                self.referenced.len()
+            }
+        }
+    }
+}
+
+impl ElementHandler for FootnoteElementHandler {
+    fn append_with(&self, handlers: &dyn Handlers) -> Option<String> {
+        let footnotes = handlers.state().get::<Footnotes>();
+        let mut footnotes = footnotes.borrow_mut();
+        if footnotes.definitions.is_empty() {
+            return None;
+        }
+        // Number the notes which are never referenced after the others.
+        let definitions = std::mem::take(&mut footnotes.definitions);
+        let mut numbered: Vec<(usize, String, Option<Vec<mdast::Node>>)> = definitions
+            .into_iter()
+            .map(|(id, content, nodes)| (footnotes.number(&id), content, nodes))
+            .collect();
+        numbered.sort_by_key(|(number, ..)| *number);
+
+        let mut result = String::from("\n\n");
+        for (index, (number, content, _)) in numbered.iter().enumerate() {
+            if index > 0 {
+                result.push('\n');
+            }
+            let content = indent_text_except_first_line(content, 4, true);
+            result.push_str(&concat_strings!("[^", number.to_string(), "]: ", content));
+        }
+        result.push_str("\n\n");
+        if mdast::is_building(handlers) {
+            let tree = handlers.state().get::<TreeState>();
+            tree.borrow_mut()
+                .footnotes
+                .extend(numbered.into_iter().map(|(number, _, nodes)| {
+                    mdast::Node::FootnoteDefinition {
+                        identifier: number.to_string(),
+                        children: mdast::flow(nodes.unwrap_or_default()),
+                    }
+                }));
+        }
+        Some(result)
+    }
+
+    fn handle(&self, handlers: &dyn Handlers, element: Element) -> Option<HandlerResult> {
+        if handlers.options().footnote_style != FootnoteStyle::Markdown {
+            return handlers.fallback(element);
+        }
+        let node = element.node;
+        match element.tag {
+            "a" if is_backlink(node) => Some("".into()),
+            "a" if is_noteref(node) => self.handle_reference(handlers, node),
+            "span" if node_has_class(node, "mw-cite-backlink") => Some("".into()),
+            "sup" => {
+                let link = if node_has_class(node, "reference") {
+                    find_descendant(node, &|it| {
+                        get_node_tag_name(it) == Some("a") && note_id(it).is_some()
+                    })
+                } else {
+                    single_child_element(node).filter(is_noteref)
+                };
+                match link {
+                    Some(link) => self.handle_reference(handlers, &link),
+                    None => handlers.fallback(element),
+                }
+            }
+            "aside" if has_role(node, "doc-footnote") => {
+                let Some(id) = get_node_attr(node, "id") else {
+                    return handlers.fallback(element);
+                };
+                self.add_definition(handlers, id, node);
+                Some("".into())
+            }
+            _ if is_endnotes(node) => {
+                let mut notes = vec![];
+                collect_notes(node, &mut notes);
+                if notes.is_empty() {
+                    return handlers.fallback(element);
+                }
+                for (id, note) in notes {
+                    self.add_definition(handlers, id, &note);
+                }
+                Some("".into())
+            }
+            _ => handlers.fallback(element),
+        }
+    }
+}
+
+impl FootnoteElementHandler {
+    pub(super) fn new() -> Self {
+        Self {}
+    }
+
+    fn handle_reference(&self, handlers: &dyn Handlers, link: &Rc<Node>) -> Option<HandlerResult> {
+        let id = note_id(link)?;
+        let footnotes = handlers.state().get::<Footnotes>();
+        let number = footnotes.borrow_mut().number(&id).to_string();
+        let content = concat_strings!("[^", number, "]");
+        mdast::set_nodes(handlers, || {
+            vec![mdast::Node::FootnoteReference { identifier: number }]
+        });
+        Some(content.into())
+    }
+
+    fn add_definition(&self, handlers: &dyn Handlers, id: String, note: &Rc<Node>) {
+        let content = handlers.walk_children(note).content;
+        let content = content.trim_document_whitespace().to_string();
+        let nodes = mdast::take_nodes(handlers);
+        let footnotes = handlers.state().get::<Footnotes>();
+        footnotes
+            .borrow_mut()
+            .definitions
+            .push((id, content, nodes));
+    }
+}
+
+/// The id of the note a reference links to, e.g. `fn1` for `href="#fn1"`.
+fn note_id(link: &Rc<Node>) -> Option<String> {
+    let href = get_node_attr(link, "href")?;
+    let id = href.strip_prefix('#')?;
+    (!id.is_empty()).then(|| id.to_string())
+}
+
+fn has_role(node: &Rc<Node>, role: &str) -> bool {
+    get_node_attr(node, "role").is_some_and(|it| it.split_ascii_whitespace().any(|it| it == role))
+}
+
+fn is_noteref(node: &Rc<Node>) -> bool {
+    get_node_tag_name(node) == Some("a")
+        && (node_has_class(node, "footnote-ref") || has_role(node, "doc-noteref"))
+        && note_id(node).is_some()
+}
+
+fn is_backlink(node: &Rc<Node>) -> bool {
+    node_has_class(node, "footnote-back") || has_role(node, "doc-backlink")
+}
+
+fn is_endnotes(node: &Rc<Node>) -> bool {
+    match get_node_tag_name(node) {
+        Some("section" | "div") => {
+            node_has_class(node, "footnotes") || has_role(node, "doc-endnotes")
+        }
+        Some("ol") => node_has_class(node, "references") || has_role(node, "doc-endnotes"),
+        _ => false,
+    }
+}
+
+/// Collect the `<li id="...">` notes of an endnotes container.
+fn collect_notes(node: &Rc<Node>, notes: &mut Vec<(String, Rc<Node>)>) {
+    for child in node.children.borrow().iter() {
+        if get_node_tag_name(child) == Some("li") {
+            if let Some(id) = get_node_attr(child, "id") {
+                notes.push((id, child.clone()));
+            }
+        } else {
+            collect_notes(child, notes);
+        }
+    }
+}
+
+fn find_descendant(node: &Rc<Node>, predicate: &dyn Fn(&Rc<Node>) -> bool) -> Option<Rc<Node>> {
+    node.children.borrow().iter().find_map(|child| {
+        if predicate(child) {
+            Some(child.clone())
+        } else {
+            find_descendant(child, predicate)
+        }
+    })
+}
+
+/// The only child element of node, if there is no other non-whitespace
+/// content.
+fn single_child_element(node: &Rc<Node>) -> Option<Rc<Node>> {
+    let children = node.children.borrow();
+    let mut content = children.iter().filter(|child| match &child.data {
+        NodeData::Text { contents } => !contents.borrow().trim_document_whitespace().is_empty(),
+        NodeData::Comment { .. } => false,
+        _ => true,
+    });
+    let child = content.next()?;
+    if content.next().is_some() || !matches!(child.data, NodeData::Element { .. }) {
+        return None;
+    }
+    Some(child.clone())
+}
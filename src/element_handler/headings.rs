@@ -1,7 +1,9 @@
 use crate::{
     Element,
     element_handler::{HandlerResult, Handlers},
+    mdast::{self, Node},
     options::HeadingStyle,
+    report::{ConversionReport, Heading},
     serialize_if_faithful,
     text_util::TrimDocumentWhitespace,
 };
@@ -9,9 +11,15 @@ use crate::{
 pub(super) fn headings_handler(handlers: &dyn Handlers, element: Element) -> Option<HandlerResult> {
     serialize_if_faithful!(handlers, element, 0);
     let level = element.tag.chars().nth(1).unwrap() as u32 - '0' as u32;
-    let content = handlers.walk_children(element.node).content;
-    let content = content.trim_document_whitespace();
+    let children = handlers.walk_children(element.node);
+    let content = children.content.trim_document_whitespace();
     let content = content.trim_matches('\n');
+    ConversionReport::record(handlers, |conversion| {
+        conversion.headings.push(Heading {
+            level: level as u8,
+            text: content.to_string(),
+        })
+    });
 
     let mut result = String::from("\n\n");
     if (level == 1 || level == 2) && handlers.options().heading_style == HeadingStyle::Setex {
@@ -27,5 +35,11 @@ pub(super) fn headings_handler(handlers: &dyn Handlers, element: Element) -> Opt
         result.push_str(content);
         result.push_str("\n\n");
     }
+    mdast::map_nodes(handlers, |children| {
+        vec![Node::Heading {
+            depth: level as u8,
+            children,
+        }]
+    });
     Some(result.into())
 }
@@ -1,15 +1,18 @@
 use crate::{
     Element,
     element_handler::{HandlerResult, Handlers},
+    mdast::{self, Node},
     options::HrStyle,
     serialize_if_faithful,
 };
 
 pub(super) fn hr_handler(handlers: &dyn Handlers, element: Element) -> Option<HandlerResult> {
     serialize_if_faithful!(handlers, element, 0);
-    match handlers.options().hr_style {
-        HrStyle::Dashes => Some("\n\n- - -\n\n".into()),
-        HrStyle::Asterisks => Some("\n\n* * *\n\n".into()),
-        HrStyle::Underscores => Some("\n\n_ _ _\n\n".into()),
-    }
+    let content = match handlers.options().hr_style {
+        HrStyle::Dashes => "\n\n- - -\n\n",
+        HrStyle::Asterisks => "\n\n* * *\n\n",
+        HrStyle::Underscores => "\n\n_ _ _\n\n",
+    };
+    mdast::set_nodes(handlers, || vec![Node::ThematicBreak]);
+    Some(content.into())
 }
@@ -1,6 +1,8 @@
 use crate::{
     Element,
-    element_handler::{HandlerResult, Handlers},
+    element_handler::{HandlerResult, Handlers, UrlKind},
+    mdast::{self, Node},
+    report::{ConversionReport, Image},
     serialize_if_faithful,
     text_util::{JoinOnStringIterator, TrimDocumentWhitespace, concat_strings},
 };
@@ -24,8 +26,28 @@ pub(super) fn img_handler(handlers: &dyn Handlers, element: Element) -> Option<H
         }
     }
 
-    link.as_ref()?;
+    let link = handlers.resolve_url(element.node, &link?);
+    let link = handlers.rewrite_url(UrlKind::Image, link, &element)?;
+    ConversionReport::record(handlers, |conversion| {
+        conversion.images.push(Image {
+            url: link.clone(),
+            alt: alt.clone(),
+            title: title.clone(),
+        })
+    });
 
+    mdast::set_nodes(handlers, || {
+        vec![Node::Image {
+            url: link.clone(),
+            title: title.clone(),
+            alt: alt.clone(),
+        }]
+    });
+    Some(format_image(&link, alt, title).into())
+}
+
+/// Format an image with the resolved and rewritten URL `link`.
+pub(crate) fn format_image(link: &str, alt: Option<String>, title: Option<String>) -> String {
     let process_alt_title = |text: String| {
         text.lines()
             .map(|line| line.trim_document_whitespace().replace('"', "\\\""))
@@ -39,21 +61,20 @@ pub(super) fn img_handler(handlers: &dyn Handlers, element: Element) -> Option<H
     // Handle new lines in title
     let title = title.map(process_alt_title);
 
-    let link = link.map(|text| text.replace('(', "\\(").replace(')', "\\)"));
+    let link = link.replace('(', "\\(").replace(')', "\\)");
 
-    let has_spaces_in_link = link.as_ref().is_some_and(|link| link.contains(' '));
+    let has_spaces_in_link = link.contains(' ');
 
-    let md = concat_strings!(
+    concat_strings!(
         "![",
         alt.as_ref().unwrap_or(&String::new()),
         "](",
         if has_spaces_in_link { "<" } else { "" },
-        link.as_ref().unwrap_or(&String::new()),
+        link,
         title
             .as_ref()
             .map_or(String::new(), |t| concat_strings!(" \"", t, "\"")),
         if has_spaces_in_link { ">" } else { "" },
         ")"
-    );
-    Some(md.into())
+    )
 }
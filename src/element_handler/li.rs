@@ -1,8 +1,13 @@
+use std::rc::Rc;
+
+use markup5ever_rcdom::{Node, NodeData};
+
 use crate::{
     Element,
     element_handler::{HandlerResult, Handlers},
+    mdast,
     node_util::{get_node_tag_name, get_parent_node},
-    options::BulletListMarker,
+    options::{BulletListMarker, MarkdownFlavor, TranslationMode},
     serialize_if_faithful,
     text_util::{TrimDocumentWhitespace, concat_strings, indent_text_except_first_line},
 };
@@ -12,11 +17,29 @@ pub(super) fn list_item_handler(
     element: Element,
 ) -> Option<HandlerResult> {
     serialize_if_faithful!(handlers, element, 0);
-    let content = handlers
-        .walk_children(element.node)
-        .content
-        .trim_start_document_whitespace()
-        .to_string();
+    let result = handlers.walk_children(element.node);
+    let content = result.content.trim_start_document_whitespace().to_string();
+
+    // GFM task list item: `* [x] Done`
+    let checked = task_list_checkbox(handlers, element.node).map(|it| is_checked(&it));
+    mdast::map_nodes(handlers, |children| {
+        vec![mdast::Node::ListItem {
+            checked,
+            spread: false,
+            children: mdast::flow(children),
+        }]
+    });
+    let content = match checked {
+        Some(checked) => {
+            let marker = if checked { "[x]" } else { "[ ]" };
+            if content.is_empty() {
+                marker.to_string()
+            } else {
+                concat_strings!(marker, " ", content)
+            }
+        }
+        None => content,
+    };
 
     let ul_li = || {
         let marker = if handlers.options().bullet_list_marker == BulletListMarker::Asterisk {
@@ -27,20 +50,114 @@ pub(super) fn list_item_handler(
         let spacing = " ".repeat(handlers.options().ul_bullet_spacing.into());
         let content = indent_text_except_first_line(&content, marker.len() + spacing.len(), true);
 
-        Some(concat_strings!("\n", marker, spacing, content).into())
+        concat_strings!("\n", marker, spacing, content)
     };
 
     let ol_li = || {
         // Marker will be added in the ol handler
-        Some(concat_strings!("\n", content, "\n").into())
+        concat_strings!("\n", content, "\n")
     };
 
-    if let Some(parent) = get_parent_node(element.node)
-        && let Some(parent_tag_name) = get_node_tag_name(&parent)
-        && parent_tag_name == "ol"
-    {
+    let content = if is_ordered_item(element.node) {
         ol_li()
     } else {
         ul_li()
+    };
+    Some(content.into())
+}
+
+/// Whether `li` is an item of an `<ol>`, whose marker is added by the list.
+pub(crate) fn is_ordered_item(li: &Rc<Node>) -> bool {
+    get_parent_node(li).is_some_and(|parent| get_node_tag_name(&parent) == Some("ol"))
+}
+
+/// Handles `<input>` elements. Checkboxes of GFM task list items are rendered
+/// by [list_item_handler], so they produce no content here.
+pub(super) fn checkbox_handler(handlers: &dyn Handlers, element: Element) -> Option<HandlerResult> {
+    let Some(li) =
+        get_parent_node(element.node).and_then(|parent| match get_node_tag_name(&parent) {
+            Some("li") => Some(parent),
+            Some("p") => get_parent_node(&parent)
+                .filter(|li| get_node_tag_name(li) == Some("li") && is_first_child(li, &parent)),
+            _ => None,
+        })
+    else {
+        return handlers.fallback(element);
+    };
+
+    if !task_list_checkbox(handlers, &li).is_some_and(|it| Rc::ptr_eq(&it, element.node)) {
+        return handlers.fallback(element);
+    }
+
+    Some("".into())
+}
+
+/// Find the checkbox that turns `li` into a GFM task list item. It must be the
+/// first child of `li`, or the first child of a leading `<p>` in `li`.
+fn task_list_checkbox(handlers: &dyn Handlers, li: &Rc<Node>) -> Option<Rc<Node>> {
+    if handlers.options().flavor != MarkdownFlavor::Gfm {
+        return None;
     }
+    let first = first_child_element(li)?;
+    let candidate = if get_node_tag_name(&first) == Some("p") {
+        first_child_element(&first)?
+    } else {
+        first
+    };
+    if !is_checkbox(&candidate) {
+        return None;
+    }
+    // `type`, `checked` and `disabled` can be represented by the task list
+    // item, everything else needs HTML.
+    if handlers.options().translation_mode == TranslationMode::Faithful
+        && let NodeData::Element { attrs, .. } = &candidate.data
+        && attrs
+            .borrow()
+            .iter()
+            .any(|attr| !matches!(&*attr.name.local, "type" | "checked" | "disabled"))
+    {
+        return None;
+    }
+    Some(candidate)
+}
+
+/// Get the first child, ignoring whitespace-only text nodes. Returns `None` if
+/// the first child is not an element.
+fn first_child_element(node: &Rc<Node>) -> Option<Rc<Node>> {
+    let children = node.children.borrow();
+    let child = children.iter().find(|child| match &child.data {
+        NodeData::Text { contents } => !contents.borrow().trim_document_whitespace().is_empty(),
+        NodeData::Comment { .. } => false,
+        _ => true,
+    })?;
+    if matches!(child.data, NodeData::Element { .. }) {
+        Some(child.clone())
+    } else {
+        None
+    }
+}
+
+fn is_first_child(parent: &Rc<Node>, child: &Rc<Node>) -> bool {
+    first_child_element(parent).is_some_and(|it| Rc::ptr_eq(&it, child))
+}
+
+fn is_checkbox(node: &Rc<Node>) -> bool {
+    let NodeData::Element { name, attrs, .. } = &node.data else {
+        return false;
+    };
+    &name.local == "input"
+        && attrs
+            .borrow()
+            .iter()
+            .any(|attr| &attr.name.local == "type" && attr.value.eq_ignore_ascii_case("checkbox"))
+}
+
+fn is_checked(node: &Rc<Node>) -> bool {
+    let NodeData::Element { attrs, .. } = &node.data else {
+        return false;
+    };
+    attrs
+        .borrow()
+        .iter()
+        .any(|attr| &attr.name.local == "checked")
 }
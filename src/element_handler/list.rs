@@ -3,6 +3,7 @@ use markup5ever_rcdom::NodeData;
 use crate::{
     Element,
     element_handler::{HandlerResult, Handlers, serialize_element},
+    mdast::{self, Node},
     node_util::{get_node_tag_name, get_parent_node},
     options::{Options, TranslationMode},
     serialize_if_faithful,
@@ -40,12 +41,10 @@ pub(super) fn list_handler(handlers: &dyn Handlers, element: Element) -> Option<
         .map(|p| get_node_tag_name(&p).is_some_and(|tag| tag == "li"))
         .unwrap_or(false);
 
-    let result = if element.tag == "ol" {
-        let (content, translated) = get_ol_content(handlers, &element);
-        HandlerResult {
-            content,
-            markdown_translated: translated,
-        }
+    let is_ordered = element.tag == "ol";
+    let start_idx = start_index(&element);
+    let result = if is_ordered {
+        get_ol_content(handlers, &element, start_idx)
     } else {
         handlers.walk_children(element.node)
     };
@@ -64,11 +63,34 @@ pub(super) fn list_handler(handlers: &dyn Handlers, element: Element) -> Option<
         return None;
     }
 
-    if is_parent_li {
-        Some(concat_strings!("\n", trimmed, "\n").into())
+    let content = if is_parent_li {
+        concat_strings!("\n", trimmed, "\n")
     } else {
-        Some(concat_strings!("\n\n", trimmed, "\n\n").into())
-    }
+        concat_strings!("\n\n", trimmed, "\n\n")
+    };
+    mdast::map_nodes(handlers, |children| {
+        let items = children
+            .iter()
+            .filter(|child| matches!(child, Node::ListItem { .. }))
+            .count();
+        vec![Node::List {
+            ordered: is_ordered,
+            start: is_ordered.then_some(start_idx as u32),
+            spread: items > 1,
+            children,
+        }]
+    });
+    Some(content.into())
+}
+
+/// The number of the first item of an `<ol>`.
+fn start_index(element: &Element) -> usize {
+    element
+        .attrs
+        .iter()
+        .find(|attr| &attr.name.local == "start")
+        .map(|attr| attr.value.to_string().parse::<i32>().unwrap_or(1).max(1) as usize)
+        .unwrap_or(1)
 }
 
 struct ListChildContent {
@@ -76,17 +98,11 @@ struct ListChildContent {
     is_li: bool,
 }
 
-fn get_ol_content(handlers: &dyn Handlers, element: &Element) -> (String, bool) {
+fn get_ol_content(handlers: &dyn Handlers, element: &Element, start_idx: usize) -> HandlerResult {
     let mut buffer: Vec<ListChildContent> = Vec::new();
     let mut li_count = 0;
     let mut all_translated = true;
-
-    let start_idx = element
-        .attrs
-        .iter()
-        .find(|attr| &attr.name.local == "start")
-        .map(|attr| attr.value.to_string().parse::<i32>().unwrap_or(1).max(1) as usize)
-        .unwrap_or(1);
+    let mut nodes = Some(vec![]);
 
     for child in element.node.children.borrow().iter() {
         let Some(res) = handlers.handle(child) else {
@@ -95,6 +111,12 @@ fn get_ol_content(handlers: &dyn Handlers, element: &Element) -> (String, bool)
         if !res.markdown_translated {
             all_translated = false;
         }
+        nodes = nodes
+            .zip(mdast::take_nodes(handlers))
+            .map(|(mut nodes, child_nodes)| {
+                nodes.extend(child_nodes);
+                nodes
+            });
 
         if let NodeData::Element { ref name, .. } = child.data
             && &name.local == "li"
@@ -134,7 +156,15 @@ fn get_ol_content(handlers: &dyn Handlers, element: &Element) -> (String, bool)
         })
         .collect::<Vec<String>>();
 
-    (join_blocks(&contents), all_translated)
+    // The nodes of the items are passed along like the nodes of walked
+    // children.
+    if let Some(nodes) = nodes {
+        mdast::set_nodes(handlers, || nodes);
+    }
+    HandlerResult {
+        content: join_blocks(&contents),
+        markdown_translated: all_translated,
+    }
 }
 
 // Add 1 before computing log10, then take the ceiling: it avoids log10(0) =
@@ -146,7 +176,7 @@ fn digits(num: usize) -> usize {
     ((num + 1) as f32).log10().ceil() as usize
 }
 
-fn add_ol_li_marker(
+pub(crate) fn add_ol_li_marker(
     options: &Options,
     content: &str,
     index: usize,
@@ -1,31 +1,41 @@
-mod anchor;
+pub(crate) mod anchor;
 mod blockquote;
 mod br;
 mod caption;
-mod code;
+pub(crate) mod code;
 mod element_util;
 mod emphasis;
+mod footnote;
 mod head_body;
 mod headings;
 mod hr;
 mod html;
-mod img;
-mod li;
-mod list;
+pub(crate) mod img;
+pub(crate) mod li;
+pub(crate) mod list;
 mod p;
 mod pre;
 mod span;
-mod table;
+mod state;
+pub(crate) mod table;
 mod tbody;
 mod td_th;
 mod thead;
 mod tr;
 
 use crate::{
+    Limit, Limits,
     dom_walker::walk_node,
     element_handler::element_util::serialize_element,
-    options::{Options, TranslationMode},
-    text_util::concat_strings,
+    error::{ConversionError, Error},
+    front_matter::FrontMatterFieldMapper,
+    mdast::TreeState,
+    node_util::find_document_head,
+    options::{MarkdownFlavor, Options, TranslationMode},
+    selector::SelectorList,
+    source_map::SourceMapState,
+    text_util::{concat_strings, join_blocks},
+    url_util::resolve_url,
 };
 
 use super::Element;
@@ -35,19 +45,25 @@ use br::br_handler;
 use caption::caption_handler;
 use code::code_handler;
 use emphasis::emphasis_handler;
+use footnote::FootnoteElementHandler;
 use head_body::head_body_handler;
 use headings::headings_handler;
 use hr::hr_handler;
 use html::html_handler;
 use html5ever::Attribute;
 use img::img_handler;
-use li::list_item_handler;
+use li::{checkbox_handler, list_item_handler};
 use list::list_handler;
-use markup5ever_rcdom::Node;
+use markup5ever_rcdom::{Node, NodeData};
 use p::p_handler;
 use pre::pre_handler;
 use span::span_handler;
-use std::{collections::HashMap, rc::Rc};
+pub use state::ConversionState;
+use std::{
+    cell::Cell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
 use table::table_handler;
 use tbody::tbody_handler;
 use td_th::td_th_handler;
@@ -80,6 +96,18 @@ impl From<&str> for HandlerResult {
     }
 }
 
+/// The kind of URL passed to the URL rewriting callback, see
+/// [`HtmlToMarkdownBuilder::rewrite_url`](crate::HtmlToMarkdownBuilder::rewrite_url).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum UrlKind {
+    /// The `href` of an `<a>` element.
+    Link,
+    /// The `src` of an `<img>` element.
+    Image,
+}
+
+pub(crate) type UrlRewriter = Box<dyn Fn(UrlKind, &str, &Element) -> Option<String> + Send + Sync>;
+
 /// Trait for handling the conversion of a specific HTML element to Markdown.
 pub trait ElementHandler: Send + Sync {
     /// Append additional content to the end of the converted Markdown.
@@ -87,6 +115,12 @@ pub trait ElementHandler: Send + Sync {
         None
     }
 
+    /// Like [`Self::append`], with access to the state of the conversion
+    /// through `handlers`. Calls [`Self::append`] by default.
+    fn append_with(&self, _handlers: &dyn Handlers) -> Option<String> {
+        self.append()
+    }
+
     /// Handle the conversion of an element.
     fn handle(&self, handlers: &dyn Handlers, element: Element) -> Option<HandlerResult>;
 }
@@ -105,6 +139,16 @@ pub(crate) struct ElementHandlers {
     pub(crate) handlers: Vec<Box<dyn ElementHandler>>,
     pub(crate) tag_to_handler_indices: HashMap<String, Vec<usize>>,
     pub(crate) options: Options,
+    pub(crate) base_url: Option<String>,
+    pub(crate) url_rewriter: Option<UrlRewriter>,
+    pub(crate) limits: Limits,
+    pub(crate) extract_main_content: bool,
+    pub(crate) skip_selectors: Vec<SelectorList>,
+    pub(crate) select_root: Option<SelectorList>,
+    pub(crate) scripting_enabled: bool,
+    pub(crate) front_matter_fields: Option<FrontMatterFieldMapper>,
+    /// The number of handlers registered by [`Self::new`].
+    builtin_handlers: usize,
 }
 
 impl ElementHandlers {
@@ -113,6 +157,15 @@ impl ElementHandlers {
             handlers: Vec::new(),
             tag_to_handler_indices: HashMap::new(),
             options,
+            base_url: None,
+            url_rewriter: None,
+            limits: Limits::default(),
+            extract_main_content: false,
+            skip_selectors: Vec::new(),
+            select_root: None,
+            scripting_enabled: true,
+            front_matter_fields: None,
+            builtin_handlers: 0,
         };
 
         // img
@@ -139,6 +192,12 @@ impl ElementHandlers {
         // italic
         handlers.add_handler(vec!["i", "em"], italic_handler);
 
+        // strikethrough
+        handlers.add_handler(vec!["del", "s", "strike"], strikethrough_handler);
+
+        // task list checkbox
+        handlers.add_handler(vec!["input"], checkbox_handler);
+
         // headings
         handlers.add_handler(vec!["h1", "h2", "h3", "h4", "h5", "h6"], headings_handler);
 
@@ -231,6 +290,13 @@ impl ElementHandlers {
             block_handler,
         );
 
+        // footnotes, falls back to the handlers above for other elements
+        handlers.add_handler(
+            vec!["a", "sup", "span", "section", "div", "aside", "ol"],
+            FootnoteElementHandler::new(),
+        );
+
+        handlers.builtin_handlers = handlers.handlers.len();
         handlers
     }
 
@@ -251,7 +317,125 @@ impl ElementHandlers {
         }
     }
 
-    pub fn handle(
+    /// Whether a handler was added for `tag` with
+    /// [`HtmlToMarkdownBuilder::add_handler`](crate::HtmlToMarkdownBuilder::add_handler)
+    /// or [`HtmlToMarkdownBuilder::skip_tags`](crate::HtmlToMarkdownBuilder::skip_tags).
+    pub(crate) fn has_custom_handler(&self, tag: &str) -> bool {
+        self.tag_to_handler_indices
+            .get(tag)
+            .and_then(|indices| indices.last())
+            .is_some_and(|index| *index >= self.builtin_handlers)
+    }
+
+    fn find_handler(&self, tag: &str, skipped_handlers: usize) -> Option<&dyn ElementHandler> {
+        let handler_indices = self.tag_to_handler_indices.get(tag)?;
+        let idx = handler_indices.iter().rev().nth(skipped_handlers)?;
+        Some(self.handlers[*idx].as_ref())
+    }
+}
+
+/// The handlers and the state of a single conversion.
+pub(crate) struct ConversionContext<'a> {
+    pub(crate) handlers: &'a ElementHandlers,
+    state: ConversionState,
+    /// The number of elements being converted, from the root to the current
+    /// element.
+    depth: Cell<usize>,
+    /// The number of `<pre>` and `<code>` elements being converted.
+    pre_depth: Cell<usize>,
+    /// Set when a limit is exceeded, the rest of the document is skipped.
+    aborted: Cell<bool>,
+    /// Elements skipped when extracting the main content.
+    pub(crate) excluded: HashSet<*const Node>,
+    /// Whether metadata is collected in a
+    /// [`ConversionReport`](crate::report::ConversionReport).
+    pub(crate) reporting: bool,
+    /// Whether the blocks are recorded for a
+    /// [`SourceMap`](crate::source_map::SourceMap).
+    pub(crate) source_mapping: bool,
+    /// Whether the handlers build a Markdown syntax tree, see
+    /// [`TreeState`].
+    pub(crate) mdast: bool,
+}
+
+impl<'a> ConversionContext<'a> {
+    pub(crate) fn new(handlers: &'a ElementHandlers) -> Self {
+        Self {
+            handlers,
+            state: ConversionState::default(),
+            depth: Cell::new(0),
+            pre_depth: Cell::new(0),
+            aborted: Cell::new(false),
+            excluded: HashSet::new(),
+            reporting: false,
+            source_mapping: false,
+            mdast: false,
+        }
+    }
+
+    /// Whether the element is skipped by a skip selector or main content
+    /// extraction.
+    pub(crate) fn is_excluded(&self, node: &Rc<Node>) -> bool {
+        !self.excluded.is_empty() && self.excluded.contains(&Rc::as_ptr(node))
+    }
+
+    /// Enter an element before converting it. Returns false if the element
+    /// must be skipped because the conversion was aborted or it is nested too
+    /// deeply.
+    pub(crate) fn enter_element(&self, tag: &str) -> bool {
+        if self.aborted.get() {
+            return false;
+        }
+        let depth = self.depth.get() + 1;
+        if let Some(max) = self.handlers.limits.max_depth
+            && depth > max
+        {
+            self.abort(Error::LimitExceeded {
+                limit: Limit::Depth,
+                max,
+            });
+            return false;
+        }
+        self.depth.set(depth);
+        if is_pre_tag(tag) {
+            self.pre_depth.set(self.pre_depth.get() + 1);
+        }
+        true
+    }
+
+    /// Leave an element entered with [`Self::enter_element`].
+    pub(crate) fn leave_element(&self, tag: &str) {
+        self.depth.set(self.depth.get() - 1);
+        if is_pre_tag(tag) {
+            self.pre_depth.set(self.pre_depth.get() - 1);
+        }
+    }
+
+    /// Abort the conversion if `output_len` exceeds the output size limit.
+    pub(crate) fn check_output_len(&self, output_len: usize) {
+        if let Some(max) = self.handlers.limits.max_output_bytes
+            && output_len > max
+            && !self.aborted.get()
+        {
+            self.abort(Error::LimitExceeded {
+                limit: Limit::OutputBytes,
+                max,
+            });
+        }
+    }
+
+    pub(crate) fn is_aborted(&self) -> bool {
+        self.aborted.get()
+    }
+
+    /// Skip the rest of the document, and return `error` instead of the
+    /// Markdown.
+    pub(crate) fn abort(&self, error: Error) {
+        self.aborted.set(true);
+        ConversionError::record(self, error);
+    }
+
+    pub(crate) fn handle(
         &self,
         node: &Rc<Node>,
         tag: &str,
@@ -259,7 +443,7 @@ impl ElementHandlers {
         markdown_translated: bool,
         skipped_handlers: usize,
     ) -> Option<HandlerResult> {
-        match self.find_handler(tag, skipped_handlers) {
+        match self.handlers.find_handler(tag, skipped_handlers) {
             Some(handler) => handler.handle(
                 self,
                 Element {
@@ -271,7 +455,7 @@ impl ElementHandlers {
                 },
             ),
             None => {
-                if self.options.translation_mode == TranslationMode::Faithful {
+                if self.handlers.options.translation_mode == TranslationMode::Faithful {
                     Some(HandlerResult {
                         content: serialize_element(
                             self,
@@ -293,10 +477,16 @@ impl ElementHandlers {
         }
     }
 
-    fn find_handler(&self, tag: &str, skipped_handlers: usize) -> Option<&dyn ElementHandler> {
-        let handler_indices = self.tag_to_handler_indices.get(tag)?;
-        let idx = handler_indices.iter().rev().nth(skipped_handlers)?;
-        Some(self.handlers[*idx].as_ref())
+    /// Collect the content appended by the handlers at the end of the
+    /// converted Markdown.
+    pub(crate) fn append(&self) -> String {
+        let contents: Vec<String> = self
+            .handlers
+            .handlers
+            .iter()
+            .filter_map(|handler| handler.append_with(self))
+            .collect();
+        join_blocks(&contents)
     }
 }
 
@@ -315,9 +505,23 @@ pub trait Handlers {
 
     /// Get the conversion options.
     fn options(&self) -> &Options;
+
+    /// Get the state of the current conversion.
+    fn state(&self) -> &ConversionState;
+
+    /// Resolve a relative URL against the base URL of the document containing
+    /// `node`: the `<base href>` of the document, resolved against the base URL
+    /// of the converter. Returns the URL unchanged if the converter has no
+    /// base URL.
+    fn resolve_url(&self, node: &Rc<Node>, url: &str) -> String;
+
+    /// Pass a resolved URL of `element` through the URL rewriting callback of
+    /// the converter, if any. Returns `None` if the link or image should be
+    /// dropped.
+    fn rewrite_url(&self, kind: UrlKind, url: String, element: &Element) -> Option<String>;
 }
 
-impl Handlers for ElementHandlers {
+impl Handlers for ConversionContext<'_> {
     fn fallback(&self, element: Element) -> Option<HandlerResult> {
         self.handle(
             element.node,
@@ -330,7 +534,11 @@ impl Handlers for ElementHandlers {
 
     fn handle(&self, node: &Rc<Node>) -> Option<HandlerResult> {
         let mut output = String::new();
+        let is_mapped = self.source_mapping && SourceMapState::enter_output(self);
+        TreeState::enter_output(self, false);
         let markdown_translated = walk_node(node, &mut output, self, None, true, false);
+        SourceMapState::leave_output(self, is_mapped, &output);
+        TreeState::leave_output(self, &output);
         Some(HandlerResult {
             content: output,
             markdown_translated,
@@ -341,9 +549,13 @@ impl Handlers for ElementHandlers {
         let mut output = String::new();
         let tag = crate::node_util::get_node_tag_name(node);
         let is_block = tag.is_some_and(crate::dom_walker::is_block_element);
-        let is_pre = tag.is_some_and(|t| t == "pre" || t == "code") || is_inside_pre(node);
+        let is_pre = tag.is_some_and(is_pre_tag) || self.pre_depth.get() > 0;
+        let is_mapped = self.source_mapping && SourceMapState::enter_output(self);
+        TreeState::enter_output(self, tag == Some("li"));
         let markdown_translated =
             crate::dom_walker::walk_children(node, &mut output, self, is_block, is_pre);
+        SourceMapState::leave_output(self, is_mapped, &output);
+        TreeState::leave_output(self, &output);
         HandlerResult {
             content: output,
             markdown_translated,
@@ -351,21 +563,62 @@ impl Handlers for ElementHandlers {
     }
 
     fn options(&self) -> &Options {
-        &self.options
+        &self.handlers.options
     }
-}
 
-fn is_inside_pre(node: &Rc<Node>) -> bool {
-    let mut current = crate::node_util::get_parent_node(node);
-    while let Some(parent) = current {
-        if let Some(tag) = crate::node_util::get_node_tag_name(&parent)
-            && (tag == "pre" || tag == "code")
-        {
-            return true;
+    fn state(&self) -> &ConversionState {
+        &self.state
+    }
+
+    fn resolve_url(&self, node: &Rc<Node>, url: &str) -> String {
+        let Some(base_url) = &self.handlers.base_url else {
+            return url.to_string();
+        };
+        // The base URL of the document is looked up once per conversion.
+        let document_base = self.state.get::<DocumentBaseHref>();
+        let document_base = document_base
+            .borrow_mut()
+            .0
+            .get_or_insert_with(|| find_document_base_href(node))
+            .clone();
+        match document_base {
+            Some(document_base) => resolve_url(&resolve_url(base_url, &document_base), url),
+            None => resolve_url(base_url, url),
+        }
+    }
+
+    fn rewrite_url(&self, kind: UrlKind, url: String, element: &Element) -> Option<String> {
+        match &self.handlers.url_rewriter {
+            Some(rewriter) => rewriter(kind, &url, element),
+            None => Some(url),
         }
-        current = crate::node_util::get_parent_node(&parent);
     }
-    false
+}
+
+#[derive(Default)]
+struct DocumentBaseHref(Option<Option<String>>);
+
+/// Find the `href` of the first `<base>` element in the `<head>` of the
+/// document containing `node`.
+fn find_document_base_href(node: &Rc<Node>) -> Option<String> {
+    let head = find_document_head(node)?;
+    head.children.borrow().iter().find_map(|child| {
+        let NodeData::Element { name, attrs, .. } = &child.data else {
+            return None;
+        };
+        if &name.local != "base" {
+            return None;
+        }
+        attrs
+            .borrow()
+            .iter()
+            .find(|attr| &attr.name.local == "href")
+            .map(|attr| attr.value.to_string())
+    })
+}
+
+fn is_pre_tag(tag: &str) -> bool {
+    tag == "pre" || tag == "code"
 }
 
 fn block_handler(handlers: &dyn Handlers, element: Element) -> Option<HandlerResult> {
@@ -388,3 +641,11 @@ fn bold_handler(handlers: &dyn Handlers, element: Element) -> Option<HandlerResu
 fn italic_handler(handlers: &dyn Handlers, element: Element) -> Option<HandlerResult> {
     emphasis_handler(handlers, element, "*")
 }
+
+fn strikethrough_handler(handlers: &dyn Handlers, element: Element) -> Option<HandlerResult> {
+    if handlers.options().flavor != MarkdownFlavor::Gfm {
+        // Strikethrough is not part of CommonMark, use the default behavior.
+        return handlers.fallback(element);
+    }
+    emphasis_handler(handlers, element, "~~")
+}
@@ -1,7 +1,7 @@
 use crate::{
     Element,
     element_handler::{HandlerResult, Handlers},
-    serialize_if_faithful,
+    mdast, serialize_if_faithful,
     text_util::concat_strings,
 };
 
@@ -9,5 +9,6 @@ pub(super) fn p_handler(handlers: &dyn Handlers, element: Element) -> Option<Han
     serialize_if_faithful!(handlers, element, 0);
     let content = handlers.walk_children(element.node).content;
     let content = content.trim_matches('\n');
+    mdast::map_nodes(handlers, mdast::flow);
     Some(concat_strings!("\n\n", content, "\n\n").into())
 }
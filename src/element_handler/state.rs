@@ -0,0 +1,57 @@
+use std::{
+    any::{Any, TypeId},
+    cell::RefCell,
+    collections::HashMap,
+    rc::Rc,
+};
+
+/// State scoped to a single conversion, available to element handlers through
+/// [`Handlers::state`](super::Handlers::state).
+///
+/// Every type has its own slot, created from [`Default`] on first access, so
+/// handlers can keep per-document state without interfering with each other,
+/// nested conversions or other converters.
+///
+/// # Example
+///
+/// ```
+/// use htmd::{Element, HtmlToMarkdown, element_handler::Handlers};
+///
+/// #[derive(Default)]
+/// struct Counter(usize);
+///
+/// let converter = HtmlToMarkdown::builder()
+///     .add_handler(vec!["video"], |handlers: &dyn Handlers, _: Element| {
+///         let counter = handlers.state().get::<Counter>();
+///         counter.borrow_mut().0 += 1;
+///         Some(format!("[Video {}]", counter.borrow().0).into())
+///     })
+///     .build();
+/// let md = converter.convert("<video></video> <video></video>").unwrap();
+/// assert_eq!("[Video 1] [Video 2]", md);
+/// ```
+#[derive(Default)]
+pub struct ConversionState {
+    values: RefCell<HashMap<TypeId, Rc<dyn Any>>>,
+}
+
+impl ConversionState {
+    /// Get the value of type `T`, inserting `T::default()` if this is the
+    /// first access during the conversion.
+    pub fn get<T: Default + 'static>(&self) -> Rc<RefCell<T>> {
+        let value = self
+            .values
+            .borrow_mut()
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Rc::new(RefCell::new(T::default())))
+            .clone();
+        value
+            .downcast::<RefCell<T>>()
+            .expect("the state is keyed by its type id")
+    }
+
+    /// Whether a value of type `T` was created during the conversion.
+    pub(crate) fn contains<T: 'static>(&self) -> bool {
+        self.values.borrow().contains_key(&TypeId::of::<T>())
+    }
+}
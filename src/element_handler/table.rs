@@ -1,9 +1,10 @@
 use crate::element_handler::element_util::serialize_element;
 use crate::element_handler::{Element, HandlerResult, Handlers};
+use crate::mdast::{self, Align, Node};
 use crate::node_util::{get_node_children, get_node_tag_name, get_parent_node};
-use crate::options::TranslationMode;
+use crate::options::{Options, TableSpanStyle, TranslationMode};
 use crate::serialize_if_faithful;
-use crate::text_util::{TrimDocumentWhitespace, concat_strings};
+use crate::text_util::{TrimDocumentWhitespace, concat_strings, display_width};
 use markup5ever_rcdom::NodeData;
 use std::rc::Rc;
 
@@ -35,8 +36,8 @@ pub(crate) fn table_handler(handlers: &dyn Handlers, element: Element) -> Option
 
     // Extract table rows
     let mut captions: Vec<String> = Vec::new();
-    let mut headers: Vec<String> = Vec::new();
-    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut headers: Vec<TableCell> = Vec::new();
+    let mut rows: Vec<Vec<TableCell>> = Vec::new();
     let mut has_thead = false;
 
     // Extract rows and headers from the table structure
@@ -52,27 +53,37 @@ pub(crate) fn table_handler(handlers: &dyn Handlers, element: Element) -> Option
                         }
                     }
                     "thead" => {
-                        let tr = child
-                            .children
-                            .borrow()
-                            .iter()
-                            .find(|it| get_node_tag_name(it).is_some_and(|tag| tag == "tr"))
-                            .cloned();
-
-                        let row_node = match tr {
-                            Some(tr) => tr,
-                            None => child,
+                        let trs = get_node_children(&child)
+                            .into_iter()
+                            .filter(|it| get_node_tag_name(it).is_some_and(|tag| tag == "tr"))
+                            .collect::<Vec<_>>();
+
+                        let (row_node, other_rows) = match trs.split_first() {
+                            Some((tr, others)) => (tr.clone(), others),
+                            None => (child, &[][..]),
                         };
 
                         has_thead = true;
-                        let (cells, translated) = extract_row_cells(handlers, &row_node, "th");
+                        let (cells, translated) = extract_row_cells(handlers, &row_node, &["th"]);
                         headers = cells;
                         all_children_translated &= translated;
                         if headers.is_empty() {
-                            let (cells, translated) = extract_row_cells(handlers, &row_node, "td");
+                            let (cells, translated) =
+                                extract_row_cells(handlers, &row_node, &["td"]);
                             headers = cells;
                             all_children_translated &= translated;
                         }
+
+                        // Markdown tables have only one header row, the other
+                        // rows become regular rows.
+                        for row_node in other_rows {
+                            let (row_cells, translated) =
+                                extract_row_cells(handlers, row_node, &["td", "th"]);
+                            all_children_translated &= translated;
+                            if !row_cells.is_empty() {
+                                rows.push(row_cells);
+                            }
+                        }
                     }
                     "tbody" | "tfoot" => {
                         for row_node in get_node_children(&child) {
@@ -82,7 +93,7 @@ pub(crate) fn table_handler(handlers: &dyn Handlers, element: Element) -> Option
                                 // If no thead is found, use the first th row as header
                                 if !has_thead && headers.is_empty() {
                                     let (cells, translated) =
-                                        extract_row_cells(handlers, &row_node, "th");
+                                        extract_row_cells(handlers, &row_node, &["th"]);
                                     headers = cells;
                                     all_children_translated &= translated;
                                     has_thead = !headers.is_empty();
@@ -93,7 +104,7 @@ pub(crate) fn table_handler(handlers: &dyn Handlers, element: Element) -> Option
                                 }
 
                                 let (row_cells, translated) =
-                                    extract_row_cells(handlers, &row_node, "td");
+                                    extract_row_cells(handlers, &row_node, &["td", "th"]);
                                 all_children_translated &= translated;
                                 if !row_cells.is_empty() {
                                     rows.push(row_cells);
@@ -104,11 +115,12 @@ pub(crate) fn table_handler(handlers: &dyn Handlers, element: Element) -> Option
                     "tr" => {
                         // If no thead is found, use the first row as headers
                         if !has_thead && headers.is_empty() {
-                            let (cells, translated) = extract_row_cells(handlers, &child, "th");
+                            let (cells, translated) = extract_row_cells(handlers, &child, &["th"]);
                             headers = cells;
                             all_children_translated &= translated;
                             if headers.is_empty() {
-                                let (cells, translated) = extract_row_cells(handlers, &child, "td");
+                                let (cells, translated) =
+                                    extract_row_cells(handlers, &child, &["td"]);
                                 if !cells.is_empty() {
                                     headers = cells;
                                     all_children_translated &= translated;
@@ -116,7 +128,8 @@ pub(crate) fn table_handler(handlers: &dyn Handlers, element: Element) -> Option
                             }
                             has_thead = !headers.is_empty();
                         } else {
-                            let (row_cells, translated) = extract_row_cells(handlers, &child, "td");
+                            let (row_cells, translated) =
+                                extract_row_cells(handlers, &child, &["td", "th"]);
                             all_children_translated &= translated;
                             if !row_cells.is_empty() {
                                 rows.push(row_cells);
@@ -129,7 +142,14 @@ pub(crate) fn table_handler(handlers: &dyn Handlers, element: Element) -> Option
         }
     }
 
-    if handlers.options().translation_mode == TranslationMode::Faithful && !all_children_translated
+    // Spanned cells can't be represented by Markdown tables.
+    let has_spans = headers
+        .iter()
+        .chain(rows.iter().flatten())
+        .any(|cell| cell.colspan > 1 || cell.rowspan != 1);
+
+    if handlers.options().translation_mode == TranslationMode::Faithful
+        && (!all_children_translated || has_spans)
     {
         return Some(HandlerResult {
             content: serialize_element(handlers, &element),
@@ -147,6 +167,8 @@ pub(crate) fn table_handler(handlers: &dyn Handlers, element: Element) -> Option
         return Some(concat_strings!("\n\n", content, "\n\n").into());
     }
 
+    let (headers, rows) = normalize_grid(headers, rows, handlers.options().table_span_style);
+
     // Determine the number of columns by finding the max column count
     let num_columns = if headers.is_empty() {
         rows.iter().map(|row| row.len()).max().unwrap_or(0)
@@ -154,6 +176,24 @@ pub(crate) fn table_handler(handlers: &dyn Handlers, element: Element) -> Option
         headers.len()
     };
 
+    let col_alignments = compute_column_alignments(element.node, &headers, &rows, num_columns);
+    // Captions and tables without a header row are kept as HTML.
+    if captions.is_empty() && !headers.is_empty() {
+        mdast::set_nodes(handlers, || table_nodes(&headers, &rows, &col_alignments));
+    }
+    let headers = headers
+        .iter()
+        .map(|cell| normalize_cell_content(&cell.content))
+        .collect::<Vec<_>>();
+    let rows = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| normalize_cell_content(&cell.content))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
     if num_columns == 0 {
         let content = handlers.walk_children(element.node).content;
         let content = content.trim_matches('\n');
@@ -170,21 +210,79 @@ pub(crate) fn table_handler(handlers: &dyn Handlers, element: Element) -> Option
         table_md.push_str(&format!("{caption}\n"));
     }
 
-    let col_widths = compute_column_widths(&headers, &rows, num_columns);
+    table_md.push_str(&format_table(
+        handlers.options(),
+        &headers,
+        &rows,
+        &col_alignments,
+    ));
+    table_md.push('\n');
+    Some(table_md.into())
+}
+
+/// The syntax tree of a table with a header row.
+fn table_nodes(
+    headers: &[TableCell],
+    rows: &[Vec<TableCell>],
+    col_alignments: &[Option<ColumnAlignment>],
+) -> Vec<Node> {
+    let align = col_alignments
+        .iter()
+        .map(|alignment| {
+            alignment.map(|alignment| match alignment {
+                ColumnAlignment::Left => Align::Left,
+                ColumnAlignment::Center => Align::Center,
+                ColumnAlignment::Right => Align::Right,
+            })
+        })
+        .collect();
+    let rows = std::iter::once(headers)
+        .chain(rows.iter().map(Vec::as_slice))
+        .map(|row| Node::TableRow {
+            children: row
+                .iter()
+                .take(col_alignments.len())
+                .map(|cell| Node::TableCell {
+                    children: cell.nodes.clone(),
+                })
+                .collect(),
+        })
+        .collect();
+    vec![Node::Table {
+        align,
+        children: rows,
+    }]
+}
+
+/// Format the rows of a table with normalized cells, see
+/// [`normalize_cell_content`]. There is no header row if `headers` is empty.
+pub(crate) fn format_table(
+    options: &Options,
+    headers: &[String],
+    rows: &[Vec<String>],
+    col_alignments: &[Option<ColumnAlignment>],
+) -> String {
+    let num_columns = col_alignments.len();
+    let (col_widths, separator_widths) = if options.pad_table_cells {
+        let col_widths = compute_column_widths(headers, rows, col_alignments);
+        (col_widths.clone(), col_widths)
+    } else {
+        // No padding, but use the conventional `---` separators.
+        (vec![0; num_columns], vec![3; num_columns])
+    };
 
+    let mut table_md = String::new();
     if !headers.is_empty() {
-        table_md.push_str(&format_row_padded(&headers, num_columns, &col_widths));
-        table_md.push_str(&format_separator_padded(num_columns, &col_widths));
+        table_md.push_str(&format_row_padded(headers, &col_widths, col_alignments));
+        table_md.push_str(&format_separator_padded(&separator_widths, col_alignments));
     }
     for row in rows {
-        table_md.push_str(&format_row_padded(&row, num_columns, &col_widths));
+        table_md.push_str(&format_row_padded(row, &col_widths, col_alignments));
     }
-
-    table_md.push('\n');
-    Some(table_md.into())
+    table_md
 }
 
-fn has_explicit_headers(node: &Rc<markup5ever_rcdom::Node>) -> bool {
+pub(crate) fn has_explicit_headers(node: &Rc<markup5ever_rcdom::Node>) -> bool {
     fn visit(node: &Rc<markup5ever_rcdom::Node>, is_root: bool) -> bool {
         for child in get_node_children(node) {
             if let NodeData::Element { name, .. } = &child.data {
@@ -208,7 +306,7 @@ fn has_explicit_headers(node: &Rc<markup5ever_rcdom::Node>) -> bool {
     visit(node, true)
 }
 
-fn is_inside_table_cell(node: &Rc<markup5ever_rcdom::Node>) -> bool {
+pub(crate) fn is_inside_table_cell(node: &Rc<markup5ever_rcdom::Node>) -> bool {
     let mut current = get_parent_node(node);
 
     while let Some(parent) = current {
@@ -221,19 +319,47 @@ fn is_inside_table_cell(node: &Rc<markup5ever_rcdom::Node>) -> bool {
     false
 }
 
+/// A table cell and the number of columns and rows it spans.
+#[derive(Clone)]
+struct TableCell {
+    content: String,
+    /// The syntax tree of the content, when building one.
+    nodes: Vec<Node>,
+    colspan: usize,
+    /// Zero means the cell spans all remaining rows of the table.
+    rowspan: usize,
+    /// From the `align` attribute or the `text-align` style.
+    alignment: Option<ColumnAlignment>,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub(crate) enum ColumnAlignment {
+    Left,
+    Center,
+    Right,
+}
+
 /// Extract cells from a row node
 fn extract_row_cells(
     handlers: &dyn Handlers,
     row_node: &Rc<markup5ever_rcdom::Node>,
-    cell_tag: &str,
-) -> (Vec<String>, bool) {
+    cell_tags: &[&str],
+) -> (Vec<TableCell>, bool) {
     let mut cells = Vec::new();
     let mut all_translated = true;
 
     for cell_node in get_node_children(row_node) {
-        if let NodeData::Element { name, .. } = &cell_node.data
-            && name.local.as_ref() == cell_tag
+        if let NodeData::Element { name, attrs, .. } = &cell_node.data
+            && cell_tags.contains(&name.local.as_ref())
         {
+            // Limits are taken from the HTML spec.
+            let colspan = parse_span_attr(&attrs.borrow(), "colspan")
+                .unwrap_or(1)
+                .clamp(1, 1000);
+            let rowspan = parse_span_attr(&attrs.borrow(), "rowspan")
+                .unwrap_or(1)
+                .min(65534);
+            let alignment = parse_alignment(&attrs.borrow());
             let Some(res) = handlers.handle(&cell_node) else {
                 continue;
             };
@@ -241,40 +367,294 @@ fn extract_row_cells(
                 all_translated = false;
             }
             let cell_content = res.content.trim_document_whitespace().to_string();
-            cells.push(cell_content);
+            // The paragraph of a cell is its content.
+            let mut nodes = mdast::take_nodes(handlers)
+                .map(mdast::flow)
+                .unwrap_or_default();
+            if let [Node::Paragraph { children }] = nodes.as_mut_slice() {
+                nodes = std::mem::take(children);
+            }
+            cells.push(TableCell {
+                content: cell_content,
+                nodes,
+                colspan,
+                rowspan,
+                alignment,
+            });
         }
     }
 
     (cells, all_translated)
 }
 
+fn parse_span_attr(attrs: &[html5ever::Attribute], name: &str) -> Option<usize> {
+    attrs
+        .iter()
+        .find(|attr| &attr.name.local == name)
+        .and_then(|attr| attr.value.trim().parse::<usize>().ok())
+}
+
+/// Parse the alignment from the `align` attribute or the `text-align` property
+/// of the `style` attribute. The style takes precedence, like in browsers.
+pub(crate) fn parse_alignment(attrs: &[html5ever::Attribute]) -> Option<ColumnAlignment> {
+    let from_value = |value: &str| match value.trim().to_ascii_lowercase().as_str() {
+        "left" | "start" => Some(ColumnAlignment::Left),
+        "center" | "-webkit-center" | "-moz-center" => Some(ColumnAlignment::Center),
+        "right" | "end" => Some(ColumnAlignment::Right),
+        _ => None,
+    };
+
+    let style_alignment = attrs
+        .iter()
+        .find(|attr| &attr.name.local == "style")
+        .and_then(|attr| {
+            attr.value
+                .split(';')
+                .filter_map(|declaration| declaration.split_once(':'))
+                .rfind(|(property, _)| property.trim().eq_ignore_ascii_case("text-align"))
+                .and_then(|(_, value)| from_value(value.trim_end_matches("!important")))
+        });
+
+    style_alignment.or_else(|| {
+        attrs
+            .iter()
+            .find(|attr| &attr.name.local == "align")
+            .and_then(|attr| from_value(&attr.value))
+    })
+}
+
+/// Alignments declared by `<col>` and `<colgroup>` elements, indexed by column.
+fn extract_col_alignments(table: &Rc<markup5ever_rcdom::Node>) -> Vec<Option<ColumnAlignment>> {
+    fn push_col(
+        alignments: &mut Vec<Option<ColumnAlignment>>,
+        attrs: &[html5ever::Attribute],
+        inherited: Option<ColumnAlignment>,
+    ) {
+        let span = parse_span_attr(attrs, "span").unwrap_or(1).clamp(1, 1000);
+        let alignment = parse_alignment(attrs).or(inherited);
+        alignments.extend(std::iter::repeat_n(alignment, span));
+    }
+
+    let mut alignments = Vec::new();
+    for child in get_node_children(table) {
+        let NodeData::Element { name, attrs, .. } = &child.data else {
+            continue;
+        };
+        match name.local.as_ref() {
+            "col" => push_col(&mut alignments, &attrs.borrow(), None),
+            "colgroup" => {
+                let group_alignment = parse_alignment(&attrs.borrow());
+                let cols = get_node_children(&child)
+                    .into_iter()
+                    .filter(|it| get_node_tag_name(it) == Some("col"))
+                    .collect::<Vec<_>>();
+                if cols.is_empty() {
+                    // A colgroup without cols spans columns itself.
+                    push_col(&mut alignments, &attrs.borrow(), None);
+                }
+                for col in cols {
+                    if let NodeData::Element { attrs, .. } = &col.data {
+                        push_col(&mut alignments, &attrs.borrow(), group_alignment);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    alignments
+}
+
+/// Determine the alignment of each column, in order of preference: the header
+/// cell, the `<col>` element, or an alignment shared by all aligned body cells.
+fn compute_column_alignments(
+    table: &Rc<markup5ever_rcdom::Node>,
+    headers: &[TableCell],
+    rows: &[Vec<TableCell>],
+    num_columns: usize,
+) -> Vec<Option<ColumnAlignment>> {
+    let col_alignments = extract_col_alignments(table);
+    (0..num_columns)
+        .map(|i| {
+            headers
+                .get(i)
+                .and_then(|cell| cell.alignment)
+                .or_else(|| col_alignments.get(i).copied().flatten())
+                .or_else(|| {
+                    let mut aligned = rows
+                        .iter()
+                        .filter_map(|row| row.get(i).and_then(|cell| cell.alignment));
+                    let first = aligned.next()?;
+                    aligned.all(|it| it == first).then_some(first)
+                })
+        })
+        .collect()
+}
+
+/// Expand spanned cells so that every row has one entry per grid column. The
+/// extra grid slots covered by a span are filled according to `span_style`.
+fn normalize_grid(
+    headers: Vec<TableCell>,
+    rows: Vec<Vec<TableCell>>,
+    span_style: TableSpanStyle,
+) -> (Vec<TableCell>, Vec<Vec<TableCell>>) {
+    let has_headers = !headers.is_empty();
+    let total_rows = rows.len() + usize::from(has_headers);
+    // Cells from previous rows that still span into later rows: (cell,
+    // remaining rows), indexed by column.
+    let mut pending: Vec<Option<(TableCell, usize)>> = Vec::new();
+    let mut grid: Vec<Vec<TableCell>> = Vec::with_capacity(total_rows);
+
+    let all_rows = if has_headers {
+        Some(headers).into_iter().chain(rows).collect::<Vec<_>>()
+    } else {
+        rows
+    };
+
+    for (row_idx, row) in all_rows.into_iter().enumerate() {
+        let mut line: Vec<TableCell> = Vec::with_capacity(pending.len().max(row.len()));
+        for cell in row {
+            fill_spanned_cells(&mut line, &mut pending, span_style);
+            let rowspan = if cell.rowspan == 0 {
+                total_rows - row_idx
+            } else {
+                cell.rowspan.min(total_rows - row_idx)
+            };
+            let start = line.len();
+            for col in start..(start + cell.colspan) {
+                if col == start {
+                    line.push(TableCell {
+                        colspan: 1,
+                        rowspan: 1,
+                        ..cell.clone()
+                    });
+                } else {
+                    line.push(spanned_cell(&cell, span_style));
+                }
+                if rowspan > 1 {
+                    if pending.len() <= col {
+                        pending.resize(col + 1, None);
+                    }
+                    pending[col] = Some((cell.clone(), rowspan - 1));
+                }
+            }
+        }
+
+        // Fill the columns after the last cell that are covered by row spans.
+        while line.len() < pending.len() {
+            if pending[line.len()].is_some() {
+                fill_spanned_cells(&mut line, &mut pending, span_style);
+            } else {
+                line.push(TableCell {
+                    content: String::new(),
+                    nodes: vec![],
+                    colspan: 1,
+                    rowspan: 1,
+                    alignment: None,
+                });
+            }
+        }
+
+        grid.push(line);
+    }
+
+    let headers = if has_headers {
+        grid.remove(0)
+    } else {
+        Vec::new()
+    };
+    (headers, grid)
+}
+
+/// Push the cells spanned from previous rows, starting at the current column.
+fn fill_spanned_cells(
+    line: &mut Vec<TableCell>,
+    pending: &mut [Option<(TableCell, usize)>],
+    span_style: TableSpanStyle,
+) {
+    while let Some(Some((cell, remaining))) = pending.get_mut(line.len()) {
+        line.push(spanned_cell(cell, span_style));
+        *remaining -= 1;
+        if *remaining == 0 {
+            pending[line.len() - 1] = None;
+        }
+    }
+}
+
+/// A grid slot covered by `cell`, which spans multiple columns or rows.
+fn spanned_cell(cell: &TableCell, span_style: TableSpanStyle) -> TableCell {
+    TableCell {
+        content: match span_style {
+            TableSpanStyle::Repeat => cell.content.clone(),
+            TableSpanStyle::Empty => String::new(),
+        },
+        nodes: match span_style {
+            TableSpanStyle::Repeat => cell.nodes.clone(),
+            TableSpanStyle::Empty => vec![],
+        },
+        colspan: 1,
+        rowspan: 1,
+        alignment: cell.alignment,
+    }
+}
+
 /// Normalize cell content for Markdown table representation
-fn normalize_cell_content(content: &str) -> String {
-    let content = content
-        .replace('\n', " ")
-        .replace('\r', "")
-        .replace('|', "&#124;");
-    content.trim_document_whitespace().to_string()
+pub(crate) fn normalize_cell_content(content: &str) -> String {
+    let mut normalized = String::with_capacity(content.len());
+    let mut is_prev_ch_backslash = false;
+    for ch in content.chars() {
+        match ch {
+            '\n' => normalized.push(' '),
+            '\r' => {}
+            // Pipes escaped as `\|` (GFM text) are already safe
+            '|' if !is_prev_ch_backslash => normalized.push_str("&#124;"),
+            _ => normalized.push(ch),
+        }
+        is_prev_ch_backslash = ch == '\\' && !is_prev_ch_backslash;
+    }
+    normalized.trim_document_whitespace().to_string()
 }
 
-fn format_row_padded(row: &[String], num_columns: usize, col_widths: &[usize]) -> String {
+fn format_row_padded(
+    row: &[String],
+    col_widths: &[usize],
+    col_alignments: &[Option<ColumnAlignment>],
+) -> String {
     let mut line = String::from("|");
-    for (i, col_width) in col_widths.iter().enumerate().take(num_columns) {
-        let cell = row
-            .get(i)
-            .map(|s| normalize_cell_content(s))
-            .unwrap_or_default();
-        let pad = col_width.saturating_sub(cell.chars().count());
-        line.push_str(&concat_strings!(" ", cell, " ".repeat(pad), " |"));
+    for (i, col_width) in col_widths.iter().enumerate() {
+        let cell = row.get(i).map(String::as_str).unwrap_or_default();
+        let pad = col_width.saturating_sub(display_width(cell));
+        let (left_pad, right_pad) = match col_alignments[i] {
+            Some(ColumnAlignment::Right) => (pad, 0),
+            Some(ColumnAlignment::Center) => (pad / 2, pad - pad / 2),
+            Some(ColumnAlignment::Left) | None => (0, pad),
+        };
+        line.push_str(&concat_strings!(
+            " ",
+            " ".repeat(left_pad),
+            cell,
+            " ".repeat(right_pad),
+            " |"
+        ));
     }
     line.push('\n');
     line
 }
 
-fn format_separator_padded(num_columns: usize, col_widths: &[usize]) -> String {
+fn format_separator_padded(
+    col_widths: &[usize],
+    col_alignments: &[Option<ColumnAlignment>],
+) -> String {
     let mut line = String::from("|");
-    for (_, col_width) in col_widths.iter().enumerate().take(num_columns) {
-        line.push_str(&concat_strings!(" ", "-".repeat(*col_width), " |"));
+    for (col_width, alignment) in col_widths.iter().zip(col_alignments) {
+        let separator = match alignment {
+            Some(ColumnAlignment::Left) => concat_strings!(":", "-".repeat(col_width - 1)),
+            Some(ColumnAlignment::Center) => {
+                concat_strings!(":", "-".repeat(col_width - 2), ":")
+            }
+            Some(ColumnAlignment::Right) => concat_strings!("-".repeat(col_width - 1), ":"),
+            None => "-".repeat(*col_width),
+        };
+        line.push_str(&concat_strings!(" ", separator, " |"));
     }
     line.push('\n');
     line
@@ -283,15 +663,24 @@ fn format_separator_padded(num_columns: usize, col_widths: &[usize]) -> String {
 fn compute_column_widths(
     headers: &[String],
     rows: &[Vec<String>],
-    num_columns: usize,
+    col_alignments: &[Option<ColumnAlignment>],
 ) -> Vec<usize> {
-    let mut widths = vec![0; num_columns];
-    for (i, header) in headers.iter().enumerate() {
-        widths[i] = header.chars().count();
+    let num_columns = col_alignments.len();
+    // Aligned separators need room for the colons: `:-`, `-:` or `:-:`.
+    let mut widths = col_alignments
+        .iter()
+        .map(|alignment| match alignment {
+            Some(ColumnAlignment::Center) => 3,
+            Some(_) => 2,
+            None => 0,
+        })
+        .collect::<Vec<_>>();
+    for (i, header) in headers.iter().enumerate().take(num_columns) {
+        widths[i] = widths[i].max(display_width(header));
     }
     for row in rows {
         for (i, cell) in row.iter().enumerate().take(num_columns) {
-            let len = cell.chars().count();
+            let len = display_width(cell);
             if len > widths[i] {
                 widths[i] = len;
             }
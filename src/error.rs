@@ -0,0 +1,79 @@
+use std::fmt;
+
+use crate::{Limit, element_handler::Handlers};
+
+/// The error of a conversion.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Reading or decoding the HTML failed.
+    Parse(std::io::Error),
+    /// Serializing an element back to HTML failed, in faithful translation
+    /// mode.
+    Serialize {
+        /// The tag name of the element.
+        tag: String,
+        /// The serializer error.
+        source: std::io::Error,
+    },
+    /// Writing the Markdown failed.
+    Write(std::io::Error),
+    /// A limit of [`Limits`](crate::Limits) was exceeded.
+    LimitExceeded {
+        /// The exceeded limit.
+        limit: Limit,
+        /// The configured maximum.
+        max: usize,
+    },
+    /// A CSS selector passed to the builder is invalid.
+    InvalidSelector {
+        /// The selector.
+        selector: String,
+        /// The syntax error.
+        message: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(err) => write!(f, "failed to parse HTML: {err}"),
+            Error::Serialize { tag, source } => {
+                write!(f, "failed to serialize <{tag}> to HTML: {source}")
+            }
+            Error::Write(err) => write!(f, "failed to write Markdown: {err}"),
+            Error::LimitExceeded { limit, max } => {
+                write!(f, "exceeded the maximum {limit} of {max}")
+            }
+            Error::InvalidSelector { selector, message } => {
+                write!(f, "invalid selector {selector:?}: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Parse(err) | Error::Write(err) => Some(err),
+            Error::Serialize { source, .. } => Some(source),
+            Error::LimitExceeded { .. } | Error::InvalidSelector { .. } => None,
+        }
+    }
+}
+
+/// The first error of the current conversion, which is returned instead of
+/// the Markdown once the document has been walked.
+#[derive(Default)]
+pub(crate) struct ConversionError(pub(crate) Option<Error>);
+
+impl ConversionError {
+    /// Record `error` if it is the first error of the conversion.
+    pub(crate) fn record(handlers: &dyn Handlers, error: Error) {
+        let state = handlers.state().get::<ConversionError>();
+        let mut state = state.borrow_mut();
+        if state.0.is_none() {
+            state.0 = Some(error);
+        }
+    }
+}
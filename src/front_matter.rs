@@ -0,0 +1,146 @@
+use std::rc::Rc;
+
+use markup5ever_rcdom::{Node, NodeData};
+
+use crate::{
+    element_handler::{ConversionContext, Handlers},
+    node_util::{find_document_head, get_node_attr, text_content},
+    options::FrontMatterStyle,
+};
+
+pub(crate) type FrontMatterFieldMapper = Box<dyn Fn(&str) -> Option<String> + Send + Sync>;
+
+enum Value {
+    Text(String),
+    List(Vec<String>),
+}
+
+/// Render the metadata in the `<head>` of `document` as front matter, see
+/// [`FrontMatterStyle`]. Returns `None` if there is no metadata.
+pub(crate) fn front_matter(context: &ConversionContext, document: &Rc<Node>) -> Option<String> {
+    let style = context.options().front_matter_style;
+    if style == FrontMatterStyle::Disabled {
+        return None;
+    }
+    let head = find_document_head(document)?;
+
+    let mut fields: Vec<(String, Value)> = vec![];
+    for child in head.children.borrow().iter() {
+        let NodeData::Element { name, .. } = &child.data else {
+            continue;
+        };
+        let attr = |name: &str| get_node_attr(child, name).filter(|it| !it.trim().is_empty());
+        let (key, value) = match &*name.local {
+            "title" => ("title".to_string(), Value::Text(text_content(child))),
+            "meta" => {
+                let Some(content) = attr("content") else {
+                    continue;
+                };
+                let content = content.trim().to_string();
+                if let Some(property) = attr("property")
+                    .map(|it| it.to_ascii_lowercase())
+                    .filter(|it| it.starts_with("og:"))
+                {
+                    (property, Value::Text(content))
+                } else {
+                    match attr("name").map(|it| it.to_ascii_lowercase()).as_deref() {
+                        Some(name @ ("description" | "author")) => {
+                            (name.to_string(), Value::Text(content))
+                        }
+                        Some("keywords") => {
+                            let keywords = content
+                                .split(',')
+                                .map(str::trim)
+                                .filter(|it| !it.is_empty())
+                                .map(str::to_string)
+                                .collect();
+                            ("keywords".to_string(), Value::List(keywords))
+                        }
+                        _ => continue,
+                    }
+                }
+            }
+            "link" => {
+                let is_canonical = attr("rel").is_some_and(|rel| {
+                    rel.split_ascii_whitespace()
+                        .any(|it| it.eq_ignore_ascii_case("canonical"))
+                });
+                let Some(href) = attr("href").filter(|_| is_canonical) else {
+                    continue;
+                };
+                let href = context.resolve_url(child, href.trim());
+                ("canonical".to_string(), Value::Text(href))
+            }
+            _ => continue,
+        };
+        if matches!(&value, Value::Text(text) if text.is_empty()) {
+            continue;
+        }
+        let key = match &context.handlers.front_matter_fields {
+            Some(mapper) => match mapper(&key) {
+                Some(key) => key,
+                None => continue,
+            },
+            None => key,
+        };
+        // The first field of a name wins.
+        if !fields.iter().any(|(it, _)| *it == key) {
+            fields.push((key, value));
+        }
+    }
+    if fields.is_empty() {
+        return None;
+    }
+
+    let (delimiter, separator) = match style {
+        FrontMatterStyle::Toml => ("+++", " = "),
+        _ => ("---", ": "),
+    };
+    let mut output = String::new();
+    output.push_str(delimiter);
+    output.push('\n');
+    for (key, value) in fields {
+        if key
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-')
+        {
+            output.push_str(&key);
+        } else {
+            push_quoted(&mut output, &key);
+        }
+        output.push_str(separator);
+        match value {
+            Value::Text(text) => push_quoted(&mut output, &text),
+            Value::List(items) => {
+                output.push('[');
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        output.push_str(", ");
+                    }
+                    push_quoted(&mut output, item);
+                }
+                output.push(']');
+            }
+        }
+        output.push('\n');
+    }
+    output.push_str(delimiter);
+    Some(output)
+}
+
+/// Push a double-quoted string, with the escapes shared by YAML and TOML.
+fn push_quoted(output: &mut String, text: &str) {
+    output.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            _ if ch.is_control() => output.push_str(&format!("\\u{:04X}", ch as u32)),
+            _ => output.push(ch),
+        }
+    }
+    output.push('"');
+}
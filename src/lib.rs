@@ -1,21 +1,49 @@
+#[cfg(feature = "encoding")]
+mod charset;
+pub mod chunk;
 mod dom_walker;
 pub mod element_handler;
+mod error;
+mod front_matter;
 mod html_escape;
+mod limits;
+pub mod mdast;
 pub(crate) mod node_util;
 pub mod options;
+mod readability;
+pub mod report;
+mod selector;
+pub mod source_map;
 pub(crate) mod text_util;
+pub(crate) mod url_util;
 
-use std::rc::Rc;
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{Read, Write},
+    rc::Rc,
+};
 
 use dom_walker::walk_node;
-use element_handler::{ElementHandler, ElementHandlers};
+use element_handler::{ConversionContext, ElementHandler, ElementHandlers, UrlKind};
+use error::ConversionError;
 use html5ever::tendril::TendrilSink;
-use html5ever::tree_builder::TreeBuilderOpts;
-use html5ever::{Attribute, ParseOpts, parse_document};
+use html5ever::tree_builder::{ElementFlags, NodeOrText, TreeBuilderOpts, TreeSink};
+use html5ever::{Attribute, LocalName, ParseOpts, QualName, ns, parse_document, parse_fragment};
+use mdast::TreeState;
 // Export publicly, providing an interface to the
 pub use markup5ever_rcdom::Node;
 use markup5ever_rcdom::RcDom;
 use options::Options;
+use report::ConversionReport;
+use source_map::{LineTrackingSink, SourceMapState};
+use text_util::TrimDocumentWhitespace;
+
+pub use chunk::Chunk;
+pub use error::Error;
+pub use limits::{Limit, Limits};
+pub use report::Conversion;
+pub use source_map::SourceMap;
 
 use crate::element_handler::Handlers;
 
@@ -29,7 +57,7 @@ use crate::element_handler::Handlers;
 /// let md = convert("<h1>Hello</h1>").unwrap();
 /// assert_eq!("# Hello", md);
 /// ```
-pub fn convert(html: &str) -> Result<String, std::io::Error> {
+pub fn convert(html: &str) -> Result<String, Error> {
     HtmlToMarkdown::new().convert(html)
 }
 
@@ -54,6 +82,10 @@ pub struct Element<'a> {
 
 /// The html-to-markdown converter.
 ///
+/// A converter is `Send + Sync`: build it once and share it between threads,
+/// e.g. in an `Arc`. Every conversion keeps its own state, so concurrent
+/// conversions using the same converter are independent.
+///
 /// # Example
 /// ```
 /// use htmd::{Element, HtmlToMarkdown};
@@ -72,7 +104,6 @@ pub struct Element<'a> {
 /// ```
 pub struct HtmlToMarkdown {
     handlers: ElementHandlers,
-    scripting_enabled: bool,
 }
 
 impl Default for HtmlToMarkdown {
@@ -86,17 +117,11 @@ impl HtmlToMarkdown {
     pub fn new() -> Self {
         let options = Options::default();
         let handlers = ElementHandlers::new(options);
-        Self {
-            handlers,
-            scripting_enabled: true,
-        }
+        Self { handlers }
     }
 
-    pub(crate) fn from_params(handlers: ElementHandlers, scripting_enabled: bool) -> Self {
-        Self {
-            handlers,
-            scripting_enabled,
-        }
+    pub(crate) fn from_params(handlers: ElementHandlers) -> Self {
+        Self { handlers }
     }
 
     /// Create a new [HtmlToMarkdownBuilder].
@@ -105,55 +130,652 @@ impl HtmlToMarkdown {
     }
 
     /// Convert HTML to a DOM tree.
-    pub fn html_to_tree(&self, html: &str) -> std::io::Result<Rc<Node>> {
-        let dom = parse_document(
+    pub fn html_to_tree(&self, html: &str) -> Result<Rc<Node>, Error> {
+        self.reader_to_tree(&mut html.as_bytes())
+    }
+
+    /// Convert an HTML fragment to a DOM tree, parsing it as if it was the
+    /// content of a `context_tag` element, see [`Self::convert_fragment`].
+    pub fn fragment_to_tree(&self, html: &str, context_tag: &str) -> Result<Rc<Node>, Error> {
+        self.check_input_len(html.len())?;
+        let dom = parse_fragment(
             RcDom::default(),
             ParseOpts {
                 tree_builder: TreeBuilderOpts {
-                    scripting_enabled: self.scripting_enabled,
+                    scripting_enabled: self.handlers.scripting_enabled,
                     ..Default::default()
                 },
                 ..Default::default()
             },
+            QualName::new(None, ns!(html), LocalName::from(context_tag)),
+            vec![],
+            self.handlers.scripting_enabled,
         )
         .from_utf8()
-        .read_from(&mut html.as_bytes())?;
+        .read_from(&mut html.as_bytes())
+        .map_err(Error::Parse)?;
+
+        // The fragment is parsed into an `<html>` element. Recreate the
+        // context of list items and table parts, so that they are converted
+        // to Markdown lists and tables.
+        let context_chain = match context_tag {
+            "ul" | "ol" | "table" => vec![context_tag],
+            "thead" | "tbody" | "tfoot" => vec!["table", context_tag],
+            "tr" => vec!["table", "tbody", "tr"],
+            _ => vec![],
+        };
+        let html_element = dom.document.children.borrow().first().cloned();
+        if let Some(html_element) = html_element
+            && !context_chain.is_empty()
+        {
+            let chain: Vec<Rc<Node>> = context_chain
+                .iter()
+                .map(|tag| {
+                    let name = QualName::new(None, ns!(html), LocalName::from(*tag));
+                    dom.create_element(name, vec![], ElementFlags::default())
+                })
+                .collect();
+            dom.reparent_children(&html_element, chain.last().unwrap());
+            for pair in chain.windows(2) {
+                dom.append(&pair[0], NodeOrText::AppendNode(pair[1].clone()));
+            }
+            dom.append(&html_element, NodeOrText::AppendNode(chain[0].clone()));
+        }
 
         Ok(dom.document)
     }
 
+    /// Parse UTF-8 encoded HTML from `reader` to a DOM tree, reading at most
+    /// [`Limits::max_input_bytes`].
+    fn reader_to_tree<R: Read>(&self, reader: &mut R) -> Result<Rc<Node>, Error> {
+        let Some(max) = self.handlers.limits.max_input_bytes else {
+            return self.parse_document(reader);
+        };
+        // Read one more byte to tell if the input is larger than the limit.
+        let mut reader = reader.take(max as u64 + 1);
+        let tree = self.parse_document(&mut reader)?;
+        if reader.limit() == 0 {
+            return Err(Error::LimitExceeded {
+                limit: Limit::InputBytes,
+                max,
+            });
+        }
+        Ok(tree)
+    }
+
+    fn check_input_len(&self, len: usize) -> Result<(), Error> {
+        match self.handlers.limits.max_input_bytes {
+            Some(max) if len > max => Err(Error::LimitExceeded {
+                limit: Limit::InputBytes,
+                max,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Parse UTF-8 encoded HTML from `reader` to a DOM tree.
+    fn parse_document<R: Read>(&self, reader: &mut R) -> Result<Rc<Node>, Error> {
+        let dom = self.parse_document_into(RcDom::default(), reader)?;
+        Ok(dom.document)
+    }
+
+    /// Parse UTF-8 encoded HTML from `reader` into `sink`.
+    fn parse_document_into<S: TreeSink, R: Read>(
+        &self,
+        sink: S,
+        reader: &mut R,
+    ) -> Result<S::Output, Error> {
+        parse_document(
+            sink,
+            ParseOpts {
+                tree_builder: TreeBuilderOpts {
+                    scripting_enabled: self.handlers.scripting_enabled,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )
+        .from_utf8()
+        .read_from(reader)
+        .map_err(Error::Parse)
+    }
+
     /// Convert a DOM tree to Markdown. For convenience, `Node` is re-exported;
     /// simply `use htmd::Node;` to access this type.
-    pub fn tree_to_markdown(&self, tree: &Rc<Node>) -> String {
+    pub fn tree_to_markdown(&self, tree: &Rc<Node>) -> Result<String, Error> {
+        let mut context = ConversionContext::new(&self.handlers);
+        let (mut content, append) = self.tree_to_markdown_parts(tree, &mut context)?;
+        content.push_str(&append);
+        Ok(content)
+    }
+
+    /// Find the element of `tree` to convert, and exclude the elements to skip
+    /// from `context`. Returns `None` if there is nothing to convert.
+    fn conversion_root(
+        &self,
+        tree: &Rc<Node>,
+        context: &mut ConversionContext,
+    ) -> Option<Rc<Node>> {
+        let mut root = tree.clone();
+        if let Some(selector) = &self.handlers.select_root {
+            root = selector.find_first(tree)?;
+        }
+        if self.handlers.extract_main_content {
+            let main_content = readability::find_main_content(&root);
+            root = main_content.root;
+            context.excluded = main_content.excluded;
+        }
+        for selector in &self.handlers.skip_selectors {
+            let skipped = selector.find_outermost(&root);
+            context.excluded.extend(skipped.iter().map(Rc::as_ptr));
+        }
+        Some(root)
+    }
+
+    /// Convert a DOM tree to the Markdown of the document and the content
+    /// appended by the handlers, e.g. link reference definitions.
+    fn tree_to_markdown_parts(
+        &self,
+        tree: &Rc<Node>,
+        context: &mut ConversionContext,
+    ) -> Result<(String, String), Error> {
         let mut content = String::new();
+        let Some(root) = self.conversion_root(tree, context) else {
+            return Ok((String::new(), String::new()));
+        };
+        if context.mdast {
+            TreeState::start(context);
+        }
+        walk_node(&root, &mut content, context, None, true, false);
+        if context.mdast {
+            TreeState::leave_root(context, &content);
+        }
+
+        // Trim in place, the content can be huge.
+        content.truncate(content.trim_end_matches('\n').len());
+        let leading_new_lines = content.len() - content.trim_start_matches('\n').len();
+        content.drain(..leading_new_lines);
+
+        let content_len = content.len();
+        if let Some(front_matter) = front_matter::front_matter(context, tree) {
+            if context.mdast {
+                TreeState::front_matter(context, &front_matter);
+            }
+            if !content.is_empty() {
+                content.insert_str(0, "\n\n");
+            }
+            content.insert_str(0, &front_matter);
+        }
+        if context.source_mapping {
+            SourceMapState::move_root(context, leading_new_lines, content.len() - content_len);
+        }
+
+        let mut append = context.append();
+        append.truncate(append.trim_end_matches('\n').len());
+        context.check_output_len(content.len() + append.len());
+
+        if let Some(error) = context.state().get::<ConversionError>().take().0 {
+            return Err(error);
+        }
+        Ok((content, append))
+    }
+
+    /// Convert HTML to Markdown.
+    pub fn convert(&self, html: &str) -> Result<String, Error> {
+        self.tree_to_markdown(&self.html_to_tree(html)?)
+    }
+
+    /// Convert HTML to Markdown, and collect the metadata of the document
+    /// while converting it: its title, links, images, headings, word count
+    /// and the elements left out of the Markdown.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use htmd::HtmlToMarkdown;
+    ///
+    /// let html = "<title>Hello</title><h1>Hello</h1><p>A <a href=\"/world\">world</a></p>";
+    /// let converter = HtmlToMarkdown::builder().skip_tags(vec!["head"]).build();
+    /// let conversion = converter.convert_with_report(html).unwrap();
+    /// assert_eq!("# Hello\n\nA [world](/world)", conversion.markdown);
+    /// assert_eq!(Some("Hello"), conversion.title.as_deref());
+    /// assert_eq!("/world", conversion.links[0].url);
+    /// assert_eq!(1, conversion.headings[0].level);
+    /// assert_eq!(3, conversion.word_count);
+    /// ```
+    pub fn convert_with_report(&self, html: &str) -> Result<Conversion, Error> {
+        let tree = self.html_to_tree(html)?;
+        let mut context = ConversionContext::new(&self.handlers);
+        context.reporting = true;
+        let report = context.state().get::<ConversionReport>();
+        report.borrow_mut().conversion = Some(Conversion::default());
+
+        let (mut content, append) = self.tree_to_markdown_parts(&tree, &mut context)?;
+        content.push_str(&append);
+
+        let mut conversion = report.borrow_mut().conversion.take().unwrap_or_default();
+        conversion.markdown = content;
+        conversion.title = node_util::find_document_head(&tree)
+            .and_then(|head| {
+                head.children
+                    .borrow()
+                    .iter()
+                    .find(|child| node_util::get_node_tag_name(child) == Some("title"))
+                    .map(node_util::text_content)
+            })
+            .filter(|title| !title.is_empty());
+        Ok(conversion)
+    }
+
+    /// Convert HTML to Markdown, and map the blocks of the Markdown to the
+    /// elements they were converted from and their lines in the HTML, e.g. to
+    /// highlight the source of a Markdown line or to debug a custom handler.
+    ///
+    /// Block elements and elements converted by custom handlers are mapped.
+    /// The blocks inside of headings, tables, `<pre>` and elements converted
+    /// by custom handlers are mapped as a whole. A block is left out if the
+    /// handler of a parent changed its Markdown other than by indenting or
+    /// quoting its lines.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use htmd::HtmlToMarkdown;
+    ///
+    /// let html = "<h1>Hello</h1>\n<blockquote>\n  <p>World</p>\n</blockquote>";
+    /// let source_map = HtmlToMarkdown::new().convert_with_source_map(html).unwrap();
+    /// assert_eq!("# Hello\n\n> World", source_map.markdown);
+    ///
+    /// let quote = &source_map.blocks[1];
+    /// assert_eq!("> World", &source_map.markdown[quote.range.clone()]);
+    /// assert_eq!("blockquote", quote.tag);
+    /// assert_eq!(Some(2), quote.line);
+    /// assert_eq!("p", source_map.blocks[2].tag);
+    /// assert_eq!(Some(3), source_map.blocks[2].line);
+    /// ```
+    pub fn convert_with_source_map(&self, html: &str) -> Result<SourceMap, Error> {
+        self.check_input_len(html.len())?;
+        let (dom, lines) =
+            self.parse_document_into(LineTrackingSink::default(), &mut html.as_bytes())?;
+        self.tree_to_source_map(&dom.document, lines)
+    }
+
+    /// Convert a DOM tree to Markdown and map the blocks of the Markdown to
+    /// the elements they were converted from, see
+    /// [`Self::convert_with_source_map`]. The lines of the elements are
+    /// unknown.
+    pub fn tree_to_markdown_with_source_map(&self, tree: &Rc<Node>) -> Result<SourceMap, Error> {
+        self.tree_to_source_map(tree, HashMap::new())
+    }
+
+    fn tree_to_source_map(
+        &self,
+        tree: &Rc<Node>,
+        lines: HashMap<*const Node, u64>,
+    ) -> Result<SourceMap, Error> {
+        let mut context = ConversionContext::new(&self.handlers);
+        context.source_mapping = true;
+        context.state().get::<SourceMapState>().borrow_mut().lines = lines;
+        SourceMapState::start(&context);
+
+        let (mut content, append) = self.tree_to_markdown_parts(tree, &mut context)?;
+        content.push_str(&append);
+        Ok(SourceMapState::resolve(&context, content))
+    }
+
+    /// Convert HTML to Markdown split into chunks of at most `max_chars`
+    /// characters, e.g. for embedding. A chunk starts at a heading and ends at
+    /// a block boundary, so tables and code blocks are never cut, lists are
+    /// only cut between items, and blockquotes and list items larger than
+    /// `max_chars` between the blocks in them. A chunk carries the path of
+    /// the headings it is nested in. A block larger than `max_chars` gets a
+    /// chunk of its own.
+    ///
+    /// Link reference definitions end up in the last chunk, use
+    /// [`LinkStyle::Inlined`](options::LinkStyle::Inlined) for self-contained
+    /// chunks.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use htmd::HtmlToMarkdown;
+    ///
+    /// let html = "<h1>Fruits</h1><p>Sweet.</p><h2>Apples</h2><p>Red.</p><p>Green.</p>";
+    /// let chunks = HtmlToMarkdown::new().convert_to_chunks(html, 20).unwrap();
+    /// assert_eq!(3, chunks.len());
+    /// assert_eq!("# Fruits\n\nSweet.", chunks[0].markdown);
+    /// assert_eq!("## Apples\n\nRed.", chunks[1].markdown);
+    /// assert_eq!("Green.", chunks[2].markdown);
+    /// assert_eq!(vec!["Fruits", "Apples"], chunks[2].breadcrumb);
+    /// ```
+    pub fn convert_to_chunks(&self, html: &str, max_chars: usize) -> Result<Vec<Chunk>, Error> {
+        self.convert_to_chunks_by(html, max_chars, |text| text.chars().count())
+    }
+
+    /// Like [`Self::convert_to_chunks`], with the size of a chunk measured by
+    /// `size`, e.g. a token counter. The size of a chunk is the sum of the
+    /// sizes of its blocks and the blank lines between them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use htmd::HtmlToMarkdown;
+    ///
+    /// let words = |text: &str| text.split_whitespace().count();
+    /// let chunks = HtmlToMarkdown::new()
+    ///     .convert_to_chunks_by("<p>One two</p><p>Three four</p>", 3, words)
+    ///     .unwrap();
+    /// assert_eq!(2, chunks.len());
+    /// ```
+    pub fn convert_to_chunks_by<F>(
+        &self,
+        html: &str,
+        max_size: usize,
+        size: F,
+    ) -> Result<Vec<Chunk>, Error>
+    where
+        F: Fn(&str) -> usize,
+    {
+        let source_map = self.convert_with_source_map(html)?;
+        Ok(chunk::chunk_markdown(
+            &source_map.markdown,
+            &source_map.blocks,
+            max_size,
+            &size,
+        ))
+    }
+
+    /// Convert HTML to a Markdown syntax tree, see [`mdast`]. The tree is built
+    /// from the output of the handlers, so rendering it with the options of
+    /// the converter gives the same Markdown as [`Self::convert`].
+    ///
+    /// Each element whose Markdown has no structured counterpart becomes an
+    /// [`mdast::Node::Html`] node holding that Markdown, in place of the
+    /// element only. This is the case for elements converted by custom
+    /// handlers, or kept as HTML in the faithful translation mode.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use htmd::{HtmlToMarkdown, mdast::Node, options::{HeadingStyle, Options}};
+    ///
+    /// let root = HtmlToMarkdown::new().convert_to_mdast("<h1>Hello</h1>").unwrap();
+    /// assert_eq!("heading", root.children()[0].node_type());
+    /// assert_eq!("Hello", root.to_text());
+    ///
+    /// let options = Options {
+    ///     heading_style: HeadingStyle::Setex,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!("Hello\n=====", root.to_markdown(&options));
+    /// ```
+    pub fn convert_to_mdast(&self, html: &str) -> Result<mdast::Node, Error> {
+        self.tree_to_mdast(&self.html_to_tree(html)?)
+    }
+
+    /// Convert a DOM tree to a Markdown syntax tree, see
+    /// [`Self::convert_to_mdast`].
+    pub fn tree_to_mdast(&self, tree: &Rc<Node>) -> Result<mdast::Node, Error> {
+        let mut context = ConversionContext::new(&self.handlers);
+        context.mdast = true;
+        self.tree_to_markdown_parts(tree, &mut context)?;
+        Ok(mdast::Node::Root {
+            children: TreeState::finish(&context),
+        })
+    }
+
+    /// Convert an HTML fragment to Markdown. Unlike [`Self::convert`], no
+    /// `<html>`, `<head>` and `<body>` are implied: the fragment is parsed as
+    /// if it was the content of a `context_tag` element, e.g. `"body"` for
+    /// snippets and email bodies, or `"tbody"` for table rows, so elements
+    /// stay where they are.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use htmd::HtmlToMarkdown;
+    ///
+    /// let converter = HtmlToMarkdown::new();
+    /// let md = converter.convert_fragment("<title>Title</title><p>Text</p>", "body").unwrap();
+    /// assert_eq!("Title\n\nText", md);
+    ///
+    /// let md = converter.convert_fragment("<tr><th>A</th><th>B</th></tr>", "tbody").unwrap();
+    /// assert_eq!("| A | B |\n| - | - |", md);
+    /// ```
+    pub fn convert_fragment(&self, html: &str, context_tag: &str) -> Result<String, Error> {
+        self.tree_to_markdown(&self.fragment_to_tree(html, context_tag)?)
+    }
+
+    /// Convert UTF-8 encoded HTML from `reader` to Markdown. The HTML is
+    /// parsed while it is read, so the input text is never held in memory as
+    /// a whole, but the DOM tree of the whole document is.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use htmd::HtmlToMarkdown;
+    ///
+    /// let file = std::fs::File::open("page.html")?;
+    /// let md = HtmlToMarkdown::new().convert_reader(std::io::BufReader::new(file))?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn convert_reader<R: Read>(&self, mut reader: R) -> Result<String, Error> {
+        self.tree_to_markdown(&self.reader_to_tree(&mut reader)?)
+    }
+
+    /// Convert HTML to Markdown, writing the Markdown to `writer` instead of
+    /// returning it. The top-level blocks of the document are written as soon
+    /// as they are converted, so the Markdown is never held in memory as a
+    /// whole. If the conversion fails, e.g. because a limit is exceeded, the
+    /// Markdown written so far is incomplete.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use htmd::HtmlToMarkdown;
+    ///
+    /// let mut md = vec![];
+    /// HtmlToMarkdown::new().convert_to_writer("<h1>Hello</h1>", &mut md)?;
+    /// assert_eq!(b"# Hello", md.as_slice());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn convert_to_writer<W: Write>(&self, html: &str, mut writer: W) -> Result<(), Error> {
+        let tree = self.html_to_tree(html)?;
+        self.tree_to_writer(&tree, &mut |markdown| {
+            writer.write_all(markdown.as_bytes()).map_err(Error::Write)
+        })?;
+        writer.flush().map_err(Error::Write)
+    }
 
-        walk_node(tree, &mut content, &self.handlers, None, true, false);
+    /// Like [`Self::convert_to_writer`], writing the Markdown to a
+    /// [`fmt::Write`](std::fmt::Write), e.g. a `String` or a
+    /// [`fmt::Formatter`](std::fmt::Formatter).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use htmd::HtmlToMarkdown;
+    ///
+    /// let mut md = String::from("<!-- Generated -->\n");
+    /// HtmlToMarkdown::new().convert_to_fmt_writer("<h1>Hello</h1>", &mut md)?;
+    /// assert_eq!("<!-- Generated -->\n# Hello", md);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn convert_to_fmt_writer<W: fmt::Write>(
+        &self,
+        html: &str,
+        mut writer: W,
+    ) -> Result<(), Error> {
+        let tree = self.html_to_tree(html)?;
+        self.tree_to_writer(&tree, &mut |markdown| {
+            writer
+                .write_str(markdown)
+                .map_err(|err| Error::Write(std::io::Error::other(err)))
+        })
+    }
 
-        let mut content = content.trim_matches(|ch| ch == '\n').to_string();
+    /// Convert a DOM tree to Markdown, passing the Markdown to `write` block
+    /// by block. The result is the same as [`Self::tree_to_markdown`].
+    fn tree_to_writer(
+        &self,
+        tree: &Rc<Node>,
+        write: &mut dyn FnMut(&str) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let mut context = ConversionContext::new(&self.handlers);
+        let Some(root) = self.conversion_root(tree, &mut context) else {
+            return Ok(());
+        };
+        let mut stream = MarkdownStream {
+            write,
+            front_matter: front_matter::front_matter(&context, tree),
+            started: false,
+            written: 0,
+        };
 
-        let mut append = String::new();
-        for handler in &self.handlers.handlers {
-            let Some(append_content) = handler.append() else {
-                continue;
+        let mut output = String::new();
+        dom_walker::stream_node(&root, &mut output, &context, &mut |output| {
+            // Blocks are normalized with the whitespace at the end of the
+            // previous ones, keep it and the last character.
+            let content = output.trim_end_document_whitespace();
+            let Some((end, _)) = content.char_indices().next_back() else {
+                return;
             };
-            append.push_str(&append_content);
+            if end < STREAM_BUFFER_SIZE {
+                return;
+            }
+            if let Err(error) = stream.write(&output[..end]) {
+                context.abort(error);
+            }
+            output.drain(..end);
+            context.check_output_len(stream.written + output.len());
+        });
+
+        output.truncate(output.trim_end_matches('\n').len());
+        stream.write(&output)?;
+        if !stream.started
+            && let Some(front_matter) = stream.front_matter.take()
+        {
+            stream.written += front_matter.len();
+            (stream.write)(&front_matter)?;
         }
 
-        content.push_str(append.trim_end_matches('\n'));
+        let mut append = context.append();
+        append.truncate(append.trim_end_matches('\n').len());
+        context.check_output_len(stream.written + append.len());
+        if let Some(error) = context.state().get::<ConversionError>().take().0 {
+            return Err(error);
+        }
+        (stream.write)(&append)
+    }
 
-        content
+    /// Convert an HTML document in any encoding to Markdown. The encoding is
+    /// detected like browsers do: from a byte order mark, the
+    /// `transport_hint`, or a `<meta charset>` near the start of the
+    /// document, in that order. The hint is usually the `Content-Type`
+    /// header of the response, e.g. `text/html; charset=Shift_JIS`, but a
+    /// bare charset label like `gbk` works too. Undeclared documents are
+    /// decoded as UTF-8 if valid, as windows-1252 otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use htmd::HtmlToMarkdown;
+    ///
+    /// let converter = HtmlToMarkdown::new();
+    /// let html = b"<meta charset=windows-1252><p>Caf\xE9</p>";
+    /// assert_eq!("Caf\u{e9}", converter.convert_bytes(html, None).unwrap());
+    ///
+    /// let html = b"<p>\xA4\xA4\xA4\xE5</p>";
+    /// assert_eq!("\u{4e2d}\u{6587}", converter.convert_bytes(html, Some("big5")).unwrap());
+    /// ```
+    #[cfg(feature = "encoding")]
+    pub fn convert_bytes(
+        &self,
+        bytes: &[u8],
+        transport_hint: Option<&str>,
+    ) -> Result<String, Error> {
+        // The limit applies to the bytes, not to the decoded text.
+        self.check_input_len(bytes.len())?;
+        let html = charset::decode_html(bytes, transport_hint);
+        self.tree_to_markdown(&self.parse_document(&mut html.as_bytes())?)
     }
 
-    /// Convert HTML to Markdown.
-    pub fn convert(&self, html: &str) -> std::io::Result<String> {
-        Ok(self.tree_to_markdown(&self.html_to_tree(html)?))
+    /// Convert many HTML documents to Markdown in parallel, using the global
+    /// [rayon](https://docs.rs/rayon) thread pool. The results are in the same
+    /// order as `htmls`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use htmd::HtmlToMarkdown;
+    ///
+    /// let converter = HtmlToMarkdown::new();
+    /// let results = converter.convert_batch(&["<h1>One</h1>", "<h2>Two</h2>"]);
+    /// assert_eq!("# One", results[0].as_ref().unwrap());
+    /// assert_eq!("## Two", results[1].as_ref().unwrap());
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn convert_batch(&self, htmls: &[&str]) -> Vec<Result<String, Error>> {
+        use rayon::prelude::*;
+
+        htmls.par_iter().map(|html| self.convert(html)).collect()
     }
 }
 
+/// The size of the Markdown buffered by [`HtmlToMarkdown::convert_to_writer`]
+/// before it is written.
+const STREAM_BUFFER_SIZE: usize = 8 * 1024;
+
+/// The Markdown of a document written as it's converted.
+struct MarkdownStream<'a> {
+    write: &'a mut dyn FnMut(&str) -> Result<(), Error>,
+    /// The front matter, written before the first block.
+    front_matter: Option<String>,
+    /// Whether the first block has been written.
+    started: bool,
+    written: usize,
+}
+
+impl MarkdownStream<'_> {
+    fn write(&mut self, markdown: &str) -> Result<(), Error> {
+        let markdown = if self.started {
+            markdown
+        } else {
+            // Like the leading blank lines of the whole document.
+            markdown.trim_start_matches('\n')
+        };
+        if markdown.is_empty() {
+            return Ok(());
+        }
+        if !self.started {
+            self.started = true;
+            if let Some(front_matter) = self.front_matter.take() {
+                self.written += front_matter.len() + 2;
+                (self.write)(&front_matter)?;
+                (self.write)("\n\n")?;
+            }
+        }
+        self.written += markdown.len();
+        (self.write)(markdown)
+    }
+}
+
+fn parse_selector(selector: &str) -> Result<selector::SelectorList, Error> {
+    selector::SelectorList::parse(selector).map_err(|message| Error::InvalidSelector {
+        selector: selector.to_string(),
+        message,
+    })
+}
+
+// Converters are shared between threads, keep them `Send + Sync`.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<HtmlToMarkdown>();
+};
+
 /// The [HtmlToMarkdown] builder for advanced configurations.
 pub struct HtmlToMarkdownBuilder {
     handlers: ElementHandlers,
-    scripting_enabled: bool,
 }
 
 impl Default for HtmlToMarkdownBuilder {
@@ -167,10 +789,7 @@ impl HtmlToMarkdownBuilder {
     pub fn new() -> Self {
         let options = Options::default();
         let handlers = ElementHandlers::new(options);
-        Self {
-            handlers,
-            scripting_enabled: true,
-        }
+        Self { handlers }
     }
 
     /// Set converting options.
@@ -184,6 +803,91 @@ impl HtmlToMarkdownBuilder {
         self.add_handler(tags, |_: &dyn Handlers, _: Element| None)
     }
 
+    /// Skip the elements matching any of these CSS selectors when converting,
+    /// e.g. `[".ad", "#comments", "[aria-hidden=true]"]`. Type, class, id
+    /// and attribute selectors, combinators, and the `:not()`,
+    /// `:first-child`, `:last-child`, `:only-child` and `:empty`
+    /// pseudo-classes are supported.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a selector is invalid, see [`Self::try_skip_selectors`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use htmd::HtmlToMarkdown;
+    ///
+    /// let converter = HtmlToMarkdown::builder()
+    ///     .skip_selectors(["nav", ".ad", "p:empty"])
+    ///     .build();
+    /// let md = converter
+    ///     .convert(r#"<nav>Menu</nav><p>Text</p><div class="ad">Buy</div>"#)
+    ///     .unwrap();
+    /// assert_eq!("Text", md);
+    /// ```
+    pub fn skip_selectors<'a, I>(self, selectors: I) -> Self
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        self.try_skip_selectors(selectors)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like [`Self::skip_selectors`], but returns
+    /// [`Error::InvalidSelector`] if a selector is invalid, e.g. for
+    /// selectors read from a configuration file.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use htmd::{Error, HtmlToMarkdown};
+    ///
+    /// let result = HtmlToMarkdown::builder().try_skip_selectors(["nav", "div >"]);
+    /// assert!(matches!(result, Err(Error::InvalidSelector { .. })));
+    /// ```
+    pub fn try_skip_selectors<'a, I>(mut self, selectors: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        for selector in selectors {
+            self.handlers.skip_selectors.push(parse_selector(selector)?);
+        }
+        Ok(self)
+    }
+
+    /// Convert only the first element matching this CSS selector, in
+    /// document order, e.g. `"article .content"`. The Markdown is empty if no
+    /// element matches. See [`Self::skip_selectors`] for the supported
+    /// selectors.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the selector is invalid, see [`Self::try_select_root`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use htmd::HtmlToMarkdown;
+    ///
+    /// let converter = HtmlToMarkdown::builder()
+    ///     .select_root("article .content")
+    ///     .build();
+    /// let html = r#"<h1>Blog</h1><article><h2>Post</h2><div class="content">Text</div></article>"#;
+    /// assert_eq!("Text", converter.convert(html).unwrap());
+    /// ```
+    pub fn select_root(self, selector: &str) -> Self {
+        self.try_select_root(selector)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like [`Self::select_root`], but returns [`Error::InvalidSelector`] if
+    /// the selector is invalid.
+    pub fn try_select_root(mut self, selector: &str) -> Result<Self, Error> {
+        self.handlers.select_root = Some(parse_selector(selector)?);
+        Ok(self)
+    }
+
     /// Apply a custom element handler for a group of tags.
     ///
     /// # Example
@@ -209,15 +913,147 @@ impl HtmlToMarkdownBuilder {
         self
     }
 
+    /// Resolve relative link and image URLs against this absolute URL. A
+    /// `<base href>` in the document head is resolved against it and takes
+    /// precedence, like in browsers.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use htmd::HtmlToMarkdown;
+    ///
+    /// let converter = HtmlToMarkdown::builder()
+    ///     .base_url("https://en.wikipedia.org/wiki/Rust")
+    ///     .build();
+    /// let md = converter.convert(r#"<a href="/wiki/Foo">Foo</a>"#).unwrap();
+    /// assert_eq!("[Foo](https://en.wikipedia.org/wiki/Foo)", md);
+    /// ```
+    pub fn base_url(mut self, url: &str) -> Self {
+        self.handlers.base_url = Some(url.to_string());
+        self
+    }
+
+    /// Rewrite the destination of links and images. The callback receives
+    /// the URL after it has been resolved against the base URL (see
+    /// [`HtmlToMarkdownBuilder::base_url`]), and before it is escaped. Return
+    /// `None` to drop the link, keeping its content, or the image.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use htmd::{HtmlToMarkdown, element_handler::UrlKind};
+    ///
+    /// let converter = HtmlToMarkdown::builder()
+    ///     .rewrite_url(|kind, url, _element| match kind {
+    ///         UrlKind::Image => Some(url.replace("https://cdn.example.com/", "assets/")),
+    ///         UrlKind::Link if url.starts_with("javascript:") => None,
+    ///         UrlKind::Link => Some(url.to_string()),
+    ///     })
+    ///     .build();
+    /// let md = converter
+    ///     .convert(r#"<img src="https://cdn.example.com/a.png"> <a href="javascript:void(0)">Menu</a>"#)
+    ///     .unwrap();
+    /// assert_eq!("![](assets/a.png) Menu", md);
+    /// ```
+    pub fn rewrite_url<F>(mut self, rewriter: F) -> Self
+    where
+        F: Fn(UrlKind, &str, &Element) -> Option<String> + Send + Sync + 'static,
+    {
+        self.handlers.url_rewriter = Some(Box::new(rewriter));
+        self
+    }
+
+    /// Convert only the main content of pages, leaving out navigation,
+    /// sidebars, banners, footers and other page chrome. The `<main>`
+    /// landmark or the only `<article>` is used if the page has one,
+    /// otherwise the content is found by scoring elements by their amount of
+    /// text, their link density and their class names, like
+    /// [Readability](https://github.com/mozilla/readability).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use htmd::HtmlToMarkdown;
+    ///
+    /// let converter = HtmlToMarkdown::builder()
+    ///     .extract_main_content(true)
+    ///     .build();
+    /// let html = r#"
+    ///     <nav><a href="/">Home</a> <a href="/blog">Blog</a></nav>
+    ///     <main>
+    ///         <h1>Title</h1>
+    ///         <p>Text</p>
+    ///         <div class="share-buttons"><a href="/share">Share</a></div>
+    ///     </main>
+    ///     <footer>Copyright</footer>
+    /// "#;
+    /// assert_eq!("# Title\n\nText", converter.convert(html).unwrap());
+    /// ```
+    pub fn extract_main_content(mut self, enabled: bool) -> Self {
+        self.handlers.extract_main_content = enabled;
+        self
+    }
+
+    /// Choose and rename the fields of the front matter, see
+    /// [`Options::front_matter_style`]. The callback receives the name of
+    /// every field, `title`, `description`, `author`, `keywords`,
+    /// `canonical` or an OpenGraph property like `og:image`, and returns the
+    /// name to write it under, or `None` to leave it out.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use htmd::{
+    ///     HtmlToMarkdown,
+    ///     options::{FrontMatterStyle, Options},
+    /// };
+    ///
+    /// let converter = HtmlToMarkdown::builder()
+    ///     .options(Options {
+    ///         front_matter_style: FrontMatterStyle::Yaml,
+    ///         ..Default::default()
+    ///     })
+    ///     .front_matter_fields(|name| match name {
+    ///         "title" => Some(name.to_string()),
+    ///         "description" => Some("summary".to_string()),
+    ///         _ => None,
+    ///     })
+    ///     .build();
+    /// let html = r#"
+    ///     <title>Hello</title>
+    ///     <meta name="description" content="A greeting">
+    ///     <meta name="generator" content="Hugo">
+    ///     <h1>Hello</h1>
+    /// "#;
+    /// assert_eq!(
+    ///     "---\ntitle: \"Hello\"\nsummary: \"A greeting\"\n---\n\n# Hello",
+    ///     converter.convert(html).unwrap()
+    /// );
+    /// ```
+    pub fn front_matter_fields<F>(mut self, fields: F) -> Self
+    where
+        F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+    {
+        self.handlers.front_matter_fields = Some(Box::new(fields));
+        self
+    }
+
+    /// Set the resource limits of conversions, see [`Limits`]. Only the
+    /// nesting depth is limited by default.
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.handlers.limits = limits;
+        self
+    }
+
     /// Option for html5ever parsing. If true, the content of <noscript> tags will be converted to raw text.
     /// If false, the content of <noscript> tags will be parsed as normal DOM.
     pub fn scripting_enabled(mut self, enabled: bool) -> Self {
-        self.scripting_enabled = enabled;
+        self.handlers.scripting_enabled = enabled;
         self
     }
 
     /// Create a new [HtmlToMarkdown].
     pub fn build(self) -> HtmlToMarkdown {
-        HtmlToMarkdown::from_params(self.handlers, self.scripting_enabled)
+        HtmlToMarkdown::from_params(self.handlers)
     }
 }
@@ -0,0 +1,68 @@
+use std::fmt;
+
+/// Resource limits of a conversion, set with
+/// [`HtmlToMarkdownBuilder::limits`](crate::HtmlToMarkdownBuilder::limits).
+/// Exceeding a limit aborts the conversion with
+/// [`Error::LimitExceeded`](crate::Error::LimitExceeded).
+///
+/// # Example
+///
+/// ```
+/// use htmd::{Error, HtmlToMarkdown, Limit, Limits};
+///
+/// let converter = HtmlToMarkdown::builder()
+///     .limits(Limits {
+///         max_input_bytes: Some(1024 * 1024),
+///         ..Default::default()
+///     })
+///     .build();
+/// let html = "<p>Hi</p>".repeat(200_000);
+/// assert!(matches!(
+///     converter.convert(&html),
+///     Err(Error::LimitExceeded { limit: Limit::InputBytes, .. })
+/// ));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    /// The maximum nesting depth of elements, 256 by default. Elements are
+    /// converted recursively, so this bounds the stack usage: 256 is safe for
+    /// threads with 2 MiB stacks. Without a limit, deeply nested documents
+    /// can overflow the stack.
+    pub max_depth: Option<usize>,
+    /// The maximum size of the HTML in bytes.
+    pub max_input_bytes: Option<usize>,
+    /// The maximum size of the Markdown in bytes.
+    pub max_output_bytes: Option<usize>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_depth: Some(256),
+            max_input_bytes: None,
+            max_output_bytes: None,
+        }
+    }
+}
+
+/// A limit of [`Limits`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
+pub enum Limit {
+    /// [`Limits::max_depth`]
+    Depth,
+    /// [`Limits::max_input_bytes`]
+    InputBytes,
+    /// [`Limits::max_output_bytes`]
+    OutputBytes,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::Depth => write!(f, "nesting depth"),
+            Limit::InputBytes => write!(f, "input size in bytes"),
+            Limit::OutputBytes => write!(f, "output size in bytes"),
+        }
+    }
+}
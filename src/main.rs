@@ -0,0 +1,308 @@
+//! The `htmd` command-line tool, built with the `cli` feature.
+//!
+//! ```sh
+//! # Convert stdin to stdout
+//! curl -s https://example.com | htmd
+//! # Convert files to stdout
+//! htmd page.html other.html
+//! # Convert a directory, writing `out/**/*.md` for every `site/**/*.html`
+//! htmd site --output out
+//! ```
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::Parser;
+use htmd::{
+    HtmlToMarkdown, Limits,
+    options::{
+        BrStyle, BulletListMarker, CodeBlockFence, CodeBlockStyle, FootnoteStyle, FrontMatterStyle,
+        HeadingStyle, HrStyle, LinkReferenceStyle, LinkStyle, MarkdownFlavor, Options,
+        TableSpanStyle, TranslationMode,
+    },
+};
+
+/// Convert HTML to Markdown.
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// HTML files or directories to convert. Reads stdin if empty or `-`.
+    inputs: Vec<PathBuf>,
+
+    /// Write the Markdown of every input to this directory, mirroring the
+    /// directory structure of the inputs, instead of stdout. Fails if two
+    /// inputs would be written to the same file.
+    #[arg(short, long, value_name = "DIR")]
+    output: Option<PathBuf>,
+
+    /// Skip these tags, e.g. `--skip-tags script,style`.
+    #[arg(long, value_delimiter = ',', value_name = "TAGS")]
+    skip_tags: Vec<String>,
+
+    /// The encoding of the inputs, e.g. `shift_jis`. Detected from the BOM
+    /// or `<meta charset>` of every input by default.
+    #[arg(long, value_name = "LABEL")]
+    encoding: Option<String>,
+
+    /// Resolve relative link and image URLs against this URL.
+    #[arg(long, value_name = "URL")]
+    base_url: Option<String>,
+
+    /// Convert only the main content of pages, without navigation, sidebars
+    /// and footers.
+    #[arg(long)]
+    main_content: bool,
+
+    /// The maximum nesting depth of elements, 0 for no limit.
+    #[arg(long, default_value_t = 256, value_name = "N")]
+    max_depth: usize,
+
+    /// Fail on inputs larger than this size in bytes.
+    #[arg(long, value_name = "BYTES")]
+    max_input_bytes: Option<usize>,
+
+    /// Fail if the Markdown of an input is larger than this size in bytes.
+    #[arg(long, value_name = "BYTES")]
+    max_output_bytes: Option<usize>,
+
+    /// Parse the content of `<noscript>` as normal DOM instead of raw text.
+    #[arg(long)]
+    no_scripting: bool,
+
+    #[arg(long, value_enum, default_value_t = HeadingStyle::Atx)]
+    heading_style: HeadingStyle,
+
+    #[arg(long, value_enum, default_value_t = HrStyle::Asterisks)]
+    hr_style: HrStyle,
+
+    #[arg(long, value_enum, default_value_t = BrStyle::TwoSpaces)]
+    br_style: BrStyle,
+
+    #[arg(long, value_enum, default_value_t = LinkStyle::Inlined)]
+    link_style: LinkStyle,
+
+    #[arg(long, value_enum, default_value_t = LinkReferenceStyle::Full)]
+    link_reference_style: LinkReferenceStyle,
+
+    #[arg(long, value_enum, default_value_t = CodeBlockStyle::Fenced)]
+    code_block_style: CodeBlockStyle,
+
+    #[arg(long, value_enum, default_value_t = CodeBlockFence::Backticks)]
+    code_block_fence: CodeBlockFence,
+
+    #[arg(long, value_enum, default_value_t = BulletListMarker::Asterisk)]
+    bullet_list_marker: BulletListMarker,
+
+    /// The number of spaces between the bullet character and the content.
+    #[arg(long, default_value_t = 3, value_name = "N")]
+    ul_bullet_spacing: u8,
+
+    /// The number of spaces between the period character and the content.
+    #[arg(long, default_value_t = 2, value_name = "N")]
+    ol_number_spacing: u8,
+
+    /// Preserve the whitespace in inline `<code>` tags.
+    #[arg(long)]
+    preformatted_code: bool,
+
+    #[arg(long, value_enum, default_value_t = TranslationMode::Pure)]
+    translation_mode: TranslationMode,
+
+    #[arg(long, value_enum, default_value_t = MarkdownFlavor::CommonMark)]
+    flavor: MarkdownFlavor,
+
+    #[arg(long, value_enum, default_value_t = TableSpanStyle::Empty)]
+    table_span_style: TableSpanStyle,
+
+    /// Don't pad table cells to line up the columns.
+    #[arg(long)]
+    no_pad_table_cells: bool,
+
+    #[arg(long, value_enum, default_value_t = FootnoteStyle::Links)]
+    footnote_style: FootnoteStyle,
+
+    /// Skip hidden elements, `<head>`, `<template>`, `<script>`, `<style>`,
+    /// and `<noscript>` unless `--no-scripting` is set.
+    #[arg(long)]
+    skip_hidden_content: bool,
+
+    /// Write the metadata of the `<head>` as front matter.
+    #[arg(long, value_enum, default_value_t = FrontMatterStyle::Disabled)]
+    front_matter_style: FrontMatterStyle,
+}
+
+impl Cli {
+    fn converter(&self) -> HtmlToMarkdown {
+        let options = Options {
+            heading_style: self.heading_style,
+            hr_style: self.hr_style,
+            br_style: self.br_style,
+            link_style: self.link_style,
+            link_reference_style: self.link_reference_style,
+            code_block_style: self.code_block_style,
+            code_block_fence: self.code_block_fence,
+            bullet_list_marker: self.bullet_list_marker,
+            ul_bullet_spacing: self.ul_bullet_spacing,
+            ol_number_spacing: self.ol_number_spacing,
+            preformatted_code: self.preformatted_code,
+            translation_mode: self.translation_mode,
+            flavor: self.flavor,
+            table_span_style: self.table_span_style,
+            pad_table_cells: !self.no_pad_table_cells,
+            footnote_style: self.footnote_style,
+            skip_hidden_content: self.skip_hidden_content,
+            front_matter_style: self.front_matter_style,
+        };
+        let mut builder = HtmlToMarkdown::builder()
+            .options(options)
+            .limits(Limits {
+                max_depth: (self.max_depth > 0).then_some(self.max_depth),
+                max_input_bytes: self.max_input_bytes,
+                max_output_bytes: self.max_output_bytes,
+            })
+            .extract_main_content(self.main_content)
+            .scripting_enabled(!self.no_scripting);
+        if !self.skip_tags.is_empty() {
+            builder = builder.skip_tags(self.skip_tags.iter().map(String::as_str).collect());
+        }
+        if let Some(base_url) = &self.base_url {
+            builder = builder.base_url(base_url);
+        }
+        builder.build()
+    }
+}
+
+/// An HTML file to convert, and the path of its Markdown file relative to the
+/// output directory.
+struct Input {
+    path: PathBuf,
+    relative_output: PathBuf,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(&cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("htmd: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: &Cli) -> io::Result<()> {
+    let converter = cli.converter();
+
+    let stdin = [PathBuf::from("-")];
+    let paths = if cli.inputs.is_empty() {
+        &stdin[..]
+    } else {
+        &cli.inputs[..]
+    };
+
+    let mut inputs = vec![];
+    for path in paths {
+        if path.is_dir() {
+            collect_html_files(path, path, &mut inputs)?;
+        } else if path.as_os_str() == "-" {
+            inputs.push(Input {
+                path: path.clone(),
+                relative_output: PathBuf::from("stdin.md"),
+            });
+        } else {
+            let file_name = path.file_name().map(PathBuf::from).unwrap_or_default();
+            inputs.push(Input {
+                path: path.clone(),
+                relative_output: file_name.with_extension("md"),
+            });
+        }
+    }
+
+    if cli.output.is_some() {
+        check_unique_outputs(&inputs)?;
+    }
+
+    let mut stdout = io::stdout().lock();
+    for (index, input) in inputs.iter().enumerate() {
+        let html = read_input(&input.path)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", input.path.display())))?;
+        let md = converter
+            .convert_bytes(&html, cli.encoding.as_deref())
+            .map_err(|e| io::Error::other(format!("{}: {e}", input.path.display())))?;
+        match &cli.output {
+            Some(dir) => write_file(&dir.join(&input.relative_output), &md)?,
+            None => {
+                if index > 0 {
+                    writeln!(stdout)?;
+                }
+                writeln!(stdout, "{md}")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Fail if two inputs would be written to the same Markdown file, e.g.
+/// `a/index.html` and `b/index.html`, before anything is written.
+fn check_unique_outputs(inputs: &[Input]) -> io::Result<()> {
+    let mut outputs: HashMap<&Path, &Path> = HashMap::new();
+    for input in inputs {
+        if let Some(other) = outputs.insert(&input.relative_output, &input.path) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!(
+                    "{} and {} would both be written to {}",
+                    other.display(),
+                    input.path.display(),
+                    input.relative_output.display()
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Recursively collect the `.html` and `.htm` files in `dir`, sorted by path.
+fn collect_html_files(root: &Path, dir: &Path, inputs: &mut Vec<Input>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|it| it.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            collect_html_files(root, &path, inputs)?;
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm"))
+        {
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            inputs.push(Input {
+                relative_output: relative.with_extension("md"),
+                path,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn read_input(path: &Path) -> io::Result<Vec<u8>> {
+    if path.as_os_str() == "-" {
+        let mut html = vec![];
+        io::stdin().read_to_end(&mut html)?;
+        Ok(html)
+    } else {
+        fs::read(path)
+    }
+}
+
+fn write_file(path: &Path, md: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, md)
+}
@@ -0,0 +1,120 @@
+use super::{Align, Node};
+
+/// Write `node` as mdast JSON, with the fields in the order of the mdast
+/// spec and `null` for absent optional fields.
+pub(super) fn write_node(output: &mut String, node: &Node) {
+    output.push_str("{\"type\":");
+    write_string(output, node.node_type());
+    match node {
+        Node::Heading { depth, .. } => {
+            output.push_str(",\"depth\":");
+            output.push_str(&depth.to_string());
+        }
+        Node::List {
+            ordered,
+            start,
+            spread,
+            ..
+        } => {
+            output.push_str(",\"ordered\":");
+            output.push_str(if *ordered { "true" } else { "false" });
+            output.push_str(",\"start\":");
+            match start {
+                Some(start) => output.push_str(&start.to_string()),
+                None => output.push_str("null"),
+            }
+            output.push_str(",\"spread\":");
+            output.push_str(if *spread { "true" } else { "false" });
+        }
+        Node::ListItem {
+            checked, spread, ..
+        } => {
+            output.push_str(",\"checked\":");
+            match checked {
+                Some(true) => output.push_str("true"),
+                Some(false) => output.push_str("false"),
+                None => output.push_str("null"),
+            }
+            output.push_str(",\"spread\":");
+            output.push_str(if *spread { "true" } else { "false" });
+        }
+        Node::Code { lang, value } => {
+            write_field(output, "lang", lang.as_deref());
+            output.push_str(",\"meta\":null");
+            write_field(output, "value", Some(value));
+        }
+        Node::Table { align, .. } => {
+            output.push_str(",\"align\":[");
+            for (index, align) in align.iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                match align {
+                    Some(Align::Left) => output.push_str("\"left\""),
+                    Some(Align::Right) => output.push_str("\"right\""),
+                    Some(Align::Center) => output.push_str("\"center\""),
+                    None => output.push_str("null"),
+                }
+            }
+            output.push(']');
+        }
+        Node::Html { value }
+        | Node::Text { value }
+        | Node::InlineCode { value }
+        | Node::Yaml { value }
+        | Node::Toml { value } => {
+            write_field(output, "value", Some(value));
+        }
+        Node::Link { url, title, .. } => {
+            write_field(output, "url", Some(url));
+            write_field(output, "title", title.as_deref());
+        }
+        Node::Image { url, title, alt } => {
+            write_field(output, "url", Some(url));
+            write_field(output, "title", title.as_deref());
+            write_field(output, "alt", alt.as_deref());
+        }
+        Node::FootnoteReference { identifier } | Node::FootnoteDefinition { identifier, .. } => {
+            write_field(output, "identifier", Some(identifier));
+            write_field(output, "label", Some(identifier));
+        }
+        _ => {}
+    }
+    if node.is_parent() {
+        output.push_str(",\"children\":[");
+        for (index, child) in node.children().iter().enumerate() {
+            if index > 0 {
+                output.push(',');
+            }
+            write_node(output, child);
+        }
+        output.push(']');
+    }
+    output.push('}');
+}
+
+fn write_field(output: &mut String, name: &str, value: Option<&str>) {
+    output.push_str(",\"");
+    output.push_str(name);
+    output.push_str("\":");
+    match value {
+        Some(value) => write_string(output, value),
+        None => output.push_str("null"),
+    }
+}
+
+fn write_string(output: &mut String, text: &str) {
+    output.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            _ if ch.is_control() => output.push_str(&format!("\\u{:04x}", ch as u32)),
+            _ => output.push(ch),
+        }
+    }
+    output.push('"');
+}
@@ -0,0 +1,298 @@
+//! A Markdown syntax tree compatible with [mdast](https://github.com/syntax-tree/mdast),
+//! see [`HtmlToMarkdown::convert_to_mdast`](crate::HtmlToMarkdown::convert_to_mdast).
+//!
+//! The tree can be inspected and transformed, then serialized to mdast JSON
+//! with [`Node::to_json`] or rendered to Markdown with [`Node::to_markdown`].
+//!
+//! # Example
+//!
+//! ```
+//! use htmd::{HtmlToMarkdown, mdast::Node, options::Options};
+//!
+//! let mut root = HtmlToMarkdown::new().convert_to_mdast("<h1>Hello</h1>").unwrap();
+//! if let Node::Root { children } = &mut root {
+//!     children.push(Node::Paragraph {
+//!         children: vec![Node::Text { value: "World".to_string() }],
+//!     });
+//! }
+//! assert_eq!("# Hello\n\nWorld", root.to_markdown(&Options::default()));
+//! assert_eq!(
+//!     r#"{"type":"root","children":[{"type":"heading","depth":1,"children":[{"type":"text","value":"Hello"}]},{"type":"paragraph","children":[{"type":"text","value":"World"}]}]}"#,
+//!     root.to_json()
+//! );
+//! ```
+
+mod json;
+mod render;
+mod tree;
+
+pub(crate) use render::Renderer;
+pub(crate) use tree::{TreeState, flow, is_building, map_nodes, set_nodes, take_nodes};
+
+use crate::options::Options;
+
+/// A node of the Markdown syntax tree. Positions are not included.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Node {
+    /// The document.
+    Root {
+        children: Vec<Node>,
+    },
+    Paragraph {
+        children: Vec<Node>,
+    },
+    Heading {
+        /// From 1 for `<h1>` to 6 for `<h6>`.
+        depth: u8,
+        children: Vec<Node>,
+    },
+    ThematicBreak,
+    Blockquote {
+        children: Vec<Node>,
+    },
+    List {
+        ordered: bool,
+        /// The number of the first item of an ordered list.
+        start: Option<u32>,
+        /// Whether the items are separated by blank lines.
+        spread: bool,
+        children: Vec<Node>,
+    },
+    ListItem {
+        /// The state of the checkbox of a task list item.
+        checked: Option<bool>,
+        /// Whether the children are separated by blank lines.
+        spread: bool,
+        children: Vec<Node>,
+    },
+    Code {
+        lang: Option<String>,
+        value: String,
+    },
+    /// Raw HTML, or the Markdown of an element converted by a custom handler.
+    Html {
+        value: String,
+    },
+    Table {
+        /// The alignment of each column.
+        align: Vec<Option<Align>>,
+        /// The rows, the first one is the header row.
+        children: Vec<Node>,
+    },
+    TableRow {
+        children: Vec<Node>,
+    },
+    TableCell {
+        children: Vec<Node>,
+    },
+    Text {
+        value: String,
+    },
+    Emphasis {
+        children: Vec<Node>,
+    },
+    Strong {
+        children: Vec<Node>,
+    },
+    Delete {
+        children: Vec<Node>,
+    },
+    InlineCode {
+        value: String,
+    },
+    Break,
+    Link {
+        url: String,
+        title: Option<String>,
+        children: Vec<Node>,
+    },
+    Image {
+        url: String,
+        title: Option<String>,
+        alt: Option<String>,
+    },
+    /// A reference to a footnote, see
+    /// [`FootnoteStyle::Markdown`](crate::options::FootnoteStyle::Markdown).
+    FootnoteReference {
+        /// The number of the footnote.
+        identifier: String,
+    },
+    /// The content of a footnote, at the end of the document.
+    FootnoteDefinition {
+        identifier: String,
+        children: Vec<Node>,
+    },
+    /// YAML front matter, without the `---` lines, see
+    /// [`FrontMatterStyle`](crate::options::FrontMatterStyle).
+    Yaml {
+        value: String,
+    },
+    /// TOML front matter, without the `+++` lines.
+    Toml {
+        value: String,
+    },
+}
+
+/// The alignment of a table column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+impl Node {
+    /// The mdast type of the node, e.g. `"heading"`.
+    pub fn node_type(&self) -> &'static str {
+        match self {
+            Node::Root { .. } => "root",
+            Node::Paragraph { .. } => "paragraph",
+            Node::Heading { .. } => "heading",
+            Node::ThematicBreak => "thematicBreak",
+            Node::Blockquote { .. } => "blockquote",
+            Node::List { .. } => "list",
+            Node::ListItem { .. } => "listItem",
+            Node::Code { .. } => "code",
+            Node::Html { .. } => "html",
+            Node::Table { .. } => "table",
+            Node::TableRow { .. } => "tableRow",
+            Node::TableCell { .. } => "tableCell",
+            Node::Text { .. } => "text",
+            Node::Emphasis { .. } => "emphasis",
+            Node::Strong { .. } => "strong",
+            Node::Delete { .. } => "delete",
+            Node::InlineCode { .. } => "inlineCode",
+            Node::Break => "break",
+            Node::Link { .. } => "link",
+            Node::Image { .. } => "image",
+            Node::FootnoteReference { .. } => "footnoteReference",
+            Node::FootnoteDefinition { .. } => "footnoteDefinition",
+            Node::Yaml { .. } => "yaml",
+            Node::Toml { .. } => "toml",
+        }
+    }
+
+    /// The children of the node, empty for leaf nodes.
+    pub fn children(&self) -> &[Node] {
+        match self {
+            Node::Root { children }
+            | Node::Paragraph { children }
+            | Node::Heading { children, .. }
+            | Node::Blockquote { children }
+            | Node::List { children, .. }
+            | Node::ListItem { children, .. }
+            | Node::Table { children, .. }
+            | Node::TableRow { children }
+            | Node::TableCell { children }
+            | Node::Emphasis { children }
+            | Node::Strong { children }
+            | Node::Delete { children }
+            | Node::Link { children, .. }
+            | Node::FootnoteDefinition { children, .. } => children,
+            _ => &[],
+        }
+    }
+
+    /// The mutable children of the node, `None` for leaf nodes.
+    pub fn children_mut(&mut self) -> Option<&mut Vec<Node>> {
+        match self {
+            Node::Root { children }
+            | Node::Paragraph { children }
+            | Node::Heading { children, .. }
+            | Node::Blockquote { children }
+            | Node::List { children, .. }
+            | Node::ListItem { children, .. }
+            | Node::Table { children, .. }
+            | Node::TableRow { children }
+            | Node::TableCell { children }
+            | Node::Emphasis { children }
+            | Node::Strong { children }
+            | Node::Delete { children }
+            | Node::Link { children, .. }
+            | Node::FootnoteDefinition { children, .. } => Some(children),
+            Node::ThematicBreak
+            | Node::Code { .. }
+            | Node::Html { .. }
+            | Node::Text { .. }
+            | Node::InlineCode { .. }
+            | Node::Break
+            | Node::Image { .. }
+            | Node::FootnoteReference { .. }
+            | Node::Yaml { .. }
+            | Node::Toml { .. } => None,
+        }
+    }
+
+    /// Whether the node has children, even if there are none.
+    pub fn is_parent(&self) -> bool {
+        !matches!(
+            self,
+            Node::ThematicBreak
+                | Node::Code { .. }
+                | Node::Html { .. }
+                | Node::Text { .. }
+                | Node::InlineCode { .. }
+                | Node::Break
+                | Node::Image { .. }
+                | Node::FootnoteReference { .. }
+                | Node::Yaml { .. }
+                | Node::Toml { .. }
+        )
+    }
+
+    /// Whether the node is phrasing content, which is part of a paragraph.
+    /// HTML can be either.
+    pub(crate) fn is_phrasing(&self) -> bool {
+        matches!(
+            self,
+            Node::Text { .. }
+                | Node::Emphasis { .. }
+                | Node::Strong { .. }
+                | Node::Delete { .. }
+                | Node::InlineCode { .. }
+                | Node::Break
+                | Node::Link { .. }
+                | Node::Image { .. }
+                | Node::Html { .. }
+                | Node::FootnoteReference { .. }
+        )
+    }
+
+    /// The text content of the node and its descendants, without any
+    /// Markdown syntax.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        self.push_text(&mut text);
+        text
+    }
+
+    fn push_text(&self, text: &mut String) {
+        match self {
+            Node::Text { value } | Node::InlineCode { value } | Node::Code { value, .. } => {
+                text.push_str(value)
+            }
+            Node::Image { alt, .. } => text.push_str(alt.as_deref().unwrap_or_default()),
+            Node::Break => text.push('\n'),
+            Node::Yaml { .. } | Node::Toml { .. } => {}
+            _ => {
+                for child in self.children() {
+                    child.push_text(text);
+                }
+            }
+        }
+    }
+
+    /// Serialize the node to mdast JSON.
+    pub fn to_json(&self) -> String {
+        let mut output = String::new();
+        json::write_node(&mut output, self);
+        output
+    }
+
+    /// Render the node to Markdown in the styles of `options`. Only the
+    /// Markdown options are used, e.g. `heading_style` and `link_style`.
+    pub fn to_markdown(&self, options: &Options) -> String {
+        render::render(self, options)
+    }
+}
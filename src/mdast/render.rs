@@ -0,0 +1,398 @@
+use std::{borrow::Cow, slice};
+
+use super::{Align, Node};
+use crate::{
+    dom_walker::{append_normalized_content, escape_if_needed, trim_output_end_spaces},
+    element_handler::{
+        anchor::{LinkReferences, format_link},
+        code::{format_code_block, format_inline_code},
+        img::format_image,
+        list::add_ol_li_marker,
+        table::{ColumnAlignment, format_table, normalize_cell_content},
+    },
+    options::{BrStyle, BulletListMarker, HeadingStyle, HrStyle, MarkdownFlavor, Options},
+    text_util::{
+        JoinOnStringIterator, StripWhitespace, TrimDocumentWhitespace, concat_strings,
+        indent_text_except_first_line, join_blocks,
+    },
+};
+
+/// Render `node` to Markdown, like the converter renders the HTML elements
+/// the nodes come from.
+pub(super) fn render(node: &Node, options: &Options) -> String {
+    let children = match node {
+        Node::Root { children } => children.as_slice(),
+        _ => slice::from_ref(node),
+    };
+    let (front_matter, children) = match children.split_first() {
+        Some((Node::Yaml { value }, children)) => (Some(("---", value)), children),
+        Some((Node::Toml { value }, children)) => (Some(("+++", value)), children),
+        _ => (None, children),
+    };
+    // The footnote definitions are appended after the link references.
+    let body_len = children
+        .iter()
+        .rposition(|node| !matches!(node, Node::FootnoteDefinition { .. }))
+        .map_or(0, |index| index + 1);
+    let (children, footnotes) = children.split_at(body_len);
+
+    let mut references = LinkReferences::default();
+    let mut renderer = Renderer {
+        options,
+        references: &mut references,
+    };
+    let mut markdown = renderer.children(children, Parent::Flow);
+    let footnotes = renderer.footnote_definitions(footnotes);
+
+    markdown.truncate(markdown.trim_end_matches('\n').len());
+    let leading_new_lines = markdown.len() - markdown.trim_start_matches('\n').len();
+    markdown.drain(..leading_new_lines);
+    if let Some((delimiter, value)) = front_matter {
+        if !markdown.is_empty() {
+            markdown.insert_str(0, "\n\n");
+        }
+        markdown.insert_str(0, &concat_strings!(delimiter, "\n", value, "\n", delimiter));
+    }
+    let appended: Vec<String> = references
+        .definitions()
+        .into_iter()
+        .chain(footnotes)
+        .collect();
+    let appended = join_blocks(&appended);
+    markdown.push_str(appended.trim_end_matches('\n'));
+    markdown
+}
+
+/// Where nodes are rendered, which decides how their blocks are separated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Parent {
+    /// Blocks, e.g. in the document or a block quote.
+    Flow,
+    /// Inline content, e.g. in a paragraph or a link.
+    Phrasing,
+    /// The blocks of a list item. The paragraphs of a tight item are not
+    /// separated by blank lines.
+    ListItem { spread: bool },
+    /// The items of a list.
+    List { ordered: bool },
+}
+
+/// Renders nodes to the Markdown of the handlers of the elements they come
+/// from, see [`HandlerResult::nodes`](crate::element_handler::HandlerResult::nodes).
+pub(crate) struct Renderer<'a> {
+    pub(crate) options: &'a Options,
+    pub(crate) references: &'a mut LinkReferences,
+}
+
+impl Renderer<'_> {
+    /// Render sibling nodes, joined like the converter joins the Markdown of
+    /// sibling elements.
+    pub(crate) fn children(&mut self, nodes: &[Node], parent: Parent) -> String {
+        let mut output = String::new();
+        for node in nodes {
+            let content = self.content(node, parent);
+            if let Node::Text { .. } = node {
+                output.push_str(&content);
+                continue;
+            }
+            if parent != Parent::Phrasing || !node.is_phrasing() {
+                // Trailing spaces are trimmed before blocks.
+                trim_output_end_spaces(&mut output);
+            }
+            append_normalized_content(&mut output, content, false);
+        }
+        output
+    }
+
+    /// Render a node like its handler, with the line breaks separating it
+    /// from its siblings.
+    fn content(&mut self, node: &Node, parent: Parent) -> String {
+        let is_gfm = self.options.flavor == MarkdownFlavor::Gfm;
+        match node {
+            Node::Root { children } => self.children(children, Parent::Flow),
+            Node::Paragraph { children } => {
+                let content = self.children(children, Parent::Phrasing);
+                let content = content.trim_matches('\n');
+                if parent == (Parent::ListItem { spread: false }) {
+                    content.to_string()
+                } else {
+                    concat_strings!("\n\n", content, "\n\n")
+                }
+            }
+            Node::Heading { depth, children } => self.heading(*depth, children),
+            Node::ThematicBreak => match self.options.hr_style {
+                HrStyle::Dashes => "\n\n- - -\n\n",
+                HrStyle::Asterisks => "\n\n* * *\n\n",
+                HrStyle::Underscores => "\n\n_ _ _\n\n",
+            }
+            .to_string(),
+            Node::Blockquote { children } => {
+                let content = self.children(children, Parent::Flow);
+                let content = content
+                    .trim_start_matches('\n')
+                    .trim_end_document_whitespace()
+                    .lines()
+                    .map(|line| concat_strings!("> ", line))
+                    .join("\n");
+                concat_strings!("\n\n", content, "\n\n")
+            }
+            Node::List {
+                ordered,
+                start,
+                spread,
+                children,
+            } => self.list(*ordered, *start, *spread, children, parent),
+            Node::ListItem {
+                checked,
+                spread,
+                children,
+            } => {
+                let is_ordered = parent == Parent::List { ordered: true };
+                self.list_item(*checked, *spread, children, is_ordered)
+            }
+            Node::Code { lang, value } => {
+                let code = format_code_block(self.options, value, lang.as_deref());
+                concat_strings!("\n\n", code, "\n\n")
+            }
+            Node::Html { value } => {
+                if parent == Parent::Phrasing {
+                    value.clone()
+                } else {
+                    concat_strings!("\n\n", value, "\n\n")
+                }
+            }
+            Node::Table { align, children } => self.table(align, children),
+            Node::TableRow { children } | Node::TableCell { children } => {
+                self.children(children, Parent::Phrasing)
+            }
+            Node::Text { value } => escape_if_needed(Cow::Borrowed(value), is_gfm).into_owned(),
+            Node::Emphasis { children } => self.emphasis(children, "*"),
+            Node::Strong { children } => self.emphasis(children, "**"),
+            Node::Delete { children } => {
+                if is_gfm {
+                    self.emphasis(children, "~~")
+                } else {
+                    // Strikethrough is not part of CommonMark.
+                    self.children(children, Parent::Phrasing)
+                }
+            }
+            Node::InlineCode { value } => format_inline_code(self.options, value),
+            Node::Break => match self.options.br_style {
+                BrStyle::TwoSpaces => "  \n",
+                BrStyle::Backslash => "\\\n",
+            }
+            .to_string(),
+            Node::Link {
+                url,
+                title,
+                children,
+            } => {
+                let content = self.children(children, Parent::Phrasing);
+                format_link(
+                    self.options,
+                    self.references,
+                    &content,
+                    url,
+                    title.as_deref(),
+                )
+            }
+            Node::Image { url, title, alt } => format_image(url, alt.clone(), title.clone()),
+            Node::FootnoteReference { identifier } => concat_strings!("[^", identifier, "]"),
+            Node::FootnoteDefinition { .. } => {
+                let definition = self.footnote_definitions(slice::from_ref(node));
+                definition.unwrap_or_default()
+            }
+            Node::Yaml { value } => concat_strings!("\n\n---\n", value, "\n---\n\n"),
+            Node::Toml { value } => concat_strings!("\n\n+++\n", value, "\n+++\n\n"),
+        }
+    }
+
+    fn heading(&mut self, depth: u8, children: &[Node]) -> String {
+        let content = self.children(children, Parent::Phrasing);
+        let content = content.trim_document_whitespace();
+        let content = content.trim_matches('\n');
+        if (depth == 1 || depth == 2) && self.options.heading_style == HeadingStyle::Setex {
+            let underline = if depth == 1 { "=" } else { "-" };
+            let underline = underline.repeat(content.chars().count());
+            concat_strings!("\n\n", content, "\n", underline, "\n\n")
+        } else {
+            let depth = "#".repeat(depth.clamp(1, 6).into());
+            concat_strings!("\n\n", depth, " ", content, "\n\n")
+        }
+    }
+
+    fn list(
+        &mut self,
+        ordered: bool,
+        start: Option<u32>,
+        spread: bool,
+        items: &[Node],
+        parent: Parent,
+    ) -> String {
+        let item_parent = Parent::List { ordered };
+        let content = if ordered {
+            let start = start.unwrap_or(1).max(1) as usize;
+            let item_count = items
+                .iter()
+                .filter(|item| matches!(item, Node::ListItem { .. }))
+                .count();
+            let highest_number = start + item_count - 1;
+            let mut number = start - 1;
+            let contents: Vec<String> = items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| {
+                    let content = self.content(item, item_parent);
+                    let mut content = if let Node::ListItem { .. } = item {
+                        number += 1;
+                        add_ol_li_marker(self.options, &content, number, highest_number)
+                    } else {
+                        content
+                    };
+                    if spread && index > 0 {
+                        content.insert_str(0, "\n\n");
+                    }
+                    content
+                })
+                .collect();
+            join_blocks(&contents)
+        } else {
+            let mut output = String::new();
+            for (index, item) in items.iter().enumerate() {
+                let mut content = self.content(item, item_parent);
+                if spread && index > 0 {
+                    content.insert_str(0, "\n\n");
+                }
+                trim_output_end_spaces(&mut output);
+                append_normalized_content(&mut output, content, false);
+            }
+            output
+        };
+        let content = content.trim_matches('\n');
+        if content.is_empty() {
+            String::new()
+        } else if let Parent::ListItem { .. } = parent {
+            concat_strings!("\n", content, "\n")
+        } else {
+            concat_strings!("\n\n", content, "\n\n")
+        }
+    }
+
+    fn list_item(
+        &mut self,
+        checked: Option<bool>,
+        spread: bool,
+        children: &[Node],
+        is_ordered: bool,
+    ) -> String {
+        let content = self.children(children, Parent::ListItem { spread });
+        let content = content.trim_start_document_whitespace();
+        let content = match checked {
+            Some(checked) if self.options.flavor == MarkdownFlavor::Gfm => {
+                let marker = if checked { "[x]" } else { "[ ]" };
+                if content.is_empty() {
+                    marker.to_string()
+                } else {
+                    concat_strings!(marker, " ", content)
+                }
+            }
+            _ => content.to_string(),
+        };
+        if is_ordered {
+            // The marker is added by the list.
+            return concat_strings!("\n", content, "\n");
+        }
+        let marker = match self.options.bullet_list_marker {
+            BulletListMarker::Asterisk => "*",
+            BulletListMarker::Dash => "-",
+        };
+        let spacing = " ".repeat(self.options.ul_bullet_spacing.into());
+        let content = indent_text_except_first_line(&content, marker.len() + spacing.len(), true);
+        concat_strings!("\n", marker, spacing, content)
+    }
+
+    /// Render a table like the table handler, with the first row as the
+    /// header row.
+    fn table(&mut self, align: &[Option<Align>], rows: &[Node]) -> String {
+        let mut rows: Vec<Vec<String>> = rows
+            .iter()
+            .map(|row| {
+                row.children()
+                    .iter()
+                    .map(|cell| {
+                        let content = self.children(cell.children(), Parent::Phrasing);
+                        normalize_cell_content(content.trim_document_whitespace())
+                    })
+                    .collect()
+            })
+            .collect();
+        let columns = if align.is_empty() {
+            rows.iter().map(Vec::len).max().unwrap_or_default()
+        } else {
+            align.len()
+        };
+        if columns == 0 {
+            return String::new();
+        }
+        let alignments: Vec<Option<ColumnAlignment>> = (0..columns)
+            .map(|index| {
+                align
+                    .get(index)
+                    .copied()
+                    .flatten()
+                    .map(|align| match align {
+                        Align::Left => ColumnAlignment::Left,
+                        Align::Right => ColumnAlignment::Right,
+                        Align::Center => ColumnAlignment::Center,
+                    })
+            })
+            .collect();
+        let headers = rows.remove(0);
+        let table = format_table(self.options, &headers, &rows, &alignments);
+        concat_strings!("\n\n", table, "\n")
+    }
+
+    /// Wrap content in emphasis markers, keeping the surrounding whitespace
+    /// outside of them.
+    fn emphasis(&mut self, children: &[Node], marker: &str) -> String {
+        let content = self.children(children, Parent::Phrasing);
+        let (content, leading_whitespace) = content.strip_leading_whitespace();
+        let (content, trailing_whitespace) = content.strip_trailing_whitespace();
+        if content.is_empty() {
+            return String::new();
+        }
+        concat_strings!(
+            leading_whitespace.unwrap_or(""),
+            marker,
+            content,
+            marker,
+            trailing_whitespace.unwrap_or("")
+        )
+    }
+
+    /// Render footnote definitions like they are appended to the document.
+    fn footnote_definitions(&mut self, definitions: &[Node]) -> Option<String> {
+        if definitions.is_empty() {
+            return None;
+        }
+        let mut result = String::from("\n\n");
+        for (index, definition) in definitions.iter().enumerate() {
+            let Node::FootnoteDefinition {
+                identifier,
+                children,
+            } = definition
+            else {
+                continue;
+            };
+            if index > 0 {
+                result.push('\n');
+            }
+            let content = self.children(children, Parent::Flow);
+            let content =
+                indent_text_except_first_line(content.trim_document_whitespace(), 4, true);
+            result.push_str(&concat_strings!("[^", identifier, "]: ", content));
+        }
+        result.push_str("\n\n");
+        Some(result)
+    }
+}
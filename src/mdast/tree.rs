@@ -0,0 +1,519 @@
+use std::{mem, ops::Range};
+
+use super::{Node, render::Parent};
+use crate::{
+    dom_walker::{append_normalized_content, escape_if_needed},
+    element_handler::{ConversionContext, Handlers, anchor::LinkReferences},
+    mdast::Renderer,
+    options::MarkdownFlavor,
+    text_util::{TrimDocumentWhitespace, concat_strings},
+};
+
+/// The nodes of the Markdown written while building a syntax tree.
+///
+/// The handlers pass the nodes of their Markdown along with it, see
+/// [`set_nodes`]. The nodes of each element are rendered and checked against
+/// its Markdown, an element whose nodes are missing or render differently is
+/// added to the tree as an [`Node::Html`] node with its Markdown.
+#[derive(Default)]
+pub(crate) struct TreeState {
+    /// The nodes of the Markdown returned by the last handler, or by the last
+    /// call of [`Handlers::walk_children`] or [`Handlers::handle`].
+    nodes: Option<Vec<Node>>,
+    /// The buffers being written, innermost last.
+    frames: Vec<Frame>,
+    /// The link references of the nodes checked so far, numbered like the
+    /// converter numbers them.
+    references: LinkReferences,
+    /// The front matter and the nodes of the document.
+    root: Vec<Node>,
+    /// The footnote definitions, appended to the document.
+    pub(crate) footnotes: Vec<Node>,
+}
+
+/// The nodes appended to a buffer.
+#[derive(Default)]
+struct Frame {
+    items: Vec<Item>,
+    /// Whether the buffer holds the children of a list item, whose blocks are
+    /// not separated by blank lines if the item is tight.
+    is_list_item: bool,
+}
+
+/// Nodes appended to a buffer.
+#[derive(Clone)]
+struct Item {
+    nodes: Vec<Node>,
+    /// The Markdown of the nodes in the buffer.
+    range: Range<usize>,
+    /// Whether the Markdown is a block, separated from the previous content
+    /// by a line break.
+    is_block: bool,
+}
+
+/// A block of the nodes of a buffer, see [`TreeState::leave_output`].
+struct Segment {
+    nodes: Vec<Node>,
+    range: Range<usize>,
+    /// Whether the nodes were not checked when their item was appended.
+    is_merged: bool,
+}
+
+/// Whether the handlers pass the nodes of their Markdown along, see
+/// [`set_nodes`].
+pub(crate) fn is_building(handlers: &dyn Handlers) -> bool {
+    handlers.state().contains::<TreeState>()
+}
+
+/// Set the nodes of the Markdown returned by the handler of the element being
+/// converted. A handler that sets no nodes returns the nodes of the children
+/// it walked last, see [`map_nodes`].
+pub(crate) fn set_nodes(handlers: &dyn Handlers, nodes: impl FnOnce() -> Vec<Node>) {
+    if is_building(handlers) {
+        handlers.state().get::<TreeState>().borrow_mut().nodes = Some(nodes());
+    }
+}
+
+/// Turn the nodes of the children walked last into the nodes of the element
+/// being converted.
+pub(crate) fn map_nodes(handlers: &dyn Handlers, map: impl FnOnce(Vec<Node>) -> Vec<Node>) {
+    if is_building(handlers) {
+        let state = handlers.state().get::<TreeState>();
+        let nodes = state.borrow_mut().nodes.take();
+        state.borrow_mut().nodes = nodes.map(map);
+    }
+}
+
+/// Take the nodes of the children walked last, e.g. to combine the nodes of
+/// several calls of [`Handlers::handle`].
+pub(crate) fn take_nodes(handlers: &dyn Handlers) -> Option<Vec<Node>> {
+    if !is_building(handlers) {
+        return None;
+    }
+    handlers
+        .state()
+        .get::<TreeState>()
+        .borrow_mut()
+        .nodes
+        .take()
+}
+
+/// The nodes of a block container, with the inline content wrapped in a
+/// paragraph.
+pub(crate) fn flow(nodes: Vec<Node>) -> Vec<Node> {
+    if nodes.is_empty() || is_flow(&nodes) || matches!(nodes.as_slice(), [Node::Html { .. }]) {
+        nodes
+    } else {
+        vec![Node::Paragraph { children: nodes }]
+    }
+}
+
+/// Whether `nodes` are blocks rather than inline content. A sequence of HTML
+/// nodes is a sequence of blocks, adjacent inline HTML is merged.
+pub(crate) fn is_flow(nodes: &[Node]) -> bool {
+    nodes.iter().any(|node| !node.is_phrasing())
+        || (nodes.len() > 1 && nodes.iter().all(|node| matches!(node, Node::Html { .. })))
+}
+
+impl TreeState {
+    /// Start building the tree of a conversion.
+    pub(crate) fn start(context: &ConversionContext) {
+        context
+            .state()
+            .get::<TreeState>()
+            .borrow_mut()
+            .frames
+            .push(Frame::default());
+    }
+
+    /// The nodes of the document, followed by the footnote definitions.
+    pub(crate) fn finish(context: &ConversionContext) -> Vec<Node> {
+        let state = context.state().get::<TreeState>();
+        let mut state = state.borrow_mut();
+        let mut nodes = mem::take(&mut state.root);
+        nodes.append(&mut state.footnotes);
+        nodes
+    }
+
+    /// Add the front matter written before the document.
+    pub(crate) fn front_matter(context: &ConversionContext, front_matter: &str) {
+        let node = match front_matter.strip_prefix("+++\n") {
+            Some(value) => Node::Toml {
+                value: value.strip_suffix("\n+++").unwrap_or(value).to_string(),
+            },
+            None => {
+                let value = front_matter.strip_prefix("---\n").unwrap_or(front_matter);
+                Node::Yaml {
+                    value: value.strip_suffix("\n---").unwrap_or(value).to_string(),
+                }
+            }
+        };
+        let state = context.state().get::<TreeState>();
+        state.borrow_mut().root.insert(0, node);
+    }
+
+    /// Start a new buffer, whose Markdown is returned to the handler of the
+    /// element being converted. `is_list_item` tells whether the buffer holds
+    /// the children of a list item.
+    pub(crate) fn enter_output(context: &ConversionContext, is_list_item: bool) {
+        if context.mdast {
+            context
+                .state()
+                .get::<TreeState>()
+                .borrow_mut()
+                .frames
+                .push(Frame {
+                    items: vec![],
+                    is_list_item,
+                });
+        }
+    }
+
+    /// Finish a buffer entered with [`Self::enter_output`], whose Markdown
+    /// `output` is returned to a handler. The nodes of the Markdown are passed
+    /// along with it, see [`take_nodes`].
+    pub(crate) fn leave_output(context: &ConversionContext, output: &str) {
+        if context.mdast {
+            let nodes = Self::finish_output(context, output);
+            context.state().get::<TreeState>().borrow_mut().nodes = nodes;
+        }
+    }
+
+    fn finish_output(context: &ConversionContext, output: &str) -> Option<Vec<Node>> {
+        let state = context.state().get::<TreeState>();
+        let mut state = state.borrow_mut();
+        let frame = state.frames.pop()?;
+        let mut renderer = Renderer {
+            options: context.options(),
+            references: &mut state.references,
+        };
+        // The blocks of a tight list item, e.g. a nested list, follow its
+        // text on the next line.
+        if frame.is_list_item {
+            let parent = Parent::ListItem { spread: false };
+            let (nodes, is_exact) =
+                finish_items(&mut renderer, frame.items.clone(), output, parent);
+            if is_exact {
+                return Some(nodes);
+            }
+        }
+        Some(finish_items(&mut renderer, frame.items, output, Parent::Flow).0)
+    }
+
+    /// Finish the buffer of the document, whose Markdown is `output`.
+    pub(crate) fn leave_root(context: &ConversionContext, output: &str) {
+        if let Some(nodes) = Self::finish_output(context, output) {
+            let mut nodes = flow(nodes);
+            // Trailing whitespace is trimmed from the document.
+            trim_end_whitespace(&mut nodes);
+            let state = context.state().get::<TreeState>();
+            state.borrow_mut().root = nodes;
+        }
+    }
+
+    /// Record the text appended to `output` from `start`. `value` is the text
+    /// before it was escaped.
+    pub(crate) fn push_text(context: &ConversionContext, value: &str, output: &str, start: usize) {
+        let markdown = &output[start..];
+        let is_gfm = context.options().flavor == MarkdownFlavor::Gfm;
+        let node = if escape_if_needed(value.into(), is_gfm) == markdown {
+            Node::Text {
+                value: value.to_string(),
+            }
+        } else {
+            Node::Html {
+                value: markdown.to_string(),
+            }
+        };
+        Self::push(context, vec![node], false, start..output.len());
+    }
+
+    /// Record the raw Markdown appended to `output` from `start`, e.g. a
+    /// comment.
+    pub(crate) fn push_html(context: &ConversionContext, output: &str, start: usize) {
+        let node = Node::Html {
+            value: output[start..].to_string(),
+        };
+        Self::push(context, vec![node], false, start..output.len());
+    }
+
+    /// Forget the nodes of earlier handlers before the handler of an element
+    /// runs.
+    pub(crate) fn enter_element(context: &ConversionContext) {
+        context.state().get::<TreeState>().borrow_mut().nodes = None;
+    }
+
+    /// Check the nodes passed along by the handler of an element against its
+    /// Markdown `content`, before it is appended to the buffer. The nodes are
+    /// replaced by an HTML node if they are missing or render differently.
+    /// `is_ordered_item` tells whether the element is an item of an ordered
+    /// list, whose marker is added by the list.
+    pub(crate) fn check_element(
+        context: &ConversionContext,
+        content: &str,
+        is_ordered_item: bool,
+    ) -> (Vec<Node>, bool) {
+        let nodes = context.state().get::<TreeState>().borrow_mut().nodes.take();
+        let markdown = content.trim_matches('\n');
+        let is_block = content.starts_with('\n');
+        if markdown.is_empty() {
+            return (vec![], is_block);
+        }
+        if let Some(nodes) = nodes {
+            let state = context.state().get::<TreeState>();
+            let mut state = state.borrow_mut();
+            let mut renderer = Renderer {
+                options: context.options(),
+                references: &mut state.references,
+            };
+            let parent = if is_ordered_item {
+                Parent::List { ordered: true }
+            } else if is_flow(&nodes) {
+                Parent::Flow
+            } else {
+                Parent::Phrasing
+            };
+            // Lists and their items are spread if they are separated by blank
+            // lines, which is checked by rendering them both ways.
+            let mut candidates = vec![nodes];
+            if let Some(other) = toggle_spread(&candidates[0]) {
+                candidates.push(other);
+            }
+            let mut fallback = None;
+            for candidate in candidates {
+                let rendered = renderer.children(&candidate, parent);
+                if rendered == content {
+                    let is_block = is_block || is_flow(&candidate);
+                    return (candidate, is_block);
+                }
+                if fallback.is_none() && rendered.trim_matches('\n') == markdown {
+                    fallback = Some(candidate);
+                }
+            }
+            if let Some(nodes) = fallback {
+                let is_block = is_block || is_flow(&nodes);
+                return (nodes, is_block);
+            }
+        }
+        let value = if is_block { markdown } else { content };
+        let node = Node::Html {
+            value: value.to_string(),
+        };
+        (vec![node], is_block)
+    }
+
+    /// Record the nodes of an element checked with [`Self::check_element`],
+    /// whose Markdown was appended to `output` from `start`.
+    pub(crate) fn push_element(
+        context: &ConversionContext,
+        (nodes, is_block): (Vec<Node>, bool),
+        output: &str,
+        start: usize,
+    ) {
+        Self::push(context, nodes, is_block, start..output.len());
+    }
+
+    fn push(context: &ConversionContext, nodes: Vec<Node>, is_block: bool, range: Range<usize>) {
+        if range.is_empty() && !is_block {
+            return;
+        }
+        let state = context.state().get::<TreeState>();
+        let mut state = state.borrow_mut();
+        let Some(Frame { items, .. }) = state.frames.last_mut() else {
+            return;
+        };
+        // Trailing spaces of the previous content are trimmed before blocks.
+        clamp_items(items, range.start);
+        items.push(Item {
+            nodes,
+            range,
+            is_block,
+        });
+    }
+}
+
+/// Shorten the items to the Markdown left in a buffer of `len` bytes.
+fn clamp_items(items: &mut [Item], len: usize) {
+    for item in items.iter_mut().rev() {
+        if item.range.end <= len {
+            break;
+        }
+        item.range.end = len;
+        item.range.start = item.range.start.min(len);
+    }
+}
+
+/// The nodes of a buffer whose Markdown is `output`, rendered in `parent`.
+/// Inline content is returned as is, and wrapped in paragraphs between
+/// blocks. Blocks whose nodes render with different line breaks between them
+/// are merged into an HTML node. Returns whether no blocks were merged.
+fn finish_items(
+    renderer: &mut Renderer,
+    mut items: Vec<Item>,
+    output: &str,
+    parent: Parent,
+) -> (Vec<Node>, bool) {
+    clamp_items(&mut items, output.len());
+    if !items.iter().any(|item| item.is_block) {
+        let mut nodes: Vec<Node> = vec![];
+        for node in items.into_iter().flat_map(|item| item.nodes) {
+            // Adjacent HTML would be parsed as blocks.
+            if let (Some(Node::Html { value }), Node::Html { value: next }) =
+                (nodes.last_mut(), &node)
+            {
+                append_normalized_content(value, next.clone(), false);
+            } else {
+                nodes.push(node);
+            }
+        }
+        return (nodes, true);
+    }
+
+    let mut segments: Vec<Segment> = vec![];
+    let mut items = items.into_iter().peekable();
+    while let Some(item) = items.next() {
+        if item.is_block {
+            segments.push(Segment {
+                nodes: flow(item.nodes),
+                range: item.range,
+                is_merged: false,
+            });
+            continue;
+        }
+        let mut nodes = item.nodes;
+        let mut range = item.range;
+        while let Some(next) = items.next_if(|next| !next.is_block) {
+            nodes.extend(next.nodes);
+            range.end = next.range.end;
+        }
+        // Trailing spaces are trimmed before the next block.
+        if items.peek().is_some() {
+            trim_end_spaces(&mut nodes);
+        }
+        segments.push(Segment {
+            nodes: flow(nodes),
+            range,
+            is_merged: true,
+        });
+    }
+    segments.retain(|segment| !segment.nodes.is_empty());
+
+    let mut is_exact = true;
+    let mut contents: Vec<String> = vec![];
+    for segment in &mut segments {
+        let content = renderer.children(&segment.nodes, parent);
+        let markdown = output[segment.range.clone()].trim_matches('\n');
+        if segment.is_merged && content.trim_matches('\n') != markdown {
+            is_exact = false;
+            *segment = html_segment(output, segment.range.clone());
+            contents.push(concat_html(markdown));
+        } else {
+            contents.push(content);
+        }
+    }
+
+    // Merge the blocks whose line breaks differ.
+    let mut index = 1;
+    while index < segments.len() {
+        let previous = &segments[index - 1];
+        let segment = &segments[index];
+        let start =
+            previous.range.start + output[previous.range.clone()].trim_end_matches('\n').len();
+        let end = segment.range.end - output[segment.range.clone()].trim_start_matches('\n').len();
+        let expected = &output[start..end.max(start)];
+        let mut rendered = contents[index - 1].clone();
+        let rendered_len = rendered.trim_end_matches('\n').len();
+        append_normalized_content(&mut rendered, contents[index].clone(), false);
+        let appended = &rendered[rendered_len..];
+        let actual = &appended[..appended.len() - appended.trim_start_matches('\n').len()];
+        if expected == actual {
+            index += 1;
+            continue;
+        }
+        is_exact = false;
+        let range = previous.range.start..segment.range.end;
+        segments[index - 1] = html_segment(output, range);
+        segments.remove(index);
+        contents[index - 1] =
+            concat_html(output[segments[index - 1].range.clone()].trim_matches('\n'));
+        contents.remove(index);
+        index = index.max(2) - 1;
+    }
+
+    let nodes = segments
+        .into_iter()
+        .flat_map(|segment| segment.nodes)
+        .collect();
+    (nodes, is_exact)
+}
+
+/// The list or list item `nodes`, spread if they are not.
+fn toggle_spread(nodes: &[Node]) -> Option<Vec<Node>> {
+    let [node @ (Node::List { .. } | Node::ListItem { .. })] = nodes else {
+        return None;
+    };
+    let mut node = node.clone();
+    if let Node::List { spread, .. } | Node::ListItem { spread, .. } = &mut node {
+        *spread = !*spread;
+    }
+    Some(vec![node])
+}
+
+/// Trim the trailing whitespace of the last paragraph of `nodes`.
+fn trim_end_whitespace(nodes: &mut Vec<Node>) {
+    while let Some(node) = nodes.last_mut() {
+        match node {
+            Node::Text { value } | Node::Html { value } => {
+                value.truncate(value.trim_end_document_whitespace().len());
+                if !value.is_empty() {
+                    return;
+                }
+            }
+            Node::Break => {}
+            // The whitespace at the end of emphasis is written after it.
+            Node::Paragraph { children }
+            | Node::Emphasis { children }
+            | Node::Strong { children }
+            | Node::Delete { children } => {
+                trim_end_whitespace(children);
+                if !children.is_empty() {
+                    return;
+                }
+            }
+            _ => return,
+        }
+        nodes.pop();
+    }
+}
+
+fn html_segment(output: &str, range: Range<usize>) -> Segment {
+    Segment {
+        nodes: vec![Node::Html {
+            value: output[range.clone()].trim_matches('\n').to_string(),
+        }],
+        range,
+        is_merged: false,
+    }
+}
+
+/// The Markdown of an HTML block.
+fn concat_html(value: &str) -> String {
+    concat_strings!("\n\n", value, "\n\n")
+}
+
+/// Trim the trailing spaces of inline nodes, like the converter trims them
+/// before blocks.
+fn trim_end_spaces(nodes: &mut Vec<Node>) {
+    while let Some(node) = nodes.last_mut() {
+        match node {
+            Node::Text { value } | Node::Html { value } => {
+                value.truncate(value.trim_end_matches(' ').len());
+                if !value.is_empty() {
+                    return;
+                }
+                nodes.pop();
+            }
+            _ => return,
+        }
+    }
+}
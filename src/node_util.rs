@@ -35,7 +35,62 @@ pub(crate) fn parent_tag_name_equals(node: &Rc<Node>, tag_names: &Vec<&str>) ->
     }
 }
 
+/// Find the `<head>` of the document containing `node`.
+pub(crate) fn find_document_head(node: &Rc<Node>) -> Option<Rc<Node>> {
+    let mut root = node.clone();
+    while let Some(parent) = get_parent_node(&root) {
+        root = parent;
+    }
+    let find_child = |node: &Rc<Node>, tag: &str| {
+        node.children
+            .borrow()
+            .iter()
+            .find(|child| get_node_tag_name(child) == Some(tag))
+            .cloned()
+    };
+    match get_node_tag_name(&root) {
+        Some("head") => Some(root),
+        _ => {
+            let html = if matches!(root.data, NodeData::Document) {
+                find_child(&root, "html")?
+            } else {
+                root
+            };
+            find_child(&html, "head")
+        }
+    }
+}
+
+/// The text of an element, with collapsed whitespace.
+pub(crate) fn text_content(node: &Rc<Node>) -> String {
+    let mut text = String::new();
+    for child in node.children.borrow().iter() {
+        if let NodeData::Text { contents } = &child.data {
+            text.push_str(&contents.borrow());
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
 pub(crate) fn get_node_children(node: &Rc<Node>) -> Vec<Rc<Node>> {
     let children = node.children.borrow();
     children.iter().cloned().collect()
 }
+
+pub(crate) fn get_node_attr(node: &Rc<Node>, attr_name: &str) -> Option<String> {
+    let NodeData::Element { attrs, .. } = &node.data else {
+        return None;
+    };
+    attrs
+        .borrow()
+        .iter()
+        .find(|attr| &attr.name.local == attr_name)
+        .map(|attr| attr.value.to_string())
+}
+
+// Check to see if the whitespace separated `class` attribute of node contains
+// the provided class name.
+pub(crate) fn node_has_class(node: &Rc<Node>, class_name: &str) -> bool {
+    get_node_attr(node, "class")
+        .is_some_and(|classes| classes.split_ascii_whitespace().any(|it| it == class_name))
+}
@@ -16,6 +16,19 @@ pub struct Options {
     /// If true, the whitespace in inline \<code> tags will be preserved.
     pub preformatted_code: bool,
     pub translation_mode: TranslationMode,
+    pub flavor: MarkdownFlavor,
+    /// How table cells with `colspan` or `rowspan` are expanded.
+    pub table_span_style: TableSpanStyle,
+    /// If true, table cells will be padded with spaces so that the columns
+    /// line up when displayed with a monospaced font.
+    pub pad_table_cells: bool,
+    pub footnote_style: FootnoteStyle,
+    /// If true, content a reader doesn't see is skipped: elements with the
+    /// `hidden` attribute, `aria-hidden="true"`, or an inline `display: none`
+    /// or `visibility: hidden` style, `<head>`, `<template>`, `<script>`,
+    /// `<style>`, and `<noscript>` when scripting is enabled.
+    pub skip_hidden_content: bool,
+    pub front_matter_style: FrontMatterStyle,
 }
 
 impl Default for Options {
@@ -33,17 +46,25 @@ impl Default for Options {
             ol_number_spacing: 2,
             preformatted_code: false,
             translation_mode: TranslationMode::Pure,
+            flavor: MarkdownFlavor::CommonMark,
+            table_span_style: TableSpanStyle::Empty,
+            pad_table_cells: true,
+            footnote_style: FootnoteStyle::Links,
+            skip_hidden_content: false,
+            front_matter_style: FrontMatterStyle::Disabled,
         }
     }
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
 pub enum HeadingStyle {
     Atx,
     Setex,
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
 pub enum HrStyle {
     /// `- - -`
     Dashes,
@@ -54,18 +75,21 @@ pub enum HrStyle {
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
 pub enum BrStyle {
     TwoSpaces,
     Backslash,
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
 pub enum CodeBlockStyle {
     Indented,
     Fenced,
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
 pub enum CodeBlockFence {
     /// Wrap code with `~~~`
     Tildes,
@@ -74,6 +98,7 @@ pub enum CodeBlockFence {
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
 pub enum BulletListMarker {
     /// List items will start with `*`
     Asterisk,
@@ -82,6 +107,7 @@ pub enum BulletListMarker {
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
 pub enum LinkStyle {
     Inlined,
     /// Will convert links with the same URL and link text to
@@ -91,6 +117,7 @@ pub enum LinkStyle {
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
 pub enum LinkReferenceStyle {
     Full,
     Collapsed,
@@ -98,6 +125,44 @@ pub enum LinkReferenceStyle {
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum TableSpanStyle {
+    /// Grid slots covered by a spanned cell are left empty.
+    Empty,
+    /// Grid slots covered by a spanned cell repeat the cell's content.
+    Repeat,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum FootnoteStyle {
+    /// Footnote references and definitions are converted like any other links
+    /// and lists.
+    Links,
+    /// Footnotes generated by Pandoc, Wikipedia and DPUB-ARIA markup are
+    /// converted to `[^1]` references, with `[^1]: ...` definitions appended
+    /// to the end of the document.
+    Markdown,
+}
+
+/// The front matter written at the top of the Markdown, with the `<title>`,
+/// the `description`, `author` and `keywords` `<meta>` tags, the OpenGraph
+/// `og:*` properties and the `<link rel="canonical">` URL of the document.
+/// See [`HtmlToMarkdownBuilder::front_matter_fields`](crate::HtmlToMarkdownBuilder::front_matter_fields)
+/// to choose and rename the fields.
+#[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum FrontMatterStyle {
+    /// No front matter.
+    Disabled,
+    /// YAML front matter between `---` lines, as used by Jekyll and Hugo.
+    Yaml,
+    /// TOML front matter between `+++` lines, as used by Hugo and Zola.
+    Toml,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
 pub enum TranslationMode {
     /// In pure translation mode, always translate HTML to Markdown, even when
     /// that translation drops attributes in the HTML.
@@ -107,3 +172,15 @@ pub enum TranslationMode {
     /// an (almost) identical result.
     Faithful,
 }
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum MarkdownFlavor {
+    /// Only emit syntax defined by the [CommonMark spec](https://spec.commonmark.org/0.31.2/).
+    #[cfg_attr(feature = "cli", value(name = "commonmark"))]
+    CommonMark,
+    /// Emit [GitHub Flavored Markdown](https://github.github.com/gfm/):
+    /// `~~strikethrough~~`, `* [x]` task list items and autolinks are enabled,
+    /// and `~` and `|` in text are escaped.
+    Gfm,
+}
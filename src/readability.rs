@@ -0,0 +1,389 @@
+use std::{collections::HashSet, rc::Rc};
+
+use html5ever::Attribute;
+use markup5ever_rcdom::{Node, NodeData};
+
+use crate::node_util::{get_node_attr, get_node_tag_name};
+
+/// Substrings of `class` and `id` attributes marking page chrome, like
+/// [Readability](https://github.com/mozilla/readability)'s unlikely candidates.
+const UNLIKELY_NAMES: &[&str] = &[
+    "-ad-",
+    "ad-break",
+    "agegate",
+    "banner",
+    "breadcrumbs",
+    "combx",
+    "comment",
+    "community",
+    "consent",
+    "cookie",
+    "disqus",
+    "footer",
+    "gdpr",
+    "header",
+    "menu",
+    "newsletter",
+    "pager",
+    "pagination",
+    "popup",
+    "related",
+    "replies",
+    "share",
+    "shoutbox",
+    "sidebar",
+    "skyscraper",
+    "social",
+    "sponsor",
+    "subscribe",
+];
+
+/// Substrings of `class` and `id` attributes which rescue an unlikely name,
+/// e.g. `main-header-content`.
+const MAYBE_NAMES: &[&str] = &["article", "body", "column", "content", "main"];
+
+const POSITIVE_NAMES: &[&str] = &[
+    "article", "blog", "body", "content", "entry", "main", "page", "post", "story", "text",
+];
+
+const NEGATIVE_NAMES: &[&str] = &[
+    "banner", "combx", "comment", "contact", "cookie", "footer", "masthead", "meta", "outbrain",
+    "promo", "related", "share", "shoutbox", "sidebar", "sponsor", "widget",
+];
+
+/// Paragraphs shorter than this don't count as content.
+const MIN_PARAGRAPH_LEN: usize = 25;
+/// Containers of links with this much text outside of the links are content.
+const MAX_LINK_LIST_TEXT_LEN: usize = 200;
+
+/// Whether an element is navigation, a sidebar, a banner, a footer, other
+/// page chrome, or has no readable text.
+fn is_boilerplate(tag: &str, attrs: &[Attribute]) -> bool {
+    match tag {
+        "nav" | "aside" | "footer" => return true,
+        // Not readable text
+        "script" | "style" | "noscript" | "template" | "svg" | "iframe" | "button" | "select"
+        | "textarea" => return true,
+        // Never drop the whole page or a table because of a class name.
+        "html" | "body" | "main" | "article" | "table" | "thead" | "tbody" | "tfoot" | "tr"
+        | "td" | "th" => return false,
+        _ => {}
+    }
+    let attr = |name: &str| {
+        attrs
+            .iter()
+            .find(|attr| &attr.name.local == name)
+            .map(|attr| attr.value.to_ascii_lowercase())
+    };
+    if attr("role").is_some_and(|role| {
+        matches!(
+            role.trim(),
+            "navigation" | "complementary" | "contentinfo" | "banner" | "dialog" | "alertdialog"
+        )
+    }) {
+        return true;
+    }
+    let names = [attr("class"), attr("id")];
+    let contains_any = |list: &[&str]| {
+        names
+            .iter()
+            .flatten()
+            .any(|name| list.iter().any(|it| name.contains(it)))
+    };
+    contains_any(UNLIKELY_NAMES) && !contains_any(MAYBE_NAMES)
+}
+
+/// The main content of a document.
+pub(crate) struct MainContent {
+    /// The element holding the content, or the document itself if there is
+    /// no better candidate.
+    pub(crate) root: Rc<Node>,
+    /// The boilerplate inside `root`, which is skipped.
+    pub(crate) excluded: HashSet<*const Node>,
+}
+
+/// An element of the document, with the text statistics of its subtree.
+struct Candidate {
+    node: Rc<Node>,
+    parent: Option<usize>,
+    /// Boilerplate elements are collected but not descended into.
+    is_boilerplate: bool,
+    /// The number of characters of the text, with collapsed whitespace.
+    text_len: usize,
+    /// The number of characters of the text inside links.
+    link_text_len: usize,
+    commas: usize,
+    has_block_child: bool,
+    /// Whether a descendant is a `<p>`, `<pre>` or `<blockquote>`.
+    has_paragraph: bool,
+    /// Whether a descendant is a list or a menu.
+    has_list: bool,
+    score: Option<f64>,
+}
+
+impl Candidate {
+    fn link_density(&self) -> f64 {
+        self.link_text_len as f64 / self.text_len.max(1) as f64
+    }
+}
+
+/// Find the main content of `document`: the `<main>` landmark or the only
+/// `<article>` if any, otherwise the element with the best score, where
+/// paragraphs with a lot of text score for their ancestors, and links lower
+/// the score. Boilerplate and link lists inside it are excluded.
+pub(crate) fn find_main_content(document: &Rc<Node>) -> MainContent {
+    let mut candidates = collect_candidates(document);
+
+    // Children come after their parent, sum the statistics bottom-up.
+    for index in (0..candidates.len()).rev() {
+        let candidate = &mut candidates[index];
+        if get_node_tag_name(&candidate.node) == Some("a") {
+            candidate.link_text_len = candidate.text_len;
+        }
+        let (text_len, link_text_len, commas) = (
+            candidate.text_len,
+            candidate.link_text_len,
+            candidate.commas,
+        );
+        let is_block = is_block(&candidate.node);
+        let tag = get_node_tag_name(&candidate.node);
+        let has_paragraph =
+            candidate.has_paragraph || matches!(tag, Some("p" | "pre" | "blockquote"));
+        let has_list = candidate.has_list || matches!(tag, Some("ul" | "ol" | "nav" | "menu"));
+        if let Some(parent) = candidate.parent {
+            let parent = &mut candidates[parent];
+            parent.has_paragraph |= has_paragraph;
+            parent.has_list |= has_list;
+            parent.text_len += text_len;
+            parent.link_text_len += link_text_len;
+            parent.commas += commas;
+            parent.has_block_child |= is_block;
+        }
+    }
+
+    let root = find_landmark(&candidates).or_else(|| find_top_candidate(&mut candidates));
+
+    // Exclude the boilerplate and link lists inside the root, parents come
+    // before their children.
+    let mut in_root = vec![false; candidates.len()];
+    let mut excluded = HashSet::new();
+    for index in 0..candidates.len() {
+        let candidate = &candidates[index];
+        let parent_in_root = match candidate.parent {
+            Some(parent) => in_root[parent],
+            // Top level elements are in the document.
+            None => root.is_none(),
+        };
+        if Some(index) == root {
+            in_root[index] = true;
+        } else if parent_in_root {
+            if candidate.is_boilerplate || is_link_list(candidate) {
+                excluded.insert(Rc::as_ptr(&candidate.node));
+            } else {
+                in_root[index] = true;
+            }
+        }
+    }
+
+    MainContent {
+        root: root.map_or_else(|| document.clone(), |index| candidates[index].node.clone()),
+        excluded,
+    }
+}
+
+/// The first `<main>` landmark, or the only `<article>`.
+fn find_landmark(candidates: &[Candidate]) -> Option<usize> {
+    let is_readable = |candidate: &Candidate| !candidate.is_boilerplate && candidate.text_len > 0;
+    let landmark = candidates.iter().position(|candidate| {
+        is_readable(candidate)
+            && (get_node_tag_name(&candidate.node) == Some("main")
+                || attr_equals(&candidate.node, "role", "main"))
+    });
+    if landmark.is_some() {
+        return landmark;
+    }
+    let mut articles = candidates.iter().enumerate().filter(|(_, candidate)| {
+        is_readable(candidate) && get_node_tag_name(&candidate.node) == Some("article")
+    });
+    match (articles.next(), articles.next()) {
+        (Some((index, _)), None) => Some(index),
+        _ => None,
+    }
+}
+
+fn find_top_candidate(candidates: &mut [Candidate]) -> Option<usize> {
+    for index in 0..candidates.len() {
+        let candidate = &candidates[index];
+        if !is_paragraph(candidate) || candidate.text_len < MIN_PARAGRAPH_LEN {
+            continue;
+        }
+        let score = 1.0 + candidate.commas as f64 + (candidate.text_len / 100).min(3) as f64;
+        let mut ancestor = candidate.parent;
+        let mut level = 0;
+        while let Some(index) = ancestor
+            && level < 3
+        {
+            let ancestor_candidate = &mut candidates[index];
+            let divider = match level {
+                0 => 1.0,
+                1 => 2.0,
+                _ => level as f64 * 3.0,
+            };
+            let initial_score = initial_score(&ancestor_candidate.node);
+            *ancestor_candidate.score.get_or_insert(initial_score) += score / divider;
+            ancestor = ancestor_candidate.parent;
+            level += 1;
+        }
+    }
+
+    candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(index, candidate)| {
+            let score = candidate.score? * (1.0 - candidate.link_density());
+            Some((index, score))
+        })
+        .filter(|(_, score)| *score > 0.0)
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(index, _)| index)
+}
+
+/// Whether a list or menu is mostly links with little other text, like
+/// language lists, tag clouds and "share" buttons. Tables of links are
+/// content.
+fn is_link_list(candidate: &Candidate) -> bool {
+    let is_list = match get_node_tag_name(&candidate.node) {
+        Some("ul" | "ol" | "nav" | "menu") => true,
+        // Divs of text and paragraphs are content, only wrappers of lists
+        // can be menus.
+        Some("div") => candidate.has_list && !candidate.has_paragraph,
+        _ => false,
+    };
+    is_list
+        && candidate.text_len - candidate.link_text_len < MAX_LINK_LIST_TEXT_LEN
+        && candidate.link_density() > 0.5
+        && candidate.commas < 10
+        && class_weight(&candidate.node) < 25.0
+}
+
+/// Collect the elements of the document in tree order. The subtrees of
+/// `<head>` and boilerplate are skipped. Iterative, so
+/// that deeply nested documents can't overflow the stack.
+fn collect_candidates(document: &Rc<Node>) -> Vec<Candidate> {
+    let mut candidates: Vec<Candidate> = vec![];
+    let mut stack: Vec<(Rc<Node>, Option<usize>)> = vec![(document.clone(), None)];
+    while let Some((node, parent)) = stack.pop() {
+        let index = match &node.data {
+            NodeData::Document => None,
+            NodeData::Element { name, attrs, .. } => {
+                if &name.local == "head" {
+                    continue;
+                }
+                let is_boilerplate = is_boilerplate(&name.local, &attrs.borrow());
+                candidates.push(Candidate {
+                    node: node.clone(),
+                    parent,
+                    is_boilerplate,
+                    text_len: 0,
+                    link_text_len: 0,
+                    commas: 0,
+                    has_block_child: false,
+                    has_paragraph: false,
+                    has_list: false,
+                    score: None,
+                });
+                if is_boilerplate {
+                    continue;
+                }
+                Some(candidates.len() - 1)
+            }
+            NodeData::Text { contents } => {
+                if let Some(parent) = parent {
+                    let text = contents.borrow();
+                    let parent = &mut candidates[parent];
+                    parent.text_len += text
+                        .split_whitespace()
+                        .map(|word| word.chars().count() + 1)
+                        .sum::<usize>();
+                    parent.commas += text.matches([',', '，', '、']).count();
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        for child in node.children.borrow().iter().rev() {
+            stack.push((child.clone(), index));
+        }
+    }
+    candidates
+}
+
+fn is_paragraph(candidate: &Candidate) -> bool {
+    match get_node_tag_name(&candidate.node).unwrap_or_default() {
+        "p" | "pre" | "td" | "blockquote" => true,
+        // Divs are often used as paragraphs.
+        "div" => !candidate.has_block_child,
+        _ => false,
+    }
+}
+
+fn is_block(node: &Rc<Node>) -> bool {
+    matches!(
+        get_node_tag_name(node).unwrap_or_default(),
+        "address"
+            | "article"
+            | "blockquote"
+            | "div"
+            | "dl"
+            | "figure"
+            | "h1"
+            | "h2"
+            | "h3"
+            | "h4"
+            | "h5"
+            | "h6"
+            | "ol"
+            | "p"
+            | "pre"
+            | "section"
+            | "table"
+            | "ul"
+    )
+}
+
+fn initial_score(node: &Rc<Node>) -> f64 {
+    let tag_score = match get_node_tag_name(node).unwrap_or_default() {
+        "div" => 5.0,
+        "pre" | "td" | "blockquote" => 3.0,
+        "address" | "ol" | "ul" | "dl" | "dd" | "dt" | "li" | "form" => -3.0,
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "th" => -5.0,
+        _ => 0.0,
+    };
+    tag_score + class_weight(node)
+}
+
+/// Score the `class` and `id` of an element by how likely they are to
+/// belong to the content.
+fn class_weight(node: &Rc<Node>) -> f64 {
+    let NodeData::Element { attrs, .. } = &node.data else {
+        return 0.0;
+    };
+    let mut weight = 0.0;
+    for attr in attrs.borrow().iter() {
+        if &attr.name.local != "class" && &attr.name.local != "id" {
+            continue;
+        }
+        let value = attr.value.to_ascii_lowercase();
+        if POSITIVE_NAMES.iter().any(|it| value.contains(it)) {
+            weight += 25.0;
+        }
+        if NEGATIVE_NAMES.iter().any(|it| value.contains(it)) {
+            weight -= 25.0;
+        }
+    }
+    weight
+}
+
+fn attr_equals(node: &Rc<Node>, name: &str, value: &str) -> bool {
+    get_node_attr(node, name).is_some_and(|it| it.trim().eq_ignore_ascii_case(value))
+}
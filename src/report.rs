@@ -0,0 +1,129 @@
+//! The metadata of a conversion, see
+//! [`HtmlToMarkdown::convert_with_report`](crate::HtmlToMarkdown::convert_with_report).
+
+use crate::element_handler::Handlers;
+
+/// The Markdown of a document and the metadata collected while converting it.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[non_exhaustive]
+pub struct Conversion {
+    /// The Markdown, the same as returned by
+    /// [`HtmlToMarkdown::convert`](crate::HtmlToMarkdown::convert).
+    pub markdown: String,
+    /// The text of the `<title>` in the head.
+    pub title: Option<String>,
+    /// The converted links, in document order.
+    pub links: Vec<Link>,
+    /// The converted images, in document order.
+    pub images: Vec<Image>,
+    /// The converted headings, in document order.
+    pub headings: Vec<Heading>,
+    /// The number of words of the converted text.
+    pub word_count: usize,
+    /// The elements left out of the Markdown, in document order. The
+    /// descendants of a dropped element are not listed.
+    pub dropped_elements: Vec<DroppedElement>,
+}
+
+/// A link converted to Markdown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    /// The destination, after resolving it against the base URL and
+    /// rewriting it.
+    pub url: String,
+    /// The Markdown content of the link.
+    pub text: String,
+    /// The `title` attribute.
+    pub title: Option<String>,
+}
+
+/// An image converted to Markdown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    /// The source, after resolving it against the base URL and rewriting it.
+    pub url: String,
+    /// The `alt` attribute.
+    pub alt: Option<String>,
+    /// The `title` attribute.
+    pub title: Option<String>,
+}
+
+/// A heading converted to Markdown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heading {
+    /// The level, from 1 for `<h1>` to 6 for `<h6>`.
+    pub level: u8,
+    /// The Markdown content of the heading.
+    pub text: String,
+}
+
+/// An element left out of the Markdown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DroppedElement {
+    /// The tag name.
+    pub tag: String,
+    /// Why the element was dropped.
+    pub reason: DropReason,
+}
+
+/// Why an element was left out of the Markdown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DropReason {
+    /// Its custom handler returned no content, e.g. for skipped tags.
+    Skipped,
+    /// It isn't rendered, see
+    /// [`Options::skip_hidden_content`](crate::options::Options::skip_hidden_content).
+    Hidden,
+    /// It matched a skip selector, or it is boilerplate outside of the main
+    /// content.
+    Excluded,
+}
+
+/// The report of the current conversion, `None` unless it was requested.
+#[derive(Default)]
+pub(crate) struct ConversionReport {
+    pub(crate) conversion: Option<Conversion>,
+    /// Whether the last text ended in the middle of a word.
+    in_word: bool,
+}
+
+impl ConversionReport {
+    /// Update the report of the current conversion, if any.
+    pub(crate) fn record(handlers: &dyn Handlers, update: impl FnOnce(&mut Conversion)) {
+        let state = handlers.state().get::<ConversionReport>();
+        if let Some(conversion) = state.borrow_mut().conversion.as_mut() {
+            update(conversion);
+        }
+    }
+
+    /// Count the words of a text node. Words may continue in the next text
+    /// node, like in `<b>bold</b>er`, unless [`Self::break_word`] is called.
+    pub(crate) fn count_words(handlers: &dyn Handlers, text: &str) {
+        let state = handlers.state().get::<ConversionReport>();
+        let mut report = state.borrow_mut();
+        let mut in_word = report.in_word;
+        let mut count = 0;
+        for ch in text.chars() {
+            if ch.is_whitespace() {
+                in_word = false;
+            } else if !in_word {
+                in_word = true;
+                count += 1;
+            }
+        }
+        report.in_word = in_word;
+        if let Some(conversion) = report.conversion.as_mut() {
+            conversion.word_count += count;
+        }
+    }
+
+    /// End the current word, at the boundary of a block.
+    pub(crate) fn break_word(handlers: &dyn Handlers) {
+        handlers
+            .state()
+            .get::<ConversionReport>()
+            .borrow_mut()
+            .in_word = false;
+    }
+}
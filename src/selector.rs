@@ -0,0 +1,636 @@
+use std::rc::Rc;
+
+use markup5ever_rcdom::{Node, NodeData};
+
+use crate::node_util::get_parent_node;
+
+/// A list of CSS selectors, e.g. `nav, .ad, article > p:first-child`.
+///
+/// Supported are type, universal, class, id and attribute selectors (`[a]`,
+/// `[a=v]`, `[a~=v]`, `[a|=v]`, `[a^=v]`, `[a$=v]`, `[a*=v]`, with the `i`
+/// flag), the descendant, child (`>`), next sibling (`+`) and subsequent
+/// sibling (`~`) combinators, and the `:not()`, `:first-child`,
+/// `:last-child`, `:only-child` and `:empty` pseudo-classes.
+#[derive(Debug)]
+pub(crate) struct SelectorList(Vec<ComplexSelector>);
+
+/// Compound selectors and the combinators between them, from right to left:
+/// `combinators[i]` relates `compounds[i]` to `compounds[i + 1]`.
+#[derive(Debug)]
+struct ComplexSelector {
+    compounds: Vec<CompoundSelector>,
+    combinators: Vec<Combinator>,
+}
+
+#[derive(Debug, Default)]
+struct CompoundSelector {
+    /// The lowercase tag name, `None` for `*`.
+    tag: Option<String>,
+    conditions: Vec<Condition>,
+}
+
+#[derive(Debug)]
+enum Condition {
+    Id(String),
+    Class(String),
+    Attribute {
+        name: String,
+        value: Option<(AttributeOperator, String)>,
+        ignore_case: bool,
+    },
+    Not(SelectorList),
+    FirstChild,
+    LastChild,
+    OnlyChild,
+    Empty,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum AttributeOperator {
+    /// `=`
+    Equals,
+    /// `~=`
+    Includes,
+    /// `|=`
+    DashMatch,
+    /// `^=`
+    Prefix,
+    /// `$=`
+    Suffix,
+    /// `*=`
+    Substring,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Combinator {
+    Descendant,
+    Child,
+    NextSibling,
+    SubsequentSibling,
+}
+
+impl SelectorList {
+    /// Parse a selector list, returning a message describing the first
+    /// syntax error.
+    pub(crate) fn parse(input: &str) -> Result<Self, String> {
+        let mut parser = Parser { input, pos: 0 };
+        let list = parser.parse_list()?;
+        if parser.pos < input.len() {
+            return Err(parser.error("unexpected character"));
+        }
+        Ok(list)
+    }
+
+    /// Whether the element `node` matches any of the selectors.
+    pub(crate) fn matches(&self, node: &Rc<Node>) -> bool {
+        self.0.iter().any(|selector| selector.matches(0, node))
+    }
+
+    /// Find the first element of `root` and its descendants matching the
+    /// selectors, in tree order.
+    pub(crate) fn find_first(&self, root: &Rc<Node>) -> Option<Rc<Node>> {
+        let mut stack = vec![root.clone()];
+        while let Some(node) = stack.pop() {
+            if is_element(&node) && self.matches(&node) {
+                return Some(node);
+            }
+            stack.extend(node.children.borrow().iter().rev().cloned());
+        }
+        None
+    }
+
+    /// Find the elements of `root` and its descendants matching the
+    /// selectors, except the descendants of matching elements.
+    pub(crate) fn find_outermost(&self, root: &Rc<Node>) -> Vec<Rc<Node>> {
+        let mut found = vec![];
+        let mut stack = vec![root.clone()];
+        while let Some(node) = stack.pop() {
+            if is_element(&node) && self.matches(&node) {
+                found.push(node);
+                continue;
+            }
+            stack.extend(node.children.borrow().iter().rev().cloned());
+        }
+        found
+    }
+}
+
+impl ComplexSelector {
+    fn matches(&self, index: usize, node: &Rc<Node>) -> bool {
+        if !self.compounds[index].matches(node) {
+            return false;
+        }
+        let Some(combinator) = self.combinators.get(index) else {
+            return true;
+        };
+        let next = index + 1;
+        match combinator {
+            Combinator::Child => parent_element(node).is_some_and(|it| self.matches(next, &it)),
+            Combinator::Descendant => {
+                let mut ancestor = parent_element(node);
+                while let Some(node) = ancestor {
+                    if self.matches(next, &node) {
+                        return true;
+                    }
+                    ancestor = parent_element(&node);
+                }
+                false
+            }
+            Combinator::NextSibling => {
+                previous_element_sibling(node).is_some_and(|it| self.matches(next, &it))
+            }
+            Combinator::SubsequentSibling => {
+                let Some(parent) = get_parent_node(node) else {
+                    return false;
+                };
+                let children = parent.children.borrow();
+                children
+                    .iter()
+                    .take_while(|child| !Rc::ptr_eq(child, node))
+                    .any(|child| is_element(child) && self.matches(next, child))
+            }
+        }
+    }
+}
+
+impl CompoundSelector {
+    fn matches(&self, node: &Rc<Node>) -> bool {
+        let NodeData::Element { name, .. } = &node.data else {
+            return false;
+        };
+        if self.tag.as_ref().is_some_and(|tag| *tag != *name.local) {
+            return false;
+        }
+        self.conditions
+            .iter()
+            .all(|condition| condition.matches(node))
+    }
+}
+
+impl Condition {
+    fn matches(&self, node: &Rc<Node>) -> bool {
+        let NodeData::Element { attrs, .. } = &node.data else {
+            return false;
+        };
+        let attr = |name: &str| {
+            attrs
+                .borrow()
+                .iter()
+                .find(|attr| &attr.name.local == name)
+                .map(|attr| attr.value.to_string())
+        };
+        match self {
+            Condition::Id(id) => attr("id").is_some_and(|it| it == *id),
+            Condition::Class(class) => {
+                attr("class").is_some_and(|it| it.split_ascii_whitespace().any(|it| it == class))
+            }
+            Condition::Attribute {
+                name,
+                value,
+                ignore_case,
+            } => {
+                let Some(actual) = attr(name) else {
+                    return false;
+                };
+                let Some((operator, expected)) = value else {
+                    return true;
+                };
+                let (actual, expected) = if *ignore_case {
+                    (actual.to_lowercase(), expected.to_lowercase())
+                } else {
+                    (actual, expected.clone())
+                };
+                match operator {
+                    AttributeOperator::Equals => actual == expected,
+                    AttributeOperator::Includes => {
+                        actual.split_ascii_whitespace().any(|it| it == expected)
+                    }
+                    AttributeOperator::DashMatch => {
+                        actual == expected
+                            || actual
+                                .strip_prefix(&expected)
+                                .is_some_and(|rest| rest.starts_with('-'))
+                    }
+                    AttributeOperator::Prefix => {
+                        !expected.is_empty() && actual.starts_with(&expected)
+                    }
+                    AttributeOperator::Suffix => {
+                        !expected.is_empty() && actual.ends_with(&expected)
+                    }
+                    AttributeOperator::Substring => {
+                        !expected.is_empty() && actual.contains(&expected)
+                    }
+                }
+            }
+            Condition::Not(list) => !list.matches(node),
+            Condition::FirstChild => is_first_element_child(node, false),
+            Condition::LastChild => is_first_element_child(node, true),
+            Condition::OnlyChild => {
+                is_first_element_child(node, false) && is_first_element_child(node, true)
+            }
+            Condition::Empty => node
+                .children
+                .borrow()
+                .iter()
+                .all(|child| match &child.data {
+                    NodeData::Text { contents } => contents.borrow().is_empty(),
+                    NodeData::Element { .. } => false,
+                    _ => true,
+                }),
+        }
+    }
+}
+
+fn is_element(node: &Rc<Node>) -> bool {
+    matches!(node.data, NodeData::Element { .. })
+}
+
+fn parent_element(node: &Rc<Node>) -> Option<Rc<Node>> {
+    get_parent_node(node).filter(is_element)
+}
+
+/// The closest element sibling before `node`.
+fn previous_element_sibling(node: &Rc<Node>) -> Option<Rc<Node>> {
+    let parent = get_parent_node(node)?;
+    let children = parent.children.borrow();
+    children
+        .iter()
+        .take_while(|child| !Rc::ptr_eq(child, node))
+        .filter(|child| is_element(child))
+        .last()
+        .cloned()
+}
+
+/// Whether `node` is the first element child of its parent, or the last one
+/// if `from_end`. Only the children up to the first element are visited, so
+/// checking all the children of an element doesn't take quadratic time.
+fn is_first_element_child(node: &Rc<Node>, from_end: bool) -> bool {
+    let Some(parent) = get_parent_node(node) else {
+        return true;
+    };
+    let children = parent.children.borrow();
+    let first = if from_end {
+        children.iter().rev().find(|child| is_element(child))
+    } else {
+        children.iter().find(|child| is_element(child))
+    };
+    first.is_none_or(|child| Rc::ptr_eq(child, node))
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> String {
+        match self.peek() {
+            Some(ch) => format!("{message} {ch:?} at offset {}", self.pos),
+            None => format!("{message}, unexpected end of input"),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, ch: char) -> bool {
+        if self.peek() == Some(ch) {
+            self.pos += ch.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Skip whitespace, returns whether there was any.
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.pos;
+        while self.peek().is_some_and(|ch| ch.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+        self.pos > start
+    }
+
+    fn parse_list(&mut self) -> Result<SelectorList, String> {
+        let mut selectors = vec![];
+        loop {
+            self.skip_whitespace();
+            selectors.push(self.parse_complex()?);
+            self.skip_whitespace();
+            if !self.eat(',') {
+                return Ok(SelectorList(selectors));
+            }
+        }
+    }
+
+    fn parse_complex(&mut self) -> Result<ComplexSelector, String> {
+        let mut compounds = vec![self.parse_compound()?];
+        let mut combinators = vec![];
+        loop {
+            let had_whitespace = self.skip_whitespace();
+            let combinator = match self.peek() {
+                Some('>') => Combinator::Child,
+                Some('+') => Combinator::NextSibling,
+                Some('~') => Combinator::SubsequentSibling,
+                Some(',' | ')') | None => break,
+                Some(_) if had_whitespace => Combinator::Descendant,
+                Some(_) => return Err(self.error("unexpected character")),
+            };
+            if combinator != Combinator::Descendant {
+                self.pos += 1;
+                self.skip_whitespace();
+            }
+            combinators.push(combinator);
+            compounds.push(self.parse_compound()?);
+        }
+        // Matching goes from the subject of the selector to the left.
+        compounds.reverse();
+        combinators.reverse();
+        Ok(ComplexSelector {
+            compounds,
+            combinators,
+        })
+    }
+
+    fn parse_compound(&mut self) -> Result<CompoundSelector, String> {
+        let mut compound = CompoundSelector::default();
+        let mut is_empty = true;
+        if self.eat('*') {
+            is_empty = false;
+        } else if self.peek().is_some_and(is_ident_start) {
+            compound.tag = Some(self.parse_ident()?.to_ascii_lowercase());
+            is_empty = false;
+        }
+        loop {
+            let condition = match self.peek() {
+                Some('#') => {
+                    self.pos += 1;
+                    Condition::Id(self.parse_ident()?)
+                }
+                Some('.') => {
+                    self.pos += 1;
+                    Condition::Class(self.parse_ident()?)
+                }
+                Some('[') => {
+                    self.pos += 1;
+                    self.parse_attribute()?
+                }
+                Some(':') => {
+                    self.pos += 1;
+                    self.parse_pseudo_class()?
+                }
+                _ => break,
+            };
+            compound.conditions.push(condition);
+            is_empty = false;
+        }
+        if is_empty {
+            return Err(self.error("expected a selector, found"));
+        }
+        Ok(compound)
+    }
+
+    fn parse_attribute(&mut self) -> Result<Condition, String> {
+        self.skip_whitespace();
+        let name = self.parse_ident()?.to_ascii_lowercase();
+        self.skip_whitespace();
+        let operator = match self.peek() {
+            Some(']') => {
+                self.pos += 1;
+                return Ok(Condition::Attribute {
+                    name,
+                    value: None,
+                    ignore_case: false,
+                });
+            }
+            Some('=') => AttributeOperator::Equals,
+            Some('~') => AttributeOperator::Includes,
+            Some('|') => AttributeOperator::DashMatch,
+            Some('^') => AttributeOperator::Prefix,
+            Some('$') => AttributeOperator::Suffix,
+            Some('*') => AttributeOperator::Substring,
+            _ => return Err(self.error("expected an attribute operator, found")),
+        };
+        self.pos += 1;
+        if !matches!(operator, AttributeOperator::Equals) && !self.eat('=') {
+            return Err(self.error("expected '=', found"));
+        }
+        self.skip_whitespace();
+        let value = match self.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                self.pos += 1;
+                self.parse_string(quote)?
+            }
+            _ => self.parse_ident()?,
+        };
+        self.skip_whitespace();
+        let ignore_case = self.eat('i') || self.eat('I');
+        self.skip_whitespace();
+        if !self.eat(']') {
+            return Err(self.error("expected ']', found"));
+        }
+        Ok(Condition::Attribute {
+            name,
+            value: Some((operator, value)),
+            ignore_case,
+        })
+    }
+
+    fn parse_pseudo_class(&mut self) -> Result<Condition, String> {
+        let start = self.pos;
+        let name = self.parse_ident()?.to_ascii_lowercase();
+        let condition = match name.as_str() {
+            "first-child" => Condition::FirstChild,
+            "last-child" => Condition::LastChild,
+            "only-child" => Condition::OnlyChild,
+            "empty" => Condition::Empty,
+            "not" => {
+                if !self.eat('(') {
+                    return Err(self.error("expected '(', found"));
+                }
+                let list = self.parse_list()?;
+                if !self.eat(')') {
+                    return Err(self.error("expected ')', found"));
+                }
+                Condition::Not(list)
+            }
+            _ => {
+                return Err(format!(
+                    "unsupported pseudo-class :{name} at offset {}",
+                    start - 1
+                ));
+            }
+        };
+        Ok(condition)
+    }
+
+    /// Parse a [CSS identifier](https://www.w3.org/TR/css-syntax-3/#ident-token-diagram).
+    fn parse_ident(&mut self) -> Result<String, String> {
+        let mut ident = String::new();
+        let start = self.pos;
+        while let Some(ch) = self.peek() {
+            if ch == '\\' {
+                self.pos += 1;
+                let escaped = self
+                    .peek()
+                    .ok_or_else(|| self.error("expected an escaped character"))?;
+                self.pos += escaped.len_utf8();
+                ident.push(escaped);
+            } else if is_ident_char(ch) && (self.pos > start || is_ident_start(ch) || ch == '-') {
+                self.pos += ch.len_utf8();
+                ident.push(ch);
+            } else {
+                break;
+            }
+        }
+        if ident.is_empty() || ident == "-" {
+            return Err(self.error("expected an identifier, found"));
+        }
+        Ok(ident)
+    }
+
+    fn parse_string(&mut self, quote: char) -> Result<String, String> {
+        let mut string = String::new();
+        loop {
+            let ch = self
+                .peek()
+                .ok_or_else(|| self.error("unterminated string"))?;
+            self.pos += ch.len_utf8();
+            match ch {
+                _ if ch == quote => return Ok(string),
+                '\\' => {
+                    let escaped = self
+                        .peek()
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    self.pos += escaped.len_utf8();
+                    string.push(escaped);
+                }
+                _ => string.push(ch),
+            }
+        }
+    }
+}
+
+fn is_ident_start(ch: char) -> bool {
+    ch.is_ascii_alphabetic() || ch == '_' || !ch.is_ascii()
+}
+
+fn is_ident_char(ch: char) -> bool {
+    is_ident_start(ch) || ch.is_ascii_digit() || ch == '-'
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use html5ever::{parse_document, tendril::TendrilSink};
+    use markup5ever_rcdom::{Node, NodeData, RcDom};
+
+    use super::SelectorList;
+
+    /// The `id`s of the elements matching `selector`.
+    fn select(html: &str, selector: &str) -> Vec<String> {
+        let dom = parse_document(RcDom::default(), Default::default()).one(html);
+        let selector = SelectorList::parse(selector).unwrap();
+        let mut ids = vec![];
+        let mut stack: Vec<Rc<Node>> = vec![dom.document.clone()];
+        while let Some(node) = stack.pop() {
+            if let NodeData::Element { attrs, .. } = &node.data
+                && selector.matches(&node)
+            {
+                let attrs = attrs.borrow();
+                let id = attrs.iter().find(|it| &it.name.local == "id");
+                ids.push(id.map(|it| it.value.to_string()).unwrap_or_default());
+            }
+            stack.extend(node.children.borrow().iter().rev().cloned());
+        }
+        ids
+    }
+
+    const HTML: &str = r#"
+        <div id="a" class="post main">
+            <p id="b" lang="en-US" data-x="Hello World">B</p>
+            <p id="c" class="note"><span id="d"></span></p>
+            <ul id="e"><li id="f">F</li></ul>
+        </div>
+        <div id="g" aria-hidden="true"></div>
+    "#;
+
+    #[test]
+    fn test_simple_selectors() {
+        assert_eq!(vec!["b", "c"], select(HTML, "p"));
+        assert_eq!(vec!["c"], select(HTML, ".note"));
+        assert_eq!(vec!["a"], select(HTML, "div.main.post"));
+        assert_eq!(vec!["e"], select(HTML, "#e"));
+        assert_eq!(vec!["g"], select(HTML, "[aria-hidden=true]"));
+        assert_eq!(vec!["g"], select(HTML, "DIV[ARIA-HIDDEN='true']"));
+        assert_eq!(vec!["b"], select(HTML, "[lang|=en]"));
+        assert_eq!(vec!["b"], select(HTML, "[data-x~=World]"));
+        assert_eq!(vec!["b"], select(HTML, "[data-x^='hello' i]"));
+        assert_eq!(vec!["b"], select(HTML, "[data-x$=World]"));
+        assert_eq!(vec!["b"], select(HTML, "[data-x*=\"o W\"]"));
+        assert_eq!(Vec::<String>::new(), select(HTML, "[data-x^='hello']"));
+    }
+
+    #[test]
+    fn test_combinators() {
+        assert_eq!(vec!["d"], select(HTML, "div span"));
+        assert_eq!(vec!["b", "c"], select(HTML, "div > p"));
+        assert_eq!(Vec::<String>::new(), select(HTML, "div > span"));
+        assert_eq!(vec!["c"], select(HTML, "#b + p"));
+        assert_eq!(vec!["c", "e"], select(HTML, "#b ~ *"));
+        assert_eq!(vec!["d", "f"], select(HTML, ".post p > span, ul li"));
+    }
+
+    #[test]
+    fn test_pseudo_classes() {
+        assert_eq!(
+            vec!["b", "d", "f"],
+            select(HTML, "body :first-child:not(div)")
+        );
+        assert_eq!(vec!["d", "f"], select(HTML, "div :only-child"));
+        assert_eq!(vec!["g"], select(HTML, "div:last-child"));
+        assert_eq!(vec!["d", "g"], select(HTML, "body :empty"));
+        assert_eq!(
+            vec!["c", "e"],
+            select(HTML, "#a > :not(#b, ul ~ *):not(#b)")
+        );
+    }
+
+    #[test]
+    fn test_many_siblings() {
+        let html = format!("<ul>{}</ul>", "<li>x</li>".repeat(5_000));
+        assert_eq!(1, select(&html, "li:first-child").len());
+        assert_eq!(1, select(&html, "li:last-child").len());
+        assert_eq!(0, select(&html, "li:only-child").len());
+        assert_eq!(4_999, select(&html, "li + li").len());
+        assert_eq!(4_999, select(&html, "li ~ li").len());
+    }
+
+    #[test]
+    fn test_invalid_selectors() {
+        for selector in [
+            "",
+            "div,",
+            "div >",
+            "a..b",
+            "[href",
+            "[href=]",
+            "[href=\"a]",
+            ":hover",
+            "p!",
+            "#",
+            ":not(p",
+        ] {
+            assert!(
+                SelectorList::parse(selector).is_err(),
+                "{selector:?} should be invalid"
+            );
+        }
+        assert_eq!(
+            Err("unsupported pseudo-class :hover at offset 1".to_string()),
+            SelectorList::parse("a:hover").map(|_| ())
+        );
+    }
+}
@@ -0,0 +1,567 @@
+//! Mapping of the Markdown back to the HTML elements it was converted from,
+//! see [`HtmlToMarkdown::convert_with_source_map`](crate::HtmlToMarkdown::convert_with_source_map).
+
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    ops::Range,
+    rc::Rc,
+};
+
+use html5ever::{
+    Attribute, ExpandedName, QualName,
+    tendril::StrTendril,
+    tree_builder::{ElementFlags, NodeOrText, QuirksMode, TreeSink},
+};
+use markup5ever_rcdom::{Node, RcDom};
+
+use crate::{
+    dom_walker::is_block_element,
+    element_handler::{ConversionContext, Handlers},
+};
+
+/// The Markdown of a document and the elements its blocks were converted
+/// from.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct SourceMap {
+    /// The Markdown, the same as returned by
+    /// [`HtmlToMarkdown::convert`](crate::HtmlToMarkdown::convert).
+    pub markdown: String,
+    /// The blocks of the Markdown, in the order they start. A block nested in
+    /// another one, e.g. a paragraph in a blockquote, comes after it.
+    pub blocks: Vec<MappedBlock>,
+}
+
+/// A block of the Markdown and the element it was converted from.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct MappedBlock {
+    /// The byte range of the block in the Markdown, without the blank lines
+    /// around it.
+    pub range: Range<usize>,
+    /// The tag name of the element.
+    pub tag: String,
+    /// The element.
+    pub node: Rc<Node>,
+    /// The line of the HTML the element was parsed at, starting at 1. `None`
+    /// if the tree was not parsed with line tracking.
+    pub line: Option<u64>,
+    /// The plain text of a heading, with collapsed whitespace.
+    pub(crate) heading_text: Option<String>,
+}
+
+/// A mapped block, in the Markdown it was last added to.
+pub(crate) struct Block {
+    /// The index of the block in [`SourceMapState::nodes`].
+    index: usize,
+    range: Range<usize>,
+}
+
+/// The blocks of an element being converted, or of a Markdown buffer being
+/// written.
+enum Frame {
+    /// The Markdown of the children of an element returned to its handler,
+    /// with the blocks in it.
+    Element(Vec<(String, Vec<Block>)>),
+    /// The blocks appended to a buffer, at their offsets in the buffer.
+    Output(Vec<Block>),
+}
+
+/// The blocks of the current source mapped conversion.
+///
+/// The offsets of the blocks are recorded when their Markdown is appended to
+/// a buffer. When a handler turns the Markdown of the children of its element
+/// into its own, the blocks are looked up in its result, allowing for the
+/// indentation and quote markers added to their lines.
+#[derive(Default)]
+pub(crate) struct SourceMapState {
+    /// The mapped elements, their tag, and the plain text of headings.
+    nodes: Vec<(Rc<Node>, String, Option<String>)>,
+    frames: Vec<Frame>,
+    pub(crate) lines: HashMap<*const Node, u64>,
+    /// The number of elements being converted whose content is not mapped.
+    opaque_depth: usize,
+    /// The text of the heading being converted.
+    heading_text: Option<String>,
+}
+
+impl SourceMapState {
+    /// Start mapping the blocks of the root buffer of the conversion.
+    pub(crate) fn start(context: &ConversionContext) {
+        let state = context.state().get::<SourceMapState>();
+        state.borrow_mut().frames.push(Frame::Output(vec![]));
+    }
+
+    /// Enter an element before converting it. Returns whether its Markdown
+    /// is mapped.
+    ///
+    /// Blocks are not mapped in elements whose Markdown is not made of the
+    /// Markdown of their blocks, like tables, or in elements converted by
+    /// custom handlers, which are mapped as a whole.
+    pub(crate) fn enter_element(context: &ConversionContext, tag: &str) -> bool {
+        let state = context.state().get::<SourceMapState>();
+        let mut state = state.borrow_mut();
+        let is_custom = context.handlers.has_custom_handler(tag);
+        let is_recording = state.opaque_depth == 0;
+        let mapped = is_recording
+            && (is_custom || is_block_element(tag) && !matches!(tag, "html" | "head" | "body"));
+        if is_recording {
+            state.frames.push(Frame::Element(vec![]));
+        }
+        if mapped && is_heading(tag) {
+            state.heading_text = Some(String::new());
+        }
+        if is_custom || is_opaque(tag) {
+            state.opaque_depth += 1;
+        }
+        mapped
+    }
+
+    /// Leave an element entered with [`Self::enter_element`], after it was
+    /// converted to `result`. Returns the blocks of its Markdown.
+    pub(crate) fn leave_element(
+        context: &ConversionContext,
+        node: &Rc<Node>,
+        tag: &str,
+        is_mapped: bool,
+        result: Option<&str>,
+    ) -> Vec<Block> {
+        let state = context.state().get::<SourceMapState>();
+        let mut state = state.borrow_mut();
+        if context.handlers.has_custom_handler(tag) || is_opaque(tag) {
+            state.opaque_depth -= 1;
+        }
+        let heading_text = if is_mapped && is_heading(tag) {
+            state.heading_text.take()
+        } else {
+            None
+        };
+        let walked = match state.opaque_depth {
+            0 => match state.frames.pop() {
+                Some(Frame::Element(walked)) => walked,
+                _ => vec![],
+            },
+            _ => vec![],
+        };
+        let Some(result) = result else {
+            return vec![];
+        };
+
+        let mut blocks = vec![];
+        let mut cursor = 0;
+        for (markdown, mut walked_blocks) in walked {
+            sort_blocks(&mut walked_blocks);
+            find_blocks(
+                &state,
+                &walked_blocks,
+                &markdown,
+                result,
+                &mut cursor,
+                &mut blocks,
+            );
+        }
+
+        let trimmed = result.trim();
+        if is_mapped && !trimmed.is_empty() {
+            let start = result.len() - result.trim_start().len();
+            blocks.push(Block {
+                index: state.nodes.len(),
+                range: start..start + trimmed.len(),
+            });
+            let heading_text =
+                heading_text.map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "));
+            state
+                .nodes
+                .push((node.clone(), tag.to_string(), heading_text));
+        }
+        blocks
+    }
+
+    /// Collect the text of a heading being converted.
+    pub(crate) fn push_text(context: &ConversionContext, text: &str) {
+        let state = context.state().get::<SourceMapState>();
+        if let Some(heading_text) = state.borrow_mut().heading_text.as_mut() {
+            heading_text.push_str(text);
+        }
+    }
+
+    /// Start mapping the blocks of a buffer the children of an element are
+    /// converted into. Returns whether the buffer is mapped.
+    pub(crate) fn enter_output(context: &ConversionContext) -> bool {
+        let state = context.state().get::<SourceMapState>();
+        let mut state = state.borrow_mut();
+        let is_mapped = state.opaque_depth == 0;
+        if is_mapped {
+            state.frames.push(Frame::Output(vec![]));
+        }
+        is_mapped
+    }
+
+    /// Finish a buffer entered with [`Self::enter_output`], whose Markdown is
+    /// returned to the handler of the element being converted.
+    pub(crate) fn leave_output(context: &ConversionContext, is_mapped: bool, markdown: &str) {
+        if !is_mapped {
+            return;
+        }
+        let state = context.state().get::<SourceMapState>();
+        let mut state = state.borrow_mut();
+        let Some(Frame::Output(blocks)) = state.frames.pop() else {
+            return;
+        };
+        if !blocks.is_empty()
+            && let Some(Frame::Element(walked)) = state.frames.last_mut()
+        {
+            walked.push((markdown.to_string(), blocks));
+        }
+    }
+
+    /// Record the blocks of Markdown of `len` bytes that was appended to the
+    /// current buffer, which is now `output_len` bytes long. Leading
+    /// whitespace of the Markdown may have been dropped.
+    pub(crate) fn appended(
+        context: &ConversionContext,
+        blocks: Vec<Block>,
+        len: usize,
+        output_len: usize,
+    ) {
+        let state = context.state().get::<SourceMapState>();
+        let mut state = state.borrow_mut();
+        let Some(Frame::Output(output_blocks)) = state.frames.last_mut() else {
+            return;
+        };
+        let offset = |position: usize| output_len - (len - position);
+        output_blocks.extend(blocks.into_iter().map(|block| Block {
+            index: block.index,
+            range: offset(block.range.start)..offset(block.range.end),
+        }));
+    }
+
+    /// Move the blocks of the root buffer after `removed` bytes were removed
+    /// from its start, and then `inserted` bytes were inserted.
+    pub(crate) fn move_root(context: &ConversionContext, removed: usize, inserted: usize) {
+        let state = context.state().get::<SourceMapState>();
+        let mut state = state.borrow_mut();
+        if let Some(Frame::Output(blocks)) = state.frames.first_mut() {
+            for block in blocks {
+                block.range =
+                    block.range.start - removed + inserted..block.range.end - removed + inserted;
+            }
+        }
+    }
+
+    /// Map the blocks of the root buffer to their ranges in `markdown`.
+    pub(crate) fn resolve(context: &ConversionContext, markdown: String) -> SourceMap {
+        let state = context.state().get::<SourceMapState>();
+        let mut state = state.borrow_mut();
+        let mut blocks = match state.frames.drain(..).next() {
+            Some(Frame::Output(blocks)) => blocks,
+            _ => vec![],
+        };
+        sort_blocks(&mut blocks);
+        let blocks = blocks
+            .into_iter()
+            .filter(|block| block.range.end <= markdown.len())
+            .map(|block| {
+                let (node, tag, heading_text) = &state.nodes[block.index];
+                MappedBlock {
+                    range: block.range,
+                    tag: tag.clone(),
+                    node: node.clone(),
+                    line: state.lines.get(&Rc::as_ptr(node)).copied(),
+                    heading_text: heading_text.clone(),
+                }
+            })
+            .collect();
+        SourceMap { markdown, blocks }
+    }
+}
+
+/// Sort blocks in the order they start, with parents, which are added after
+/// their children, first.
+fn sort_blocks(blocks: &mut [Block]) {
+    blocks.sort_by(|a, b| {
+        a.range
+            .start
+            .cmp(&b.range.start)
+            .then(b.range.end.cmp(&a.range.end))
+            .then(b.index.cmp(&a.index))
+    });
+}
+
+/// Find the blocks of `markdown` in `result`, the Markdown the handler of an
+/// element made of it, from `cursor` on. A block that is not found, because
+/// the handler changed it, is left out, and its children are looked up
+/// instead.
+fn find_blocks(
+    state: &SourceMapState,
+    blocks: &[Block],
+    markdown: &str,
+    result: &str,
+    cursor: &mut usize,
+    found: &mut Vec<Block>,
+) {
+    let mut index = 0;
+    while index < blocks.len() {
+        let block = &blocks[index];
+        let end = index
+            + 1
+            + blocks[index + 1..]
+                .iter()
+                .take_while(|it| it.range.end <= block.range.end)
+                .count();
+        let Some(lines) = find_lines(&markdown[block.range.clone()], result, *cursor) else {
+            find_blocks(
+                state,
+                &blocks[index + 1..end],
+                markdown,
+                result,
+                cursor,
+                found,
+            );
+            index = end;
+            continue;
+        };
+        for child in &blocks[index..end] {
+            let start = lines.position(child.range.start - block.range.start);
+            let end = lines.position(child.range.end - block.range.start);
+            found.push(Block {
+                index: child.index,
+                range: start..end,
+            });
+        }
+        *cursor = lines.position(block.range.len());
+        // The marker of an item added by its list is part of the item.
+        if state.nodes[block.index].1 == "li"
+            && let Some(item) = found.iter_mut().rev().find(|it| it.index == block.index)
+        {
+            let line_start = result[..item.range.start]
+                .rfind('\n')
+                .map_or(0, |it| it + 1);
+            let prefix = result[line_start..item.range.start].trim_start_matches(LINE_PREFIX);
+            if is_list_marker(prefix) {
+                item.range.start -= prefix.len();
+            }
+        }
+        index = end;
+    }
+}
+
+/// The characters of indentation and quote markers added before the lines of
+/// nested blocks.
+pub(crate) const LINE_PREFIX: [char; 3] = [' ', '\t', '>'];
+
+/// The lines of a block found in the Markdown of a parent.
+struct FoundLines {
+    /// The offsets of the lines in the block.
+    block_lines: Vec<usize>,
+    /// The offsets of the lines in the Markdown of the parent.
+    lines: Vec<usize>,
+}
+
+impl FoundLines {
+    /// The offset in the Markdown of the parent of `position` in the block.
+    fn position(&self, position: usize) -> usize {
+        let line = self.block_lines.partition_point(|it| *it <= position) - 1;
+        self.lines[line] + position - self.block_lines[line]
+    }
+}
+
+/// Find `block` in `markdown` from `from` on, with any indentation and quote
+/// markers added before its lines but the first, and trailing spaces
+/// removed.
+fn find_lines(block: &str, markdown: &str, from: usize) -> Option<FoundLines> {
+    let mut block_lines = vec![0];
+    block_lines.extend(block.match_indices('\n').map(|(index, _)| index + 1));
+    let mut lines = block.split('\n').map(|line| line.trim_end_matches(' '));
+    let first = lines.next().filter(|it| !it.is_empty())?;
+    let rest: Vec<&str> = lines.collect();
+
+    'candidates: for (start, _) in markdown.get(from..)?.match_indices(first) {
+        let start = from + start;
+        let mut found = vec![start];
+        let mut position = start + first.len();
+        for (index, line) in rest.iter().enumerate() {
+            position = markdown.len() - markdown[position..].trim_start_matches(' ').len();
+            if !markdown[position..].starts_with('\n') {
+                continue 'candidates;
+            }
+            let line_start = position + 1;
+            let markdown_line = markdown[line_start..]
+                .split('\n')
+                .next()
+                .unwrap_or_default();
+            let prefix_len =
+                markdown_line.len() - markdown_line.trim_start_matches(LINE_PREFIX).len();
+            let is_last = index == rest.len() - 1;
+            let indent = (0..=prefix_len).find(|indent| {
+                let rest = &markdown_line[*indent..];
+                if is_last {
+                    rest.starts_with(line)
+                } else {
+                    rest.trim_end_matches(' ') == *line
+                }
+            });
+            let Some(indent) = indent else {
+                continue 'candidates;
+            };
+            found.push(line_start + indent);
+            position = line_start + indent + line.len();
+        }
+        return Some(FoundLines {
+            block_lines,
+            lines: found,
+        });
+    }
+    None
+}
+
+/// Whether `text` is the marker of a list item, e.g. `1.  ` or `*   `.
+fn is_list_marker(text: &str) -> bool {
+    let marker = text.trim_end_matches(' ');
+    if marker.len() == text.len() {
+        return false;
+    }
+    match marker.strip_suffix(['.', ')']) {
+        Some(number) => !number.is_empty() && number.chars().all(|ch| ch.is_ascii_digit()),
+        None => matches!(marker, "*" | "-" | "+"),
+    }
+}
+
+fn is_heading(tag: &str) -> bool {
+    matches!(tag, "h1" | "h2" | "h3" | "h4" | "h5" | "h6")
+}
+
+/// Elements whose Markdown is not made of the Markdown of their blocks.
+fn is_opaque(tag: &str) -> bool {
+    matches!(
+        tag,
+        "table" | "pre" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6"
+    )
+}
+
+/// A [`TreeSink`] building an [`RcDom`], which records the line each element
+/// is created at.
+pub(crate) struct LineTrackingSink {
+    dom: RcDom,
+    line: Cell<u64>,
+    lines: RefCell<HashMap<*const Node, u64>>,
+}
+
+impl Default for LineTrackingSink {
+    fn default() -> Self {
+        Self {
+            dom: RcDom::default(),
+            // The parser only reports line changes.
+            line: Cell::new(1),
+            lines: RefCell::default(),
+        }
+    }
+}
+
+impl TreeSink for LineTrackingSink {
+    type Handle = Rc<Node>;
+    type Output = (RcDom, HashMap<*const Node, u64>);
+    type ElemName<'a>
+        = ExpandedName<'a>
+    where
+        Self: 'a;
+
+    fn finish(self) -> Self::Output {
+        (self.dom, self.lines.into_inner())
+    }
+
+    fn parse_error(&self, msg: Cow<'static, str>) {
+        self.dom.parse_error(msg);
+    }
+
+    fn get_document(&self) -> Rc<Node> {
+        self.dom.get_document()
+    }
+
+    fn elem_name<'a>(&'a self, target: &'a Rc<Node>) -> ExpandedName<'a> {
+        self.dom.elem_name(target)
+    }
+
+    fn create_element(
+        &self,
+        name: QualName,
+        attrs: Vec<Attribute>,
+        flags: ElementFlags,
+    ) -> Rc<Node> {
+        let element = self.dom.create_element(name, attrs, flags);
+        self.lines
+            .borrow_mut()
+            .insert(Rc::as_ptr(&element), self.line.get());
+        element
+    }
+
+    fn create_comment(&self, text: StrTendril) -> Rc<Node> {
+        self.dom.create_comment(text)
+    }
+
+    fn create_pi(&self, target: StrTendril, data: StrTendril) -> Rc<Node> {
+        self.dom.create_pi(target, data)
+    }
+
+    fn append(&self, parent: &Rc<Node>, child: NodeOrText<Rc<Node>>) {
+        self.dom.append(parent, child);
+    }
+
+    fn append_based_on_parent_node(
+        &self,
+        element: &Rc<Node>,
+        prev_element: &Rc<Node>,
+        child: NodeOrText<Rc<Node>>,
+    ) {
+        self.dom
+            .append_based_on_parent_node(element, prev_element, child);
+    }
+
+    fn append_doctype_to_document(
+        &self,
+        name: StrTendril,
+        public_id: StrTendril,
+        system_id: StrTendril,
+    ) {
+        self.dom
+            .append_doctype_to_document(name, public_id, system_id);
+    }
+
+    fn get_template_contents(&self, target: &Rc<Node>) -> Rc<Node> {
+        self.dom.get_template_contents(target)
+    }
+
+    fn same_node(&self, x: &Rc<Node>, y: &Rc<Node>) -> bool {
+        self.dom.same_node(x, y)
+    }
+
+    fn set_quirks_mode(&self, mode: QuirksMode) {
+        self.dom.set_quirks_mode(mode);
+    }
+
+    fn append_before_sibling(&self, sibling: &Rc<Node>, new_node: NodeOrText<Rc<Node>>) {
+        self.dom.append_before_sibling(sibling, new_node);
+    }
+
+    fn add_attrs_if_missing(&self, target: &Rc<Node>, attrs: Vec<Attribute>) {
+        self.dom.add_attrs_if_missing(target, attrs);
+    }
+
+    fn remove_from_parent(&self, target: &Rc<Node>) {
+        self.dom.remove_from_parent(target);
+    }
+
+    fn reparent_children(&self, node: &Rc<Node>, new_parent: &Rc<Node>) {
+        self.dom.reparent_children(node, new_parent);
+    }
+
+    fn is_mathml_annotation_xml_integration_point(&self, handle: &Rc<Node>) -> bool {
+        self.dom.is_mathml_annotation_xml_integration_point(handle)
+    }
+
+    fn set_current_line(&self, line_number: u64) {
+        self.line.set(line_number);
+    }
+}
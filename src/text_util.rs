@@ -245,6 +245,13 @@ pub(crate) fn indent_text_except_first_line(
     result
 }
 
+/// The number of columns the text occupies in a terminal or an editor using a
+/// monospaced font. East Asian wide characters and emoji take two columns,
+/// zero width joiners and combining marks take none.
+pub(crate) fn display_width(text: &str) -> usize {
+    UnicodeWidthStr::width(text)
+}
+
 pub(crate) fn is_markdown_atx_heading(text: &str) -> bool {
     let mut is_prev_ch_hash = false;
     for ch in text.chars() {
@@ -302,12 +309,13 @@ macro_rules! concat_strings {
     }};
 }
 use std::borrow::Cow;
+use unicode_width::UnicodeWidthStr;
 
 pub(crate) use concat_strings;
 
 #[cfg(test)]
 mod tests {
-    use super::index_of_markdown_ordered_item_dot;
+    use super::{display_width, index_of_markdown_ordered_item_dot};
 
     #[test]
     fn test_index_of_markdown_ordered_item_dot() {
@@ -324,6 +332,17 @@ mod tests {
         assert_eq!(None, index_of_markdown_ordered_item_dot("1."));
     }
 
+    #[test]
+    fn test_display_width() {
+        assert_eq!(5, display_width("Hello"));
+        assert_eq!(4, display_width("你好"));
+        assert_eq!(10, display_width("こんにちは"));
+        // Combining acute accent
+        assert_eq!(1, display_width("e\u{301}"));
+        // Family emoji joined by zero width joiners
+        assert_eq!(2, display_width("👨\u{200d}👩\u{200d}👧"));
+    }
+
     #[test]
     fn test_index_of_markdown_ordered_item_dot_multibyte() {
         // U+00BD (½) is 2 bytes in UTF-8: the dot byte offset is 3, not 2
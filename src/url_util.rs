@@ -0,0 +1,256 @@
+/// The components of a URI reference, see
+/// [RFC 3986](https://www.rfc-editor.org/rfc/rfc3986#appendix-B).
+struct UrlParts<'a> {
+    scheme: Option<&'a str>,
+    authority: Option<&'a str>,
+    path: &'a str,
+    query: Option<&'a str>,
+    fragment: Option<&'a str>,
+}
+
+impl<'a> UrlParts<'a> {
+    fn parse(url: &'a str) -> Self {
+        let (rest, fragment) = match url.split_once('#') {
+            Some((rest, fragment)) => (rest, Some(fragment)),
+            None => (url, None),
+        };
+        let (rest, query) = match rest.split_once('?') {
+            Some((rest, query)) => (rest, Some(query)),
+            None => (rest, None),
+        };
+        let (scheme, rest) = match scheme_len(rest) {
+            Some(len) => (Some(&rest[..len]), &rest[len + 1..]),
+            None => (None, rest),
+        };
+        let (authority, path) = match rest.strip_prefix("//") {
+            Some(rest) => {
+                let end = rest.find('/').unwrap_or(rest.len());
+                (Some(&rest[..end]), &rest[end..])
+            }
+            None => (None, rest),
+        };
+        Self {
+            scheme,
+            authority,
+            path,
+            query,
+            fragment,
+        }
+    }
+}
+
+/// The length of the scheme of `url`, excluding the `:`.
+fn scheme_len(url: &str) -> Option<usize> {
+    let colon = url.find(':')?;
+    let scheme = &url[..colon];
+    let mut chars = scheme.chars();
+    if !chars.next()?.is_ascii_alphabetic() {
+        return None;
+    }
+    if chars.all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '+' | '-' | '.')) {
+        Some(colon)
+    } else {
+        None
+    }
+}
+
+/// Returns true if the url has a scheme, e.g. `https:` or `mailto:`.
+pub(crate) fn is_absolute_url(url: &str) -> bool {
+    scheme_len(url.trim()).is_some()
+}
+
+/// Resolve `url` against the absolute `base` URL, following
+/// [RFC 3986](https://www.rfc-editor.org/rfc/rfc3986#section-5.2).
+///
+/// Fragment-only references (`#section`) are kept as-is, so that they still
+/// point into the converted document. Returns `url` unchanged if `base` is
+/// not absolute.
+pub(crate) fn resolve_url(base: &str, url: &str) -> String {
+    let url = url.trim();
+    if url.starts_with('#') || !is_absolute_url(base) {
+        return url.to_string();
+    }
+
+    let base = UrlParts::parse(base.trim());
+    let reference = UrlParts::parse(url);
+
+    let mut path;
+    let (scheme, authority, query);
+    if reference.scheme.is_some() {
+        scheme = reference.scheme;
+        authority = reference.authority;
+        path = remove_dot_segments(reference.path);
+        query = reference.query;
+    } else if reference.authority.is_some() {
+        scheme = base.scheme;
+        authority = reference.authority;
+        path = remove_dot_segments(reference.path);
+        query = reference.query;
+    } else {
+        scheme = base.scheme;
+        authority = base.authority;
+        if reference.path.is_empty() {
+            path = base.path.to_string();
+            query = reference.query.or(base.query);
+        } else {
+            if reference.path.starts_with('/') {
+                path = remove_dot_segments(reference.path);
+            } else {
+                path = remove_dot_segments(&merge_paths(&base, reference.path));
+            }
+            query = reference.query;
+        }
+    }
+
+    if authority.is_some() && !path.is_empty() && !path.starts_with('/') {
+        path.insert(0, '/');
+    }
+
+    let mut result = String::with_capacity(url.len() + base.path.len() + 16);
+    if let Some(scheme) = scheme {
+        result.push_str(scheme);
+        result.push(':');
+    }
+    if let Some(authority) = authority {
+        result.push_str("//");
+        result.push_str(authority);
+    }
+    result.push_str(&path);
+    if let Some(query) = query {
+        result.push('?');
+        result.push_str(query);
+    }
+    if let Some(fragment) = reference.fragment {
+        result.push('#');
+        result.push_str(fragment);
+    }
+    result
+}
+
+fn merge_paths(base: &UrlParts, path: &str) -> String {
+    if base.authority.is_some() && base.path.is_empty() {
+        return format!("/{path}");
+    }
+    match base.path.rfind('/') {
+        Some(idx) => format!("{}{path}", &base.path[..=idx]),
+        None => path.to_string(),
+    }
+}
+
+/// See [RFC 3986](https://www.rfc-editor.org/rfc/rfc3986#section-5.2.4).
+fn remove_dot_segments(path: &str) -> String {
+    let mut input = path;
+    let mut output = String::with_capacity(path.len());
+
+    let pop_last_segment = |output: &mut String| {
+        let idx = output.rfind('/').unwrap_or(0);
+        output.truncate(idx);
+    };
+
+    while !input.is_empty() {
+        if let Some(rest) = input.strip_prefix("../") {
+            input = rest;
+        } else if let Some(rest) = input.strip_prefix("./") {
+            input = rest;
+        } else if input.starts_with("/./") {
+            input = &input[2..];
+        } else if input == "/." {
+            input = "/";
+        } else if input.starts_with("/../") {
+            input = &input[3..];
+            pop_last_segment(&mut output);
+        } else if input == "/.." {
+            input = "/";
+            pop_last_segment(&mut output);
+        } else if input == "." || input == ".." {
+            input = "";
+        } else {
+            let start = usize::from(input.starts_with('/'));
+            let end = input[start..]
+                .find('/')
+                .map_or(input.len(), |idx| idx + start);
+            output.push_str(&input[..end]);
+            input = &input[end..];
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{remove_dot_segments, resolve_url};
+
+    // Examples from https://www.rfc-editor.org/rfc/rfc3986#section-5.4
+    #[test]
+    fn test_resolve_rfc_examples() {
+        let base = "http://a/b/c/d;p?q";
+        let cases = [
+            ("g:h", "g:h"),
+            ("g", "http://a/b/c/g"),
+            ("./g", "http://a/b/c/g"),
+            ("g/", "http://a/b/c/g/"),
+            ("/g", "http://a/g"),
+            ("//g", "http://g"),
+            ("?y", "http://a/b/c/d;p?y"),
+            ("g?y", "http://a/b/c/g?y"),
+            ("g#s", "http://a/b/c/g#s"),
+            ("g?y#s", "http://a/b/c/g?y#s"),
+            (";x", "http://a/b/c/;x"),
+            ("g;x", "http://a/b/c/g;x"),
+            ("", "http://a/b/c/d;p?q"),
+            (".", "http://a/b/c/"),
+            ("./", "http://a/b/c/"),
+            ("..", "http://a/b/"),
+            ("../", "http://a/b/"),
+            ("../g", "http://a/b/g"),
+            ("../..", "http://a/"),
+            ("../../", "http://a/"),
+            ("../../g", "http://a/g"),
+            ("../../../g", "http://a/g"),
+            ("/./g", "http://a/g"),
+            ("/../g", "http://a/g"),
+            ("g.", "http://a/b/c/g."),
+            ("..g", "http://a/b/c/..g"),
+            ("./../g", "http://a/b/g"),
+            ("./g/.", "http://a/b/c/g/"),
+            ("g/./h", "http://a/b/c/g/h"),
+            ("g/../h", "http://a/b/c/h"),
+            ("g;x=1/./y", "http://a/b/c/g;x=1/y"),
+            ("g;x=1/../y", "http://a/b/c/y"),
+        ];
+        for (reference, expected) in cases {
+            assert_eq!(expected, resolve_url(base, reference), "{reference}");
+        }
+    }
+
+    #[test]
+    fn test_resolve_keeps_fragments_and_absolute_urls() {
+        let base = "https://example.com/docs/page.html";
+        assert_eq!("#intro", resolve_url(base, "#intro"));
+        assert_eq!(
+            "mailto:hi@example.com",
+            resolve_url(base, "mailto:hi@example.com")
+        );
+        assert_eq!(
+            "https://example.com/img/a.png",
+            resolve_url(base, " ../img/a.png ")
+        );
+        assert_eq!(
+            "https://example.com/",
+            resolve_url("https://example.com", ".")
+        );
+        assert_eq!(
+            "https://example.com/wiki/Foo",
+            resolve_url("https://example.com", "wiki/Foo")
+        );
+        // Relative base URLs are ignored
+        assert_eq!("wiki/Foo", resolve_url("/base/", "wiki/Foo"));
+    }
+
+    #[test]
+    fn test_remove_dot_segments() {
+        assert_eq!("/a/g", remove_dot_segments("/a/b/c/./../../g"));
+        assert_eq!("mid/6", remove_dot_segments("mid/content=5/../6"));
+    }
+}
@@ -1,12 +1,15 @@
-use std::{sync::Arc, thread::JoinHandle};
+use std::{
+    sync::{Arc, Barrier},
+    thread::JoinHandle,
+};
 
 use indoc::indoc;
 use pretty_assertions::assert_eq;
 
 use htmd::{
-    Element, HtmlToMarkdown,
-    element_handler::Handlers,
-    options::{BrStyle, LinkStyle, Options, TranslationMode},
+    Element, HtmlToMarkdown, Limit, Limits,
+    element_handler::{ElementHandler, HandlerResult, Handlers},
+    options::{BrStyle, FootnoteStyle, LinkStyle, Options, TranslationMode},
 };
 mod common;
 use common::convert;
@@ -59,10 +62,9 @@ fn consecutive_referenced_links_with_title() {
     assert_eq!(
         indoc!(
             r#"
-        [Example][1][Another example][2]
+        [Example][1][Another example][1]
 
-        [1]: https://example.com "Some title"
-        [2]: https://example.com "Some title""#
+        [1]: https://example.com "Some title""#
         ),
         &md
     )
@@ -541,6 +543,28 @@ fn with_custom_rules() {
     assert_eq!("", &md);
 }
 
+#[test]
+fn with_custom_rules_appending_content() {
+    struct Footer;
+
+    impl ElementHandler for Footer {
+        fn append(&self) -> Option<String> {
+            Some("\n\nThe end".to_string())
+        }
+
+        fn handle(&self, handlers: &dyn Handlers, element: Element) -> Option<HandlerResult> {
+            handlers.fallback(element)
+        }
+    }
+
+    let md = HtmlToMarkdown::builder()
+        .add_handler(vec!["footer"], Footer)
+        .build()
+        .convert("<p>Text</p>")
+        .unwrap();
+    assert_eq!("Text\n\nThe end", &md);
+}
+
 #[test]
 fn with_custom_rules_and_fallback() {
     let html = r#"<img src="https://example.com"/>"#;
@@ -613,20 +637,19 @@ fn scripting_option() {
 
 #[test]
 fn multithreading() {
-    let html = r#"<a href="https://example.com">Example</a>
-    <a href="https://example.com">Example</a>
-    <a href="https://example.com">Example</a>
-    <a href="https://example.com">Example</a>
-    <a href="https://example.com">Example</a>
+    let html = r#"<a href="https://example.com/1">Example</a>
+    <a href="https://example.com/2">Example</a>
+    <a href="https://example.com/3">Example</a>
+    <a href="https://example.com/4">Example</a>
+    <a href="https://example.com/5">Example</a>
     "#;
     let expected = "[Example][1] [Example][2] [Example][3] [Example][4] [Example][5]\n\n\
-    [1]: https://example.com\n[2]: https://example.com\n[3]: https://example.com\n\
-    [4]: https://example.com\n[5]: https://example.com";
+    [1]: https://example.com/1\n[2]: https://example.com/2\n[3]: https://example.com/3\n\
+    [4]: https://example.com/4\n[5]: https://example.com/5";
     let converter = HtmlToMarkdown::builder()
         .options(Options {
-            // We use a global vec to store all referenced links of the doc in
-            // the anchor element handler, this is unsafe for multithreading
-            // usage if we do nothing
+            // The anchor element handler collects the referenced links of the
+            // doc while converting, which must not leak between threads
             link_style: LinkStyle::Referenced,
             translation_mode: TranslationMode::Faithful,
             ..Default::default()
@@ -647,6 +670,47 @@ fn multithreading() {
     }
 }
 
+#[test]
+fn concurrent_conversions_are_independent() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<HtmlToMarkdown>();
+
+    let converter = Arc::new(
+        HtmlToMarkdown::builder()
+            .options(Options {
+                link_style: LinkStyle::Referenced,
+                footnote_style: FootnoteStyle::Markdown,
+                ..Default::default()
+            })
+            .build(),
+    );
+    let barrier = Arc::new(Barrier::new(8));
+    let handles: Vec<JoinHandle<()>> = (0..8)
+        .map(|thread| {
+            let converter = converter.clone();
+            let barrier = barrier.clone();
+            std::thread::spawn(move || {
+                barrier.wait();
+                for round in 0..50 {
+                    let id = thread * 1000 + round;
+                    let html = format!(
+                        r##"<p><a href="https://example.com/{id}">Doc {id}</a>
+                        <a href="#fn{id}" class="footnote-ref">{id}</a></p>
+                        <section class="footnotes"><ol><li id="fn{id}">Note {id}</li></ol></section>"##
+                    );
+                    let expected = format!(
+                        "[Doc {id}][1] [^1]\n\n[1]: https://example.com/{id}\n\n[^1]: Note {id}"
+                    );
+                    assert_eq!(expected, converter.convert(&html).unwrap());
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
 #[test]
 fn unterminated_html() {
     // The `<i>` tag isn't terminated. Make sure the conversion still works.
@@ -743,3 +807,354 @@ fn multibyte_atx_heading_escape_umlaut() {
     let md = convert("<p>## über</p>").unwrap();
     assert_eq!(r"\## über", md);
 }
+
+#[test]
+fn convert_reader() {
+    // Yields one byte at a time, splitting multibyte characters.
+    struct SlowReader<'a>(&'a [u8]);
+
+    impl std::io::Read for SlowReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let Some((first, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+            buf[0] = *first;
+            self.0 = rest;
+            Ok(1)
+        }
+    }
+
+    let html = "<h1>Grüße</h1><p>你好, <b>world</b></p>";
+    let md = HtmlToMarkdown::new()
+        .convert_reader(SlowReader(html.as_bytes()))
+        .unwrap();
+    assert_eq!("# Grüße\n\n你好, **world**", md);
+}
+
+#[test]
+fn convert_to_writer() {
+    let converter = HtmlToMarkdown::builder()
+        .options(Options {
+            link_style: LinkStyle::Referenced,
+            ..Default::default()
+        })
+        .build();
+    let html = r#"<p>A <a href="https://example.com">link</a></p>"#;
+    let mut output = vec![];
+    converter.convert_to_writer(html, &mut output).unwrap();
+    assert_eq!(
+        converter.convert(html).unwrap(),
+        String::from_utf8(output).unwrap()
+    );
+}
+
+#[test]
+fn convert_to_writer_streams_blocks() {
+    // Records the size of every write.
+    struct Writes(Vec<u8>, Vec<usize>);
+
+    impl std::io::Write for Writes {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.extend_from_slice(buf);
+            self.1.push(buf.len());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let converter = HtmlToMarkdown::builder()
+        .options(Options {
+            link_style: LinkStyle::Referenced,
+            ..Default::default()
+        })
+        .build();
+    let html = format!(
+        "<title>Title</title>{}",
+        r#"<h2>Section</h2><p>Some <a href="/a">text</a>  </p>  <ul><li>Item</li></ul>"#
+            .repeat(2_000)
+    );
+    let mut writes = Writes(vec![], vec![]);
+    converter.convert_to_writer(&html, &mut writes).unwrap();
+    let md = converter.convert(&html).unwrap();
+    assert_eq!(md, String::from_utf8(writes.0).unwrap());
+    assert!(writes.1.len() > 10);
+    assert!(writes.1.iter().all(|len| *len < md.len() / 4));
+}
+
+#[test]
+fn convert_to_fmt_writer() {
+    let converter = HtmlToMarkdown::new();
+    let html = "<h1>Hello</h1><p>World</p>";
+    let mut md = String::new();
+    converter.convert_to_fmt_writer(html, &mut md).unwrap();
+    assert_eq!(converter.convert(html).unwrap(), md);
+
+    struct Failing;
+
+    impl std::fmt::Write for Failing {
+        fn write_str(&mut self, _: &str) -> std::fmt::Result {
+            Err(std::fmt::Error)
+        }
+    }
+
+    let err = converter.convert_to_fmt_writer(html, Failing).unwrap_err();
+    assert!(matches!(err, htmd::Error::Write(_)));
+}
+
+#[test]
+fn convert_fragment() {
+    let converter = HtmlToMarkdown::new();
+    // No implied `<head>`, the title stays in place
+    assert_eq!(
+        "Before\n\nTitle\n\nAfter",
+        converter
+            .convert_fragment("<p>Before</p><title>Title</title><p>After</p>", "body")
+            .unwrap()
+    );
+    // Table rows are not dropped by the parser
+    assert_eq!(
+        indoc!(
+            "
+            | Name | Age |
+            | ---- | --- |
+            | Ann  | 42  |"
+        ),
+        converter
+            .convert_fragment(
+                "<tr><th>Name</th><th>Age</th></tr><tr><td>Ann</td><td>42</td></tr>",
+                "tbody"
+            )
+            .unwrap()
+    );
+    assert_eq!(
+        "*   One\n*   Two",
+        converter.convert_fragment("<li>One<li>Two", "ul").unwrap()
+    );
+    // In a document, the stray rows are dropped
+    assert_eq!(
+        "NameAgeAnn42",
+        convert("<tr><th>Name</th><th>Age</th></tr><tr><td>Ann</td><td>42</td></tr>").unwrap()
+    );
+}
+
+#[test]
+fn io_errors() {
+    struct Failing;
+
+    impl std::io::Read for Failing {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("disk on fire"))
+        }
+    }
+
+    impl std::io::Write for Failing {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("disk on fire"))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let converter = HtmlToMarkdown::new();
+    let err = converter.convert_reader(Failing).unwrap_err();
+    assert!(matches!(err, htmd::Error::Parse(_)));
+    assert_eq!("failed to parse HTML: disk on fire", err.to_string());
+
+    let err = converter
+        .convert_to_writer("<p>Hi</p>", Failing)
+        .unwrap_err();
+    assert!(matches!(err, htmd::Error::Write(_)));
+    assert_eq!("failed to write Markdown: disk on fire", err.to_string());
+}
+
+#[test]
+fn serialize_error() {
+    // Falls back to the built-in handler with a text node, which can't be
+    // serialized as an element.
+    let converter = HtmlToMarkdown::builder()
+        .options(Options {
+            translation_mode: TranslationMode::Faithful,
+            ..Default::default()
+        })
+        .add_handler(vec!["em"], |handlers: &dyn Handlers, element: Element| {
+            let text = element.node.children.borrow()[0].clone();
+            let mut element = element;
+            element.node = &text;
+            handlers.fallback(element)
+        })
+        .build();
+    let err = converter
+        .convert(r#"<p>Some <em class="x">text</em></p>"#)
+        .unwrap_err();
+    assert!(matches!(err, htmd::Error::Serialize { ref tag, .. } if tag == "em"));
+    assert_eq!(
+        "failed to serialize <em> to HTML: Not an element.",
+        err.to_string()
+    );
+}
+
+#[test]
+fn nesting_depth_limit() {
+    // 256 by default
+    let html = "<div>".repeat(20_000);
+    assert!(matches!(
+        htmd::convert(&html),
+        Err(htmd::Error::LimitExceeded {
+            limit: Limit::Depth,
+            max: 256
+        })
+    ));
+    let converter = HtmlToMarkdown::builder()
+        .limits(Limits {
+            max_depth: None,
+            ..Default::default()
+        })
+        .build();
+    let html = format!("{}x", "<div>".repeat(300));
+    assert_eq!("x", converter.convert(&html).unwrap());
+
+    let converter = HtmlToMarkdown::builder()
+        .limits(Limits {
+            max_depth: Some(256),
+            ..Default::default()
+        })
+        .build();
+    let html = format!("{}Deep{}", "<div>".repeat(5_000), "</div>".repeat(5_000));
+    let err = converter.convert(&html).unwrap_err();
+    assert!(matches!(
+        err,
+        htmd::Error::LimitExceeded {
+            limit: Limit::Depth,
+            max: 256
+        }
+    ));
+    assert_eq!("exceeded the maximum nesting depth of 256", err.to_string());
+
+    let html = format!("{}Deep{}", "<div>".repeat(200), "</div>".repeat(200));
+    assert_eq!("Deep", converter.convert(&html).unwrap());
+
+    let converter = HtmlToMarkdown::builder()
+        .limits(Limits {
+            max_depth: Some(10),
+            ..Default::default()
+        })
+        .build();
+    // Whitespace is kept inside deeply nested code blocks
+    let html = format!(
+        "<pre><code>{}a  b\n  c{}</code></pre>",
+        "<span>".repeat(5),
+        "</span>".repeat(5)
+    );
+    assert_eq!("```\na  b\n  c\n```", converter.convert(&html).unwrap());
+    let html = html.replace("<span>", "<span><span>");
+    assert!(converter.convert(&html).is_err());
+}
+
+#[test]
+fn input_and_output_limits() {
+    let converter = HtmlToMarkdown::builder()
+        .limits(Limits {
+            max_input_bytes: Some(16),
+            ..Default::default()
+        })
+        .build();
+    assert_eq!("Hello", converter.convert("<p>Hello</p>").unwrap());
+    for result in [
+        converter.convert("<p>Hello world</p>"),
+        converter.convert_reader("<p>Hello world</p>".as_bytes()),
+        converter.convert_fragment("<p>Hello world</p>", "body"),
+    ] {
+        assert!(matches!(
+            result,
+            Err(htmd::Error::LimitExceeded {
+                limit: Limit::InputBytes,
+                max: 16
+            })
+        ));
+    }
+
+    let converter = HtmlToMarkdown::builder()
+        .limits(Limits {
+            max_output_bytes: Some(8),
+            ..Default::default()
+        })
+        .build();
+    assert_eq!("# Hello", converter.convert("<h1>Hello</h1>").unwrap());
+    assert!(matches!(
+        converter.convert("<h1>Hello</h1><p>World</p>"),
+        Err(htmd::Error::LimitExceeded {
+            limit: Limit::OutputBytes,
+            max: 8
+        })
+    ));
+    let html = "<p>Hello</p>".repeat(2_000);
+    assert!(matches!(
+        converter.convert_to_writer(&html, std::io::sink()),
+        Err(htmd::Error::LimitExceeded {
+            limit: Limit::OutputBytes,
+            max: 8
+        })
+    ));
+    // Link reference definitions count too
+    let converter = HtmlToMarkdown::builder()
+        .options(Options {
+            link_style: LinkStyle::Referenced,
+            ..Default::default()
+        })
+        .limits(Limits {
+            max_output_bytes: Some(8),
+            ..Default::default()
+        })
+        .build();
+    assert!(
+        converter
+            .convert(r#"<a href="https://example.com">Hi</a>"#)
+            .is_err()
+    );
+}
+
+#[test]
+fn skip_hidden_content() {
+    let html = r#"
+        <html>
+        <head><title>Title</title><style>p { color: red; }</style></head>
+        <body>
+        <p>Visible</p>
+        <p hidden>Hidden attribute</p>
+        <p hidden="until-found">Until found</p>
+        <p aria-hidden="true">ARIA hidden</p>
+        <p aria-hidden="false">ARIA visible</p>
+        <p style="color: red; DISPLAY : none !important">Display none</p>
+        <p style="visibility:hidden">Visibility hidden</p>
+        <p style="display: block">Display block</p>
+        <template><p>Template</p></template>
+        <script>let x = 0;</script>
+        <noscript><p>Enable JavaScript</p></noscript>
+        </body>
+        </html>
+        "#;
+    let options = || Options {
+        skip_hidden_content: true,
+        ..Default::default()
+    };
+    let converter = HtmlToMarkdown::builder().options(options()).build();
+    assert_eq!(
+        "Visible\n\nUntil found\n\nARIA visible\n\nDisplay block",
+        converter.convert(html).unwrap()
+    );
+
+    // Without scripting, <noscript> is rendered.
+    let converter = HtmlToMarkdown::builder()
+        .options(options())
+        .scripting_enabled(false)
+        .build();
+    assert_eq!(
+        "Visible\n\nUntil found\n\nARIA visible\n\nDisplay block\n\nEnable JavaScript",
+        converter.convert(html).unwrap()
+    );
+}
@@ -0,0 +1,36 @@
+#![cfg(feature = "rayon")]
+
+use htmd::{
+    HtmlToMarkdown,
+    options::{LinkStyle, Options},
+};
+use pretty_assertions::assert_eq;
+
+#[test]
+fn convert_batch_keeps_order() {
+    let converter = HtmlToMarkdown::builder()
+        .options(Options {
+            link_style: LinkStyle::Referenced,
+            ..Default::default()
+        })
+        .build();
+    let htmls: Vec<String> = (0..200)
+        .map(|i| format!(r#"<h1>Page {i}</h1><a href="https://example.com/{i}">Link</a>"#))
+        .collect();
+    let htmls: Vec<&str> = htmls.iter().map(String::as_str).collect();
+
+    let results = converter.convert_batch(&htmls);
+
+    assert_eq!(htmls.len(), results.len());
+    for (i, result) in results.into_iter().enumerate() {
+        assert_eq!(
+            format!("# Page {i}\n\n[Link][1]\n\n[1]: https://example.com/{i}"),
+            result.unwrap()
+        );
+    }
+}
+
+#[test]
+fn convert_batch_empty() {
+    assert!(HtmlToMarkdown::new().convert_batch(&[]).is_empty());
+}
@@ -0,0 +1,263 @@
+use htmd::{
+    HtmlToMarkdown,
+    options::{FrontMatterStyle, HeadingStyle, Options},
+};
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+
+const HTML: &str = r#"
+    <h1>Guide</h1>
+    <p>Intro text.</p>
+    <h2>Install</h2>
+    <pre><code>cargo add htmd
+
+cargo build</code></pre>
+    <table>
+        <tr><th>Name</th><th>Value</th></tr>
+        <tr><td>One</td><td>1</td></tr>
+        <tr><td>Two</td><td>2</td></tr>
+    </table>
+    <h3>Nested</h3>
+    <h2>Usage</h2>
+    <h3>Convert</h3>
+    <p>Use it.</p>
+    "#;
+
+fn chunks(converter: &HtmlToMarkdown, html: &str, max_chars: usize) -> Vec<(Vec<String>, String)> {
+    converter
+        .convert_to_chunks(html, max_chars)
+        .unwrap()
+        .into_iter()
+        .map(|chunk| (chunk.breadcrumb, chunk.markdown))
+        .collect()
+}
+
+fn breadcrumb(headings: &[&str]) -> Vec<String> {
+    headings.iter().map(|it| it.to_string()).collect()
+}
+
+#[test]
+fn chunks_at_headings_and_blocks() {
+    assert_eq!(
+        vec![
+            (breadcrumb(&["Guide"]), "# Guide\n\nIntro text.".to_string()),
+            (
+                breadcrumb(&["Guide", "Install"]),
+                "## Install\n\n```\ncargo add htmd\n\ncargo build\n```".to_string()
+            ),
+            (
+                breadcrumb(&["Guide", "Install"]),
+                indoc!(
+                    "
+                    | Name | Value |
+                    | ---- | ----- |
+                    | One  | 1     |
+                    | Two  | 2     |"
+                )
+                .to_string()
+            ),
+            (
+                breadcrumb(&["Guide", "Install", "Nested"]),
+                "### Nested".to_string()
+            ),
+            (
+                breadcrumb(&["Guide", "Usage", "Convert"]),
+                "## Usage\n\n### Convert\n\nUse it.".to_string()
+            ),
+        ],
+        chunks(&HtmlToMarkdown::new(), HTML, 40)
+    );
+}
+
+#[test]
+fn large_budget() {
+    let converter = HtmlToMarkdown::new();
+    let chunks = converter.convert_to_chunks(HTML, 1000).unwrap();
+    // Sections still start new chunks.
+    assert_eq!(4, chunks.len());
+    let markdown = chunks
+        .iter()
+        .map(|chunk| chunk.markdown.as_str())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    assert_eq!(converter.convert(HTML).unwrap(), markdown);
+}
+
+#[test]
+fn oversized_blocks() {
+    let html = "<p>A paragraph that is longer than the budget.</p><p>Short.</p>";
+    assert_eq!(
+        vec![
+            (
+                vec![],
+                "A paragraph that is longer than the budget.".to_string()
+            ),
+            (vec![], "Short.".to_string()),
+        ],
+        chunks(&HtmlToMarkdown::new(), html, 10)
+    );
+}
+
+#[test]
+fn lists() {
+    let html = indoc!(
+        "
+        <p>Before the list.</p>
+        <ul>
+            <li><p>First item</p><p>Continued</p></li>
+            <li><p>Second item</p></li>
+        </ul>
+        <p>After.</p>"
+    );
+    let converter = HtmlToMarkdown::new();
+    // The list is moved to the next chunk instead of being cut.
+    assert_eq!(
+        vec![
+            (vec![], "Before the list.".to_string()),
+            (
+                vec![],
+                "*   First item\n\n    Continued\n\n*   Second item".to_string()
+            ),
+            (vec![], "After.".to_string()),
+        ],
+        chunks(&converter, html, 50)
+    );
+    // Items are kept whole if the list doesn't fit.
+    assert_eq!(
+        vec![
+            (vec![], "Before the list.".to_string()),
+            (vec![], "*   First item\n\n    Continued".to_string()),
+            (vec![], "*   Second item\n\nAfter.".to_string()),
+        ],
+        chunks(&converter, html, 30)
+    );
+}
+
+#[test]
+fn setext_headings_and_front_matter() {
+    let converter = HtmlToMarkdown::builder()
+        .options(Options {
+            heading_style: HeadingStyle::Setex,
+            front_matter_style: FrontMatterStyle::Yaml,
+            ..Default::default()
+        })
+        .build();
+    let html = "<title>Title</title><h1>One</h1><p>Text</p><h2>Two</h2><p>Text</p>";
+    assert_eq!(
+        vec![
+            (vec![], "---\ntitle: \"Title\"\n---".to_string()),
+            (breadcrumb(&["One"]), "One\n===\n\nText".to_string()),
+            (breadcrumb(&["One", "Two"]), "Two\n---\n\nText".to_string()),
+        ],
+        chunks(&converter, html, 20)
+    );
+}
+
+#[test]
+fn token_counter() {
+    let words = |text: &str| text.split_whitespace().count();
+    let chunks = HtmlToMarkdown::new()
+        .convert_to_chunks_by("<p>one two three</p><p>four five</p><p>six</p>", 5, words)
+        .unwrap();
+    let markdown: Vec<_> = chunks.iter().map(|it| it.markdown.as_str()).collect();
+    assert_eq!(vec!["one two three\n\nfour five", "six"], markdown);
+}
+
+#[test]
+fn plain_text_breadcrumbs() {
+    let html = indoc!(
+        r#"
+        <div class="content">
+            <h1><a href="/api">API</a> for foo_bar</h1>
+            <p>Text</p>
+            <section>
+                <h2>Sub *x*</h2>
+                <p>More text</p>
+            </section>
+        </div>"#
+    );
+    assert_eq!(
+        vec![
+            (
+                breadcrumb(&["API for foo_bar"]),
+                "# [API](/api) for foo\\_bar\n\nText".to_string()
+            ),
+            (
+                breadcrumb(&["API for foo_bar", "Sub *x*"]),
+                "## Sub \\*x\\*\n\nMore text".to_string()
+            ),
+        ],
+        chunks(&HtmlToMarkdown::new(), html, 1000)
+    );
+}
+
+#[test]
+fn tight_lists() {
+    let html = "<ul><li>One</li><li>Two</li><li>Three</li></ul>";
+    let converter = HtmlToMarkdown::new();
+    assert_eq!(
+        vec![(vec![], "*   One\n*   Two\n*   Three".to_string())],
+        chunks(&converter, html, 100)
+    );
+    assert_eq!(
+        vec![
+            (vec![], "*   One\n*   Two".to_string()),
+            (vec![], "*   Three".to_string()),
+        ],
+        chunks(&converter, html, 20)
+    );
+}
+
+#[test]
+fn oversized_blockquotes() {
+    let html = "<blockquote><p>First paragraph.</p><p>Second paragraph.</p><p>Third.</p></blockquote><p>After.</p>";
+    let converter = HtmlToMarkdown::new();
+    assert_eq!(
+        vec![
+            (
+                vec![],
+                "> First paragraph.\n> \n> Second paragraph.".to_string()
+            ),
+            (vec![], "> Third.\n\nAfter.".to_string()),
+        ],
+        chunks(&converter, html, 45)
+    );
+
+    let html = format!(
+        "<blockquote>{}</blockquote>",
+        "<p>A paragraph of the quote.</p>".repeat(16_000)
+    );
+    let chunks = converter.convert_to_chunks(&html, 500).unwrap();
+    assert!(chunks.len() > 900);
+    assert!(
+        chunks
+            .iter()
+            .all(|chunk| chunk.markdown.chars().count() <= 500
+                && chunk.markdown.starts_with("> A paragraph"))
+    );
+}
+
+#[test]
+fn oversized_list_items() {
+    let html = indoc!(
+        "
+        <ol>
+            <li>Text before<p>Para one is here</p>
+                <ul><li><p>Nested item one</p><p>Nested continued</p></li><li>x</li></ul>
+            </li>
+        </ol>
+        <blockquote><ul><li><p>Quoted item</p><blockquote><p>Inner quote</p></blockquote></li></ul></blockquote>"
+    );
+    // The lines of the items are not indented once they are cut.
+    assert_eq!(
+        vec![
+            (vec![], "1.  Text before".to_string()),
+            (vec![], "Para one is here".to_string()),
+            (vec![], "*   Nested item one".to_string()),
+            (vec![], "Nested continued".to_string()),
+            (vec![], "*   x\n\n> *   Quoted item".to_string()),
+            (vec![], "> > Inner quote".to_string()),
+        ],
+        chunks(&HtmlToMarkdown::new(), html, 25)
+    );
+}
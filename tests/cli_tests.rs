@@ -0,0 +1,176 @@
+#![cfg(feature = "cli")]
+
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+};
+
+use pretty_assertions::assert_eq;
+
+/// A new empty directory for the files of a test.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("htmd-cli-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn write(path: &Path, content: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+fn htmd(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_htmd"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> &str {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+#[test]
+fn stdin() {
+    let output = htmd(&[], "<h1>Hello</h1><p>World</p>");
+    assert_eq!("# Hello\n\nWorld\n", stdout(&output));
+
+    let output = htmd(&["-"], "<p>Dash</p>");
+    assert_eq!("Dash\n", stdout(&output));
+}
+
+#[test]
+fn file_arguments() {
+    let dir = test_dir("files");
+    write(&dir.join("a.html"), "<p>A</p>");
+    write(&dir.join("b.html"), "<p>B</p>");
+    let a = dir.join("a.html");
+    let b = dir.join("b.html");
+    let output = htmd(&[a.to_str().unwrap(), b.to_str().unwrap()], "");
+    assert_eq!("A\n\nB\n", stdout(&output));
+}
+
+#[test]
+fn directory_to_output() {
+    let dir = test_dir("directory");
+    let site = dir.join("site");
+    write(&site.join("index.html"), "<h1>Home</h1>");
+    write(&site.join("docs/intro.htm"), "<p>Intro</p>");
+    write(&site.join("style.css"), "p {}");
+    let out = dir.join("out");
+    let output = htmd(&[site.to_str().unwrap(), "-o", out.to_str().unwrap()], "");
+    assert_eq!("", stdout(&output));
+    assert_eq!("# Home", fs::read_to_string(out.join("index.md")).unwrap());
+    assert_eq!(
+        "Intro",
+        fs::read_to_string(out.join("docs/intro.md")).unwrap()
+    );
+    assert!(!out.join("style.md").exists());
+}
+
+#[test]
+fn file_to_output() {
+    let dir = test_dir("file-output");
+    let page = dir.join("page.html");
+    write(&page, "<p>Page</p>");
+    let out = dir.join("out");
+    let output = htmd(
+        &[page.to_str().unwrap(), "--output", out.to_str().unwrap()],
+        "",
+    );
+    assert_eq!("", stdout(&output));
+    assert_eq!("Page", fs::read_to_string(out.join("page.md")).unwrap());
+}
+
+#[test]
+fn duplicate_outputs_fail() {
+    let dir = test_dir("duplicates");
+    let a = dir.join("a/index.html");
+    let b = dir.join("b/index.html");
+    write(&a, "<p>A</p>");
+    write(&b, "<p>B</p>");
+    let out = dir.join("out");
+    let output = htmd(
+        &[
+            a.to_str().unwrap(),
+            b.to_str().unwrap(),
+            "-o",
+            out.to_str().unwrap(),
+        ],
+        "",
+    );
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("would both be written to index.md"),
+        "{stderr}"
+    );
+    assert!(!out.exists());
+
+    // Directories with the same files
+    let output = htmd(
+        &[
+            dir.join("a").to_str().unwrap(),
+            dir.join("b").to_str().unwrap(),
+            "-o",
+            out.to_str().unwrap(),
+        ],
+        "",
+    );
+    assert!(!output.status.success());
+    assert!(!out.exists());
+}
+
+#[test]
+fn option_flags() {
+    let html = r#"<h1>Title</h1><ul><li><a href="/a">A</a></li></ul>"#;
+    let output = htmd(
+        &[
+            "--heading-style",
+            "setex",
+            "--bullet-list-marker",
+            "dash",
+            "--link-style",
+            "referenced",
+        ],
+        html,
+    );
+    assert_eq!("Title\n=====\n\n-   [A][1]\n\n[1]: /a\n", stdout(&output));
+
+    let output = htmd(
+        &["--skip-tags", "nav,footer"],
+        "<nav>Nav</nav><p>Text</p><footer>F</footer>",
+    );
+    assert_eq!("Text\n", stdout(&output));
+
+    let output = htmd(
+        &["--base-url", "https://example.com/docs/"],
+        r#"<a href="a">A</a>"#,
+    );
+    assert_eq!("[A](https://example.com/docs/a)\n", stdout(&output));
+}
+
+#[test]
+fn conversion_errors() {
+    let output = htmd(&["--max-input-bytes", "4"], "<p>Too long</p>");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("htmd: "));
+}
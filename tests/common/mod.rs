@@ -5,7 +5,7 @@ use htmd::{
 };
 
 // By default, use the faithful translation mode, which is more stringent.
-pub fn convert(html: &str) -> std::io::Result<String> {
+pub fn convert(html: &str) -> Result<String, htmd::Error> {
     HtmlToMarkdown::builder()
         .options(Options {
             translation_mode: TranslationMode::Faithful,
@@ -0,0 +1,137 @@
+use htmd::{
+    HtmlToMarkdown,
+    options::{FootnoteStyle, LinkStyle, Options},
+};
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+
+fn convert(html: &str) -> String {
+    HtmlToMarkdown::builder()
+        .options(Options {
+            footnote_style: FootnoteStyle::Markdown,
+            ..Default::default()
+        })
+        .build()
+        .convert(html)
+        .unwrap()
+}
+
+#[test]
+fn pandoc_footnotes() {
+    let html = r##"
+        <p>Text<a href="#fn1" class="footnote-ref" id="fnref1" role="doc-noteref"><sup>1</sup></a>
+        and more<a href="#fn2" class="footnote-ref" id="fnref2" role="doc-noteref"><sup>2</sup></a>.</p>
+        <section id="footnotes" class="footnotes footnotes-end-of-document" role="doc-endnotes">
+        <hr />
+        <ol>
+        <li id="fn1"><p>The <em>first</em> note.<a href="#fnref1" class="footnote-back" role="doc-backlink">↩︎</a></p></li>
+        <li id="fn2"><p>The second note.</p><p>With two paragraphs.<a href="#fnref2" class="footnote-back" role="doc-backlink">↩︎</a></p></li>
+        </ol>
+        </section>
+        "##;
+    assert_eq!(
+        indoc!(
+            "
+            Text[^1] and more[^2].
+
+            [^1]: The *first* note.
+            [^2]: The second note.
+
+                With two paragraphs."
+        ),
+        convert(html)
+    );
+}
+
+#[test]
+fn wikipedia_references() {
+    let html = r##"
+        <p>Rust is fast.<sup id="cite_ref-1" class="reference"><a href="#cite_note-1">[1]</a></sup>
+        It is safe.<sup id="cite_ref-2" class="reference"><a href="#cite_note-2">[2]</a></sup>
+        Really fast.<sup id="cite_ref-1-1" class="reference"><a href="#cite_note-1">[1]</a></sup></p>
+        <div class="reflist">
+        <ol class="references">
+        <li id="cite_note-2"><span class="mw-cite-backlink"><b><a href="#cite_ref-2">^</a></b></span> <span class="reference-text">Safety docs.</span></li>
+        <li id="cite_note-1"><span class="mw-cite-backlink">^ <a href="#cite_ref-1"><sup>a</sup></a> <a href="#cite_ref-1-1"><sup>b</sup></a></span> <span class="reference-text">Benchmarks.</span></li>
+        </ol>
+        </div>
+        "##;
+    assert_eq!(
+        indoc!(
+            "
+            Rust is fast.[^1] It is safe.[^2] Really fast.[^1]
+
+            [^1]: Benchmarks.
+            [^2]: Safety docs."
+        ),
+        convert(html)
+    );
+}
+
+#[test]
+fn dpub_aria_footnotes() {
+    let html = r##"
+        <p>See the note<a role="doc-noteref" href="#n1">*</a>.</p>
+        <aside role="doc-footnote" id="n1">An aside note.</aside>
+        <section role="doc-endnotes">
+        <ol><li role="doc-endnote" id="n2">Unreferenced note.</li></ol>
+        </section>
+        "##;
+    assert_eq!(
+        indoc!(
+            "
+            See the note[^1].
+
+            [^1]: An aside note.
+            [^2]: Unreferenced note."
+        ),
+        convert(html)
+    );
+}
+
+#[test]
+fn footnotes_as_links_by_default() {
+    let html = r##"
+        <p>Text<a href="#fn1" class="footnote-ref" role="doc-noteref"><sup>1</sup></a></p>
+        <section class="footnotes"><ol><li id="fn1">Note</li></ol></section>
+        "##;
+    assert_eq!("Text[1](#fn1)\n\n1.  Note", htmd::convert(html).unwrap());
+}
+
+#[test]
+fn other_elements_are_not_footnotes() {
+    assert_eq!(
+        "x^2 [Link](#top)\n\n1.  Item",
+        convert(r##"x^<sup>2</sup> <a href="#top">Link</a><ol><li id="a">Item</li></ol>"##)
+    );
+}
+
+#[test]
+fn footnotes_with_referenced_links() {
+    let html = r##"
+        <p>Text<a href="#fn1" class="footnote-ref" role="doc-noteref"><sup>1</sup></a>
+        and a <a href="https://example.com">link</a>.</p>
+        <section class="footnotes"><ol><li id="fn1">Note</li></ol></section>
+        "##;
+    let md = HtmlToMarkdown::builder()
+        .options(Options {
+            footnote_style: FootnoteStyle::Markdown,
+            link_style: LinkStyle::Referenced,
+            ..Default::default()
+        })
+        .build()
+        .convert(html)
+        .unwrap();
+    // The definitions of the footnotes and of the links are separate blocks.
+    assert_eq!(
+        indoc!(
+            "
+            Text[^1] and a [link][1].
+
+            [1]: https://example.com
+
+            [^1]: Note"
+        ),
+        md
+    );
+}
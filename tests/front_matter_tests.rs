@@ -0,0 +1,123 @@
+use htmd::{
+    HtmlToMarkdown,
+    options::{FrontMatterStyle, Options},
+};
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+
+const HTML: &str = r#"
+    <html>
+    <head>
+        <title>
+            Hello,   "World"
+        </title>
+        <meta charset="utf-8">
+        <meta name="description" content="A greeting\with a backslash">
+        <meta name="Author" content="Jane Doe">
+        <meta name="keywords" content="greeting, world, ,hello">
+        <meta name="viewport" content="width=device-width">
+        <meta property="og:title" content="Hello">
+        <meta property="og:image" content="https://example.com/a.png">
+        <meta property="og:image" content="https://example.com/b.png">
+        <link rel="stylesheet" href="/style.css">
+        <link rel="canonical" href="/hello">
+    </head>
+    <body><h1>Hello</h1></body>
+    </html>
+    "#;
+
+fn converter(style: FrontMatterStyle) -> HtmlToMarkdown {
+    HtmlToMarkdown::builder()
+        .options(Options {
+            front_matter_style: style,
+            ..Default::default()
+        })
+        .base_url("https://example.com/blog/")
+        .build()
+}
+
+#[test]
+fn yaml_front_matter() {
+    assert_eq!(
+        indoc!(
+            r#"
+            ---
+            title: "Hello, \"World\""
+            description: "A greeting\\with a backslash"
+            author: "Jane Doe"
+            keywords: ["greeting", "world", "hello"]
+            "og:title": "Hello"
+            "og:image": "https://example.com/a.png"
+            canonical: "https://example.com/hello"
+            ---
+
+            # Hello"#
+        ),
+        converter(FrontMatterStyle::Yaml).convert(HTML).unwrap()
+    );
+}
+
+#[test]
+fn toml_front_matter() {
+    assert_eq!(
+        indoc!(
+            r#"
+            +++
+            title = "Hello, \"World\""
+            description = "A greeting\\with a backslash"
+            author = "Jane Doe"
+            keywords = ["greeting", "world", "hello"]
+            "og:title" = "Hello"
+            "og:image" = "https://example.com/a.png"
+            canonical = "https://example.com/hello"
+            +++
+
+            # Hello"#
+        ),
+        converter(FrontMatterStyle::Toml).convert(HTML).unwrap()
+    );
+}
+
+#[test]
+fn front_matter_fields() {
+    let converter = HtmlToMarkdown::builder()
+        .options(Options {
+            front_matter_style: FrontMatterStyle::Yaml,
+            ..Default::default()
+        })
+        .front_matter_fields(|name| match name {
+            "og:title" | "title" => Some("title".to_string()),
+            "og:image" => Some("image".to_string()),
+            _ => None,
+        })
+        .build();
+    assert_eq!(
+        indoc!(
+            r#"
+            ---
+            title: "Hello, \"World\""
+            image: "https://example.com/a.png"
+            ---
+
+            # Hello"#
+        ),
+        converter.convert(HTML).unwrap()
+    );
+}
+
+#[test]
+fn no_metadata() {
+    let yaml = converter(FrontMatterStyle::Yaml);
+    assert_eq!("# Hello", yaml.convert("<h1>Hello</h1>").unwrap());
+    assert_eq!(
+        "---\ntitle: \"Hello\"\n---",
+        yaml.convert("<title>Hello</title>").unwrap()
+    );
+    // The title is converted like the body without front matter.
+    assert_eq!(
+        "Hello\n\n# Hello",
+        converter(FrontMatterStyle::Disabled)
+            .convert("<title>Hello</title><h1>Hello</h1>")
+            .unwrap()
+    );
+}
@@ -0,0 +1,137 @@
+use htmd::{
+    HtmlToMarkdown,
+    options::{MarkdownFlavor, Options, TranslationMode},
+};
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+
+fn gfm_converter(translation_mode: TranslationMode) -> HtmlToMarkdown {
+    HtmlToMarkdown::builder()
+        .options(Options {
+            flavor: MarkdownFlavor::Gfm,
+            translation_mode,
+            ..Default::default()
+        })
+        .build()
+}
+
+fn convert(html: &str) -> String {
+    gfm_converter(TranslationMode::Faithful)
+        .convert(html)
+        .unwrap()
+}
+
+#[test]
+fn strikethrough() {
+    assert_eq!(
+        "~~Deleted~~ ~~Struck~~ ~~Old~~",
+        convert("<del>Deleted</del> <s>Struck</s> <strike>Old</strike>")
+    );
+    assert_eq!(
+        "A ~~**bold**~~ word",
+        convert("A <del><b>bold</b></del> word")
+    );
+}
+
+#[test]
+fn strikethrough_in_commonmark() {
+    let html = "<del>Deleted</del>";
+    assert_eq!("Deleted", htmd::convert(html).unwrap());
+
+    let md = HtmlToMarkdown::builder()
+        .options(Options {
+            translation_mode: TranslationMode::Faithful,
+            ..Default::default()
+        })
+        .build()
+        .convert(html)
+        .unwrap();
+    assert_eq!("<del>Deleted</del>", md);
+}
+
+#[test]
+fn task_list_items() {
+    let html = r#"
+        <ul>
+            <li><input type="checkbox" checked disabled> Done</li>
+            <li><input type="checkbox" disabled> Todo</li>
+            <li><p><input type="checkbox"> In a paragraph</p></li>
+            <li>Not a task <input type="checkbox"></li>
+        </ul>
+        "#;
+    assert_eq!(
+        indoc!(
+            "
+            *   [x] Done
+            *   [ ] Todo
+            *   [ ] In a paragraph
+
+            *   Not a task"
+        ),
+        gfm_converter(TranslationMode::Pure).convert(html).unwrap()
+    );
+}
+
+#[test]
+fn task_list_items_in_ordered_list() {
+    let html = r#"
+        <ol>
+            <li><input type="checkbox" checked> One</li>
+            <li><input type="checkbox"> Two</li>
+        </ol>
+        "#;
+    assert_eq!("1.  [x] One\n2.  [ ] Two", convert(html));
+}
+
+#[test]
+fn task_list_checkbox_with_extra_attrs_in_faithful_mode() {
+    let html = r#"<ul><li><input type="checkbox" id="task"> Todo</li></ul>"#;
+    assert_eq!(
+        r#"*   <input type="checkbox" id="task"> Todo"#,
+        convert(html)
+    );
+}
+
+#[test]
+fn autolinks() {
+    assert_eq!(
+        "<https://example.com>",
+        convert(r#"<a href="https://example.com">https://example.com</a>"#)
+    );
+    assert_eq!(
+        "<foo@example.com>",
+        convert(r#"<a href="mailto:foo@example.com">foo@example.com</a>"#)
+    );
+    assert_eq!(
+        "[Example](https://example.com)",
+        convert(r#"<a href="https://example.com">Example</a>"#)
+    );
+}
+
+#[test]
+fn escaping() {
+    assert_eq!(r"\~\~Not deleted\~\~", convert("<p>~~Not deleted~~</p>"));
+    assert_eq!(r"a \| b", convert("<p>a | b</p>"));
+    assert_eq!(r"\~\~\~", convert("<p>~~~</p>"));
+    // Not escaped in CommonMark
+    assert_eq!("a | b ~c~", htmd::convert("<p>a | b ~c~</p>").unwrap());
+}
+
+#[test]
+fn escaped_pipes_in_table_cells() {
+    let html = r#"
+        <table>
+            <tr><th>Expression</th></tr>
+            <tr><td>a | b</td></tr>
+        </table>
+        "#;
+    assert_eq!(
+        indoc!(
+            r"
+            | Expression |
+            | ---------- |
+            | a \| b     |"
+        ),
+        convert(html)
+    );
+}
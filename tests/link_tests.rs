@@ -1,6 +1,7 @@
 use htmd::{
     HtmlToMarkdown,
-    options::{LinkStyle, Options, TranslationMode},
+    element_handler::UrlKind,
+    options::{LinkReferenceStyle, LinkStyle, Options, TranslationMode},
 };
 mod common;
 use common::convert;
@@ -59,3 +60,212 @@ fn links_inlined_prefer_autolinks() {
         converter.convert(html).unwrap()
     );
 }
+
+#[test]
+fn links_and_images_resolved_against_base_url() {
+    let converter = HtmlToMarkdown::builder()
+        .base_url("https://example.com/docs/guide/index.html")
+        .build();
+    let html = r##"
+        <a href="../api/">API</a>
+        <a href="/about">About</a>
+        <a href="#intro">Intro</a>
+        <a href="mailto:hi@example.com">Mail</a>
+        <img src="img/logo.png" alt="Logo">
+        "##;
+    assert_eq!(
+        "[API](https://example.com/docs/api/) [About](https://example.com/about) \
+         [Intro](#intro) [Mail](mailto:hi@example.com) \
+         ![Logo](https://example.com/docs/guide/img/logo.png)",
+        converter.convert(html).unwrap(),
+    );
+}
+
+#[test]
+fn links_resolved_against_document_base_href() {
+    let html = r#"
+        <html>
+        <head><base href="/v2/"></head>
+        <body><a href="start">Start</a></body>
+        </html>
+        "#;
+    let converter = HtmlToMarkdown::builder()
+        .base_url("https://example.com/docs/")
+        .build();
+    assert_eq!(
+        "[Start](https://example.com/v2/start)",
+        converter.convert(html).unwrap(),
+    );
+
+    let html = r#"<head><base href="https://cdn.example.com/a/"></head><img src="b.png">"#;
+    assert_eq!(
+        "![](https://cdn.example.com/a/b.png)",
+        converter.convert(html).unwrap(),
+    );
+}
+
+#[test]
+fn document_base_href_ignored_without_base_url() {
+    let html = r#"<base href="https://example.com/a/"><a href="b">l</a>"#;
+    assert_eq!("[l](b)", htmd::convert(html).unwrap());
+}
+
+#[test]
+fn links_without_base_url_are_unchanged() {
+    assert_eq!(
+        "[API](../api/)",
+        htmd::convert(r#"<a href="../api/">API</a>"#).unwrap()
+    );
+}
+
+#[test]
+fn rewrite_urls() {
+    let converter = HtmlToMarkdown::builder()
+        .base_url("https://example.com/blog/")
+        .options(Options {
+            link_style: LinkStyle::Referenced,
+            ..Default::default()
+        })
+        .rewrite_url(|kind, url, element| match kind {
+            UrlKind::Link if element.attrs.iter().any(|attr| &attr.name.local == "rel") => None,
+            UrlKind::Link => Some(url.split('?').next().unwrap().to_string()),
+            UrlKind::Image => Some(url.replace("https://example.com/", "./")),
+        })
+        .build();
+    let html = r#"
+        <a href="post?utm_source=feed">Post</a>
+        <a href="https://ads.example.com" rel="sponsored">Ad</a>
+        <img src="/img/photo.jpg" alt="Photo">
+        "#;
+    assert_eq!(
+        "[Post][1] Ad ![Photo](./img/photo.jpg)\n\n[1]: https://example.com/blog/post",
+        converter.convert(html).unwrap(),
+    );
+}
+
+#[test]
+fn rewrite_url_drops_images() {
+    let converter = HtmlToMarkdown::builder()
+        .rewrite_url(|kind, url, _| (kind == UrlKind::Link).then(|| url.to_string()))
+        .build();
+    assert_eq!(
+        "Before [Link](/a) after",
+        converter
+            .convert(r#"Before <img src="a.png"><a href="/a">Link</a> after"#)
+            .unwrap(),
+    );
+}
+
+#[test]
+fn reference_links_are_scoped_to_a_conversion() {
+    use htmd::{Element, element_handler::Handlers};
+    use std::sync::Arc;
+
+    let options = || Options {
+        link_style: LinkStyle::Referenced,
+        ..Default::default()
+    };
+    let inner = Arc::new(HtmlToMarkdown::builder().options(options()).build());
+    // Converts the content of `<aside>` separately, while the outer document
+    // is being converted.
+    let outer = HtmlToMarkdown::builder()
+        .options(options())
+        .add_handler(vec!["aside"], move |_: &dyn Handlers, element: Element| {
+            Some(inner.tree_to_markdown(element.node).unwrap().into())
+        })
+        .build();
+    let html = r#"
+        <p><a href="https://a.example">A</a></p>
+        <aside><a href="https://b.example">B</a></aside>
+        <p><a href="https://c.example">C</a></p>
+        "#;
+    assert_eq!(
+        indoc::indoc!(
+            "
+            [A][1]
+
+            [B][1]
+
+            [1]: https://b.example
+
+            [C][2]
+
+            [1]: https://a.example
+            [2]: https://c.example"
+        ),
+        outer.convert(html).unwrap(),
+    );
+}
+
+fn convert_referenced(html: &str, link_reference_style: LinkReferenceStyle) -> String {
+    HtmlToMarkdown::builder()
+        .options(Options {
+            link_style: LinkStyle::Referenced,
+            link_reference_style,
+            ..Default::default()
+        })
+        .build()
+        .convert(html)
+        .unwrap()
+}
+
+#[test]
+fn full_reference_links_are_deduplicated() {
+    let html = r#"
+        <a href="https://a.example">A</a>
+        <a href="https://b.example">B</a>
+        <a href="https://a.example">A again</a>
+        <a href="https://a.example" title="Title">A with title</a>
+        "#;
+    assert_eq!(
+        indoc::indoc!(
+            r#"
+            [A][1] [B][2] [A again][1] [A with title][3]
+
+            [1]: https://a.example
+            [2]: https://b.example
+            [3]: https://a.example "Title""#
+        ),
+        convert_referenced(html, LinkReferenceStyle::Full),
+    );
+}
+
+#[test]
+fn collapsed_reference_label_collisions() {
+    let html = r#"
+        <a href="https://a.example">Example</a>
+        <a href="https://b.example">example</a>
+        <a href="https://a.example">Example</a>
+        <a href="https://c.example">EXAMPLE</a>
+        "#;
+    assert_eq!(
+        indoc::indoc!(
+            "
+            [Example][] [example][example 2] [Example][] [EXAMPLE][EXAMPLE 3]
+
+            [Example]: https://a.example
+            [example 2]: https://b.example
+            [EXAMPLE 3]: https://c.example"
+        ),
+        convert_referenced(html, LinkReferenceStyle::Collapsed),
+    );
+}
+
+#[test]
+fn shortcut_reference_label_collisions() {
+    let html = r#"
+        <a href="https://a.example">Docs</a>
+        <a href="https://b.example">Docs</a>
+        <a href="https://b.example">Docs</a>
+        "#;
+    assert_eq!(
+        indoc::indoc!(
+            "
+            [Docs] [Docs][Docs 2] [Docs][Docs 2]
+
+            [Docs]: https://a.example
+            [Docs 2]: https://b.example"
+        ),
+        convert_referenced(html, LinkReferenceStyle::Shortcut),
+    );
+}
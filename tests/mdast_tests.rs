@@ -0,0 +1,444 @@
+use htmd::{
+    Element, Error, HtmlToMarkdown, Limit, Limits,
+    mdast::{Align, Node},
+    options::{
+        BulletListMarker, FootnoteStyle, HeadingStyle, LinkStyle, MarkdownFlavor, Options,
+        TableSpanStyle, TranslationMode,
+    },
+};
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+
+const HTML: &str = r#"
+    <h1>Guide <em>one</em></h1>
+    <p>Some <b>bold</b>er text, with <a href="/a" title="T">a  link</a> and <code>x`y</code>.</p>
+    <blockquote><p>Quote</p><p>Two</p></blockquote>
+    <ul><li>One</li><li>Two<ul><li>Nested</li></ul></li></ul>
+    <ol start="9"><li><p>Nine</p><p>More</p></li><li><p>Ten</p></li></ol>
+    <pre><code class="language-rust">fn main() {}
+    </code></pre>
+    <table>
+        <thead><tr><th>A</th><th align="right">B</th></tr></thead>
+        <tbody><tr><td>1</td><td>22</td></tr></tbody>
+    </table>
+    <hr>
+    <div>Loose <span>text</span></div>
+    <p>1. not a list * star</p>
+    "#;
+
+fn text(value: &str) -> Node {
+    Node::Text {
+        value: value.to_string(),
+    }
+}
+
+fn paragraph(value: &str) -> Node {
+    Node::Paragraph {
+        children: vec![text(value)],
+    }
+}
+
+fn gfm() -> HtmlToMarkdown {
+    HtmlToMarkdown::builder()
+        .options(Options {
+            flavor: MarkdownFlavor::Gfm,
+            ..Default::default()
+        })
+        .build()
+}
+
+#[test]
+fn renders_like_convert() {
+    let converter = HtmlToMarkdown::new();
+    let root = converter.convert_to_mdast(HTML).unwrap();
+    let options = Options::default();
+    assert_eq!(converter.convert(HTML).unwrap(), root.to_markdown(&options));
+}
+
+#[test]
+fn tree() {
+    let html = indoc!(
+        r#"
+        <p>Some <b>bold</b>er   text<br><a href="/a">a <img src="/i.png" alt="An image"></a></p>
+        <ol start="3"><li><input type="checkbox" checked> Done</li></ol>
+        <table><tr><th>A</th><th style="text-align: center">B</th></tr></table>"#
+    );
+    let root = gfm().convert_to_mdast(html).unwrap();
+    assert_eq!(
+        Node::Root {
+            children: vec![
+                Node::Paragraph {
+                    children: vec![
+                        text("Some "),
+                        Node::Strong {
+                            children: vec![text("bold")]
+                        },
+                        text("er text"),
+                        Node::Break,
+                        Node::Link {
+                            url: "/a".to_string(),
+                            title: None,
+                            children: vec![
+                                text("a "),
+                                Node::Image {
+                                    url: "/i.png".to_string(),
+                                    title: None,
+                                    alt: Some("An image".to_string()),
+                                },
+                            ],
+                        },
+                    ],
+                },
+                Node::List {
+                    ordered: true,
+                    start: Some(3),
+                    spread: false,
+                    children: vec![Node::ListItem {
+                        checked: Some(true),
+                        spread: false,
+                        children: vec![paragraph("Done")],
+                    }],
+                },
+                Node::Table {
+                    align: vec![None, Some(Align::Center)],
+                    children: vec![Node::TableRow {
+                        children: vec![
+                            Node::TableCell {
+                                children: vec![text("A")]
+                            },
+                            Node::TableCell {
+                                children: vec![text("B")]
+                            },
+                        ],
+                    }],
+                },
+            ],
+        },
+        root
+    );
+}
+
+#[test]
+fn json() {
+    let html = r#"<h2>Title</h2><p>A "quote"<br>and <a href="/a">link</a></p><pre><code>code</code></pre><hr>"#;
+    let root = HtmlToMarkdown::new().convert_to_mdast(html).unwrap();
+    assert_eq!(
+        concat!(
+            r#"{"type":"root","children":["#,
+            r#"{"type":"heading","depth":2,"children":[{"type":"text","value":"Title"}]},"#,
+            r#"{"type":"paragraph","children":[{"type":"text","value":"A \"quote\""},{"type":"break"},"#,
+            r#"{"type":"text","value":"and "},{"type":"link","url":"/a","title":null,"children":[{"type":"text","value":"link"}]}]},"#,
+            r#"{"type":"code","lang":null,"meta":null,"value":"code"},"#,
+            r#"{"type":"thematicBreak"}"#,
+            "]}"
+        ),
+        root.to_json()
+    );
+}
+
+#[test]
+fn render_with_options() {
+    let html = indoc!(
+        r#"
+        <h1>Title</h1>
+        <ul><li><input type="checkbox"> Todo <del>later</del></li></ul>
+        <p><a href="/a">A</a> and <a href="/b">B</a></p>"#
+    );
+    let root = gfm().convert_to_mdast(html).unwrap();
+    let options = Options {
+        heading_style: HeadingStyle::Setex,
+        bullet_list_marker: BulletListMarker::Dash,
+        ul_bullet_spacing: 1,
+        link_style: LinkStyle::Referenced,
+        flavor: MarkdownFlavor::Gfm,
+        ..Default::default()
+    };
+    assert_eq!(
+        indoc!(
+            "
+            Title
+            =====
+
+            - [ ] Todo ~~later~~
+
+            [A][1] and [B][2]
+
+            [1]: /a
+            [2]: /b"
+        ),
+        root.to_markdown(&options)
+    );
+    // Rendering twice starts over.
+    assert_eq!(root.to_markdown(&options), root.to_markdown(&options));
+    assert_eq!(
+        "# Title\n\n*   Todo later\n\n[A](/a) and [B](/b)",
+        root.to_markdown(&Options::default())
+    );
+}
+
+#[test]
+fn converter_options() {
+    let converter = HtmlToMarkdown::builder()
+        .options(Options {
+            skip_hidden_content: true,
+            translation_mode: TranslationMode::Faithful,
+            ..Default::default()
+        })
+        .base_url("https://example.com/")
+        .skip_tags(vec!["nav"])
+        .skip_selectors([".ad"])
+        .add_handler(
+            vec!["video"],
+            |_: &dyn htmd::element_handler::Handlers, _: Element| Some("[video]".into()),
+        )
+        .build();
+    let html = indoc!(
+        r#"
+        <nav><a href="/">Home</a></nav>
+        <p hidden>Hidden</p>
+        <div class="ad">Ad</div>
+        <p><a href="page">Page</a> <video src="a.mp4"></video></p>"#
+    );
+    assert_eq!(
+        Node::Root {
+            children: vec![Node::Paragraph {
+                children: vec![
+                    Node::Link {
+                        url: "https://example.com/page".to_string(),
+                        title: None,
+                        children: vec![text("Page")],
+                    },
+                    text(" "),
+                    Node::Html {
+                        value: "[video]".to_string()
+                    },
+                ],
+            }],
+        },
+        converter.convert_to_mdast(html).unwrap()
+    );
+}
+
+fn list(ordered: bool, children: Vec<Node>) -> Node {
+    Node::List {
+        ordered,
+        start: ordered.then_some(1),
+        spread: false,
+        children,
+    }
+}
+
+fn item(children: Vec<Node>) -> Node {
+    Node::ListItem {
+        checked: None,
+        spread: false,
+        children,
+    }
+}
+
+#[test]
+fn nested_lists() {
+    let html =
+        "<ol><li>y<ul><li>z<ul><li>deep</li></ul></li></ul>tail</li><li><p>a</p><p>b</p></li></ol>";
+    let converter = HtmlToMarkdown::new();
+    let root = converter.convert_to_mdast(html).unwrap();
+    assert_eq!(
+        Node::Root {
+            children: vec![Node::List {
+                ordered: true,
+                start: Some(1),
+                spread: false,
+                children: vec![
+                    item(vec![
+                        paragraph("y"),
+                        list(
+                            false,
+                            vec![item(vec![
+                                paragraph("z"),
+                                list(false, vec![item(vec![paragraph("deep")])]),
+                            ])]
+                        ),
+                        paragraph("tail"),
+                    ]),
+                    Node::ListItem {
+                        checked: None,
+                        spread: true,
+                        children: vec![paragraph("a"), paragraph("b")],
+                    },
+                ],
+            }],
+        },
+        root
+    );
+    assert_eq!(
+        converter.convert(html).unwrap(),
+        root.to_markdown(&Options::default())
+    );
+}
+
+#[test]
+fn custom_handlers_in_lists_and_tables() {
+    let converter = HtmlToMarkdown::builder()
+        .add_handler(
+            vec!["kbd"],
+            |handlers: &dyn htmd::element_handler::Handlers, element: Element| {
+                let content = handlers.walk_children(element.node).content;
+                Some(format!("<kbd>{content}</kbd>").into())
+            },
+        )
+        .build();
+    let kbd = |value: &str| Node::Html {
+        value: format!("<kbd>{value}</kbd>"),
+    };
+
+    let html = "<ul><li>Press <kbd>Ctrl</kbd> <b>now</b></li></ul>";
+    let root = converter.convert_to_mdast(html).unwrap();
+    assert_eq!(
+        Node::Root {
+            children: vec![list(
+                false,
+                vec![item(vec![Node::Paragraph {
+                    children: vec![
+                        text("Press "),
+                        kbd("Ctrl"),
+                        text(" "),
+                        Node::Strong {
+                            children: vec![text("now")]
+                        },
+                    ],
+                }])]
+            )],
+        },
+        root
+    );
+    assert_eq!(
+        converter.convert(html).unwrap(),
+        root.to_markdown(&Options::default())
+    );
+
+    let html = "<table><tr><th>Key</th></tr><tr><td><kbd>K</kbd> up</td></tr></table>";
+    let root = converter.convert_to_mdast(html).unwrap();
+    assert_eq!(
+        Node::Root {
+            children: vec![Node::Table {
+                align: vec![None],
+                children: vec![
+                    Node::TableRow {
+                        children: vec![Node::TableCell {
+                            children: vec![text("Key")]
+                        }],
+                    },
+                    Node::TableRow {
+                        children: vec![Node::TableCell {
+                            children: vec![kbd("K"), text(" up")]
+                        }],
+                    },
+                ],
+            }],
+        },
+        root
+    );
+    assert_eq!(
+        converter.convert(html).unwrap(),
+        root.to_markdown(&Options::default())
+    );
+}
+
+#[test]
+fn markdown_footnotes() {
+    let html = r##"
+        <p>Text<a href="#fn1" role="doc-noteref"><sup>1</sup></a>.</p>
+        <section role="doc-endnotes"><ol><li id="fn1"><p>The <em>note</em>.</p></li></ol></section>"##;
+    let options = || Options {
+        footnote_style: FootnoteStyle::Markdown,
+        ..Default::default()
+    };
+    let converter = HtmlToMarkdown::builder().options(options()).build();
+    let root = converter.convert_to_mdast(html).unwrap();
+    assert_eq!(
+        Node::Root {
+            children: vec![
+                Node::Paragraph {
+                    children: vec![
+                        text("Text"),
+                        Node::FootnoteReference {
+                            identifier: "1".to_string()
+                        },
+                        text("."),
+                    ],
+                },
+                Node::FootnoteDefinition {
+                    identifier: "1".to_string(),
+                    children: vec![Node::Paragraph {
+                        children: vec![
+                            text("The "),
+                            Node::Emphasis {
+                                children: vec![text("note")]
+                            },
+                            text("."),
+                        ],
+                    }],
+                },
+            ],
+        },
+        root
+    );
+    assert_eq!(
+        converter.convert(html).unwrap(),
+        root.to_markdown(&options())
+    );
+}
+
+#[test]
+fn repeated_table_spans() {
+    let html = r#"<table><tr><th colspan="2">A</th></tr><tr><td>1</td><td>2</td></tr></table>"#;
+    let options = || Options {
+        table_span_style: TableSpanStyle::Repeat,
+        ..Default::default()
+    };
+    let converter = HtmlToMarkdown::builder().options(options()).build();
+    let root = converter.convert_to_mdast(html).unwrap();
+    assert_eq!(
+        "| A | A |\n| - | - |\n| 1 | 2 |",
+        root.to_markdown(&options())
+    );
+    assert_eq!(
+        converter.convert(html).unwrap(),
+        root.to_markdown(&options())
+    );
+}
+
+#[test]
+fn head_is_converted_like_the_body() {
+    let html = "<html><head><title>Title</title></head><body><p>Body</p></body></html>";
+    let converter = HtmlToMarkdown::new();
+    let root = converter.convert_to_mdast(html).unwrap();
+    assert_eq!(
+        Node::Root {
+            children: vec![paragraph("Title"), paragraph("Body")],
+        },
+        root
+    );
+    assert_eq!(
+        converter.convert(html).unwrap(),
+        root.to_markdown(&Options::default())
+    );
+}
+
+#[test]
+fn nesting_depth_limit() {
+    let converter = HtmlToMarkdown::builder()
+        .limits(Limits {
+            max_depth: Some(10),
+            ..Default::default()
+        })
+        .build();
+    let html = "<div>".repeat(20);
+    assert!(matches!(
+        converter.convert_to_mdast(&html),
+        Err(Error::LimitExceeded {
+            limit: Limit::Depth,
+            max: 10
+        })
+    ));
+}
@@ -0,0 +1,166 @@
+use htmd::HtmlToMarkdown;
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+
+fn extract(html: &str) -> String {
+    HtmlToMarkdown::builder()
+        .extract_main_content(true)
+        .build()
+        .convert(html)
+        .unwrap()
+}
+
+#[test]
+fn main_landmark() {
+    let html = r#"
+        <header class="site-header"><a href="/">Blog</a></header>
+        <nav><a href="/">Home</a> <a href="/about">About</a></nav>
+        <div role="main">
+            <h1>Title</h1>
+            <p>Text</p>
+            <div class="cookie-banner">We use cookies. <button>OK</button></div>
+        </div>
+        <aside>Popular posts</aside>
+        <footer>Copyright</footer>
+        "#;
+    assert_eq!("# Title\n\nText", extract(html));
+}
+
+#[test]
+fn single_article() {
+    let html = r#"
+        <div class="menu"><a href="/">Home</a></div>
+        <article>
+            <h1>Title</h1>
+            <p>Text</p>
+            <footer>Tags: <a href="/tags/a">a</a></footer>
+        </article>
+        <div id="comments"><p>First, great post, thanks for sharing it with us!</p></div>
+        "#;
+    assert_eq!("# Title\n\nText", extract(html));
+}
+
+#[test]
+fn scored_content() {
+    let html = r#"
+        <div id="top-menu">
+            <ul>
+                <li><a href="/">Home</a></li>
+                <li><a href="/news">News, weather and sports</a></li>
+            </ul>
+        </div>
+        <div class="layout">
+            <div class="links">
+                <a href="/1">A long link to another article in the sidebar</a>
+                <a href="/2">Another long link to one more article</a>
+            </div>
+            <div class="post-body">
+                <h2>Heading</h2>
+                <p>The first paragraph, which is long enough to count as content.</p>
+                <p>The second paragraph, with <a href="/link">a link</a>, and commas.</p>
+                <ul class="share">
+                    <li><a href="/share/a">Share on A</a></li>
+                    <li><a href="/share/b">Share on B</a></li>
+                </ul>
+            </div>
+        </div>
+        "#;
+    assert_eq!(
+        indoc!(
+            "
+            ## Heading
+
+            The first paragraph, which is long enough to count as content.
+
+            The second paragraph, with [a link](/link), and commas."
+        ),
+        extract(html)
+    );
+}
+
+#[test]
+fn link_lists_inside_content() {
+    let html = r#"
+        <main>
+            <p>Text</p>
+            <ul>
+                <li><a href="/de">Deutsch</a></li>
+                <li><a href="/fr">Français</a></li>
+            </ul>
+            <ul>
+                <li>Item, with <a href="/link">a link</a></li>
+            </ul>
+        </main>
+        "#;
+    assert_eq!("Text\n\n*   Item, with [a link](/link)", extract(html));
+}
+
+#[test]
+fn link_divs_inside_content() {
+    let html = r#"
+        <main>
+            <p>Text</p>
+            <div><a href="/r.pdf">Download the full annual report as PDF</a></div>
+            <div>See <a href="/m">the detailed methodology document on our site</a>.</div>
+            <div><p><a href="/d">Read the discussion of the results</a></p></div>
+            <div><div><a href="/a">Browse the archive of earlier reports</a></div></div>
+        </main>
+        "#;
+    assert_eq!(
+        indoc!(
+            "
+            Text
+
+            [Download the full annual report as PDF](/r.pdf)
+
+            See [the detailed methodology document on our site](/m).
+
+            [Read the discussion of the results](/d)
+
+            [Browse the archive of earlier reports](/a)"
+        ),
+        extract(html)
+    );
+}
+
+#[test]
+fn link_tables_inside_content() {
+    let html = r#"
+        <main>
+            <p>Text</p>
+            <table>
+                <tr><th>Crate</th><th>Docs</th></tr>
+                <tr><td><a href="/htmd">htmd</a></td><td><a href="/htmd/docs">docs</a></td></tr>
+            </table>
+        </main>
+        "#;
+    assert_eq!(
+        indoc!(
+            "
+            Text
+
+            | Crate         | Docs               |
+            | ------------- | ------------------ |
+            | [htmd](/htmd) | [docs](/htmd/docs) |"
+        ),
+        extract(html)
+    );
+}
+
+#[test]
+fn disabled_by_default() {
+    let html = "<nav>Menu</nav><main><p>Text</p></main>";
+    assert_eq!("Menu\n\nText", HtmlToMarkdown::new().convert(html).unwrap());
+}
+
+#[test]
+fn wikipedia_page() {
+    let html = include_str!("../examples/page-to-markdown/html/Elon Musk - Wikipedia.html");
+    let md = extract(html);
+    assert!(md.starts_with("# Elon Musk\n"));
+    assert!(md.contains("## Early life and education"));
+    // Navigation, tools and the language list are gone.
+    assert!(!md.contains("Jump to content"));
+    assert!(!md.contains("Main page"));
+    assert!(!md.contains("languages"));
+}
@@ -0,0 +1,123 @@
+use htmd::{
+    HtmlToMarkdown,
+    options::{LinkStyle, Options},
+    report::{DropReason, DroppedElement, Heading, Image, Link},
+};
+use pretty_assertions::assert_eq;
+
+const HTML: &str = r#"
+    <html>
+    <head><title> Hello,   World </title></head>
+    <body>
+        <nav><a href="/">Home</a></nav>
+        <h1>Hello <em>World</em></h1>
+        <p>Some <b>bold</b>er text with <a href="a b" title="A">a link</a>.</p>
+        <img src="/image.png" alt="An image">
+        <h2>Details</h2>
+        <p hidden>Hidden</p>
+        <div class="ad">Buy now</div>
+        <p>The end</p>
+    </body>
+    </html>
+    "#;
+
+fn converter() -> HtmlToMarkdown {
+    HtmlToMarkdown::builder()
+        .options(Options {
+            skip_hidden_content: true,
+            ..Default::default()
+        })
+        .base_url("https://example.com/")
+        .skip_tags(vec!["nav"])
+        .skip_selectors([".ad"])
+        .build()
+}
+
+#[test]
+fn report() {
+    let converter = converter();
+    let conversion = converter.convert_with_report(HTML).unwrap();
+    assert_eq!(converter.convert(HTML).unwrap(), conversion.markdown);
+    assert_eq!(Some("Hello, World"), conversion.title.as_deref());
+    assert_eq!(
+        vec![Link {
+            url: "https://example.com/a b".to_string(),
+            text: "a link".to_string(),
+            title: Some("A".to_string()),
+        }],
+        conversion.links
+    );
+    assert_eq!(
+        vec![Image {
+            url: "https://example.com/image.png".to_string(),
+            alt: Some("An image".to_string()),
+            title: None,
+        }],
+        conversion.images
+    );
+    assert_eq!(
+        vec![
+            Heading {
+                level: 1,
+                text: "Hello *World*".to_string(),
+            },
+            Heading {
+                level: 2,
+                text: "Details".to_string(),
+            },
+        ],
+        conversion.headings
+    );
+    // "bolder" is a single word.
+    assert_eq!(11, conversion.word_count);
+    let dropped = |tag: &str, reason| DroppedElement {
+        tag: tag.to_string(),
+        reason,
+    };
+    assert_eq!(
+        vec![
+            dropped("head", DropReason::Hidden),
+            dropped("nav", DropReason::Skipped),
+            dropped("p", DropReason::Hidden),
+            dropped("div", DropReason::Excluded),
+        ],
+        conversion.dropped_elements
+    );
+}
+
+#[test]
+fn empty_elements_are_not_dropped() {
+    let conversion = HtmlToMarkdown::new()
+        .convert_with_report("<body><em></em><p></p><div><span></span></div></body>")
+        .unwrap();
+    assert_eq!("", conversion.markdown);
+    assert!(conversion.dropped_elements.is_empty());
+}
+
+#[test]
+fn report_is_per_conversion() {
+    let converter = HtmlToMarkdown::builder()
+        .options(Options {
+            link_style: LinkStyle::Referenced,
+            ..Default::default()
+        })
+        .build();
+    let first = converter
+        .convert_with_report("<a href='/a'>A</a> <a href='/b'>B</a>")
+        .unwrap();
+    assert_eq!(2, first.links.len());
+    assert_eq!("[A][1] [B][2]\n\n[1]: /a\n[2]: /b", first.markdown);
+
+    let second = converter.convert_with_report("<p>No links</p>").unwrap();
+    assert_eq!(None, second.title);
+    assert!(second.links.is_empty());
+    assert_eq!(2, second.word_count);
+}
+
+#[test]
+fn words_are_split_by_blocks() {
+    let conversion = HtmlToMarkdown::new()
+        .convert_with_report("<p>one</p><p>two</p><div>three<br>four</div><span>fi</span>ve")
+        .unwrap();
+    assert_eq!(5, conversion.word_count);
+}
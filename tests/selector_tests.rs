@@ -0,0 +1,100 @@
+use htmd::HtmlToMarkdown;
+use pretty_assertions::assert_eq;
+
+const HTML: &str = r#"
+    <nav><a href="/">Home</a></nav>
+    <article>
+        <h1>Title</h1>
+        <div class="content">
+            <p>Text</p>
+            <div class="ad">Buy now</div>
+            <p aria-hidden="true">Hidden</p>
+        </div>
+        <section id="comments"><p>Comment</p></section>
+    </article>
+    "#;
+
+#[test]
+fn skip_selectors() {
+    let converter = HtmlToMarkdown::builder()
+        .skip_selectors(["nav", ".ad", "#comments", "[aria-hidden=true]"])
+        .build();
+    assert_eq!("# Title\n\nText", converter.convert(HTML).unwrap());
+}
+
+#[test]
+fn skip_selectors_accumulate() {
+    let converter = HtmlToMarkdown::builder()
+        .skip_selectors(vec!["nav, .ad"])
+        .skip_selectors(["#comments"])
+        .skip_tags(vec!["h1"])
+        .build();
+    assert_eq!("Text\n\nHidden", converter.convert(HTML).unwrap());
+}
+
+#[test]
+fn select_root() {
+    let converter = HtmlToMarkdown::builder()
+        .select_root("article .content")
+        .skip_selectors([".ad", "p:last-child"])
+        .build();
+    assert_eq!("Text", converter.convert(HTML).unwrap());
+
+    // The first match in document order
+    let converter = HtmlToMarkdown::builder().select_root("p").build();
+    assert_eq!("Text", converter.convert(HTML).unwrap());
+
+    let converter = HtmlToMarkdown::builder().select_root("main").build();
+    assert_eq!("", converter.convert(HTML).unwrap());
+}
+
+#[test]
+fn select_root_with_main_content_extraction() {
+    let converter = HtmlToMarkdown::builder()
+        .select_root("article")
+        .extract_main_content(true)
+        .skip_selectors([".ad"])
+        .build();
+    assert_eq!(
+        "# Title\n\nText\n\nHidden",
+        converter.convert(HTML).unwrap()
+    );
+}
+
+#[test]
+#[should_panic(expected = "invalid selector \"div >\"")]
+fn invalid_selector() {
+    HtmlToMarkdown::builder().skip_selectors(["div >"]);
+}
+
+#[test]
+fn try_invalid_selector() {
+    let err = HtmlToMarkdown::builder()
+        .try_skip_selectors(["nav", "div >"])
+        .err()
+        .unwrap();
+    assert!(matches!(
+        err,
+        htmd::Error::InvalidSelector { ref selector, .. } if selector == "div >"
+    ));
+    assert!(err.to_string().starts_with("invalid selector \"div >\": "));
+
+    let err = HtmlToMarkdown::builder()
+        .try_select_root("a:hover")
+        .err()
+        .unwrap();
+    assert!(matches!(err, htmd::Error::InvalidSelector { .. }));
+
+    let converter = HtmlToMarkdown::builder()
+        .try_select_root("p")
+        .unwrap()
+        .try_skip_selectors([".ad"])
+        .unwrap()
+        .build();
+    assert_eq!(
+        "Text",
+        converter
+            .convert(r#"<h1>Title</h1><p>Text<span class="ad"> Buy</span></p>"#)
+            .unwrap()
+    );
+}
@@ -0,0 +1,165 @@
+use htmd::{
+    Element, HtmlToMarkdown, SourceMap,
+    element_handler::Handlers,
+    options::{FrontMatterStyle, LinkStyle, Options},
+};
+use indoc::indoc;
+use markup5ever_rcdom::NodeData;
+use pretty_assertions::assert_eq;
+
+const HTML: &str = indoc!(
+    r#"
+    <h1>Title</h1>
+    <blockquote>
+      <p>Quote</p>
+      <p>More</p>
+    </blockquote>
+    <ol>
+      <li><p>One</p></li>
+      <li>Two
+        <ul><li>Nested</li></ul>
+      </li>
+    </ol>
+    <p>A <a href="/a">link</a></p>"#
+);
+
+/// The tag, line and Markdown of the blocks.
+fn blocks(source_map: &SourceMap) -> Vec<(&str, Option<u64>, &str)> {
+    source_map
+        .blocks
+        .iter()
+        .map(|block| {
+            (
+                block.tag.as_str(),
+                block.line,
+                &source_map.markdown[block.range.clone()],
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn source_map() {
+    let converter = HtmlToMarkdown::new();
+    let source_map = converter.convert_with_source_map(HTML).unwrap();
+    assert_eq!(converter.convert(HTML).unwrap(), source_map.markdown);
+    assert_eq!(
+        vec![
+            ("h1", Some(1), "# Title"),
+            ("blockquote", Some(2), "> Quote\n> \n> More"),
+            ("p", Some(3), "Quote"),
+            ("p", Some(4), "More"),
+            ("ol", Some(6), "1.  One\n\n2.  Two\n    *   Nested"),
+            ("li", Some(7), "1.  One"),
+            ("p", Some(7), "One"),
+            ("li", Some(8), "2.  Two\n    *   Nested"),
+            ("ul", Some(9), "*   Nested"),
+            ("li", Some(9), "*   Nested"),
+            ("p", Some(12), "A [link](/a)"),
+        ],
+        blocks(&source_map)
+    );
+    assert!(
+        source_map
+            .blocks
+            .iter()
+            .all(|block| match &block.node.data {
+                NodeData::Element { name, .. } => *name.local == block.tag,
+                _ => false,
+            })
+    );
+}
+
+#[test]
+fn appended_content() {
+    let converter = HtmlToMarkdown::builder()
+        .options(Options {
+            link_style: LinkStyle::Referenced,
+            front_matter_style: FrontMatterStyle::Yaml,
+            ..Default::default()
+        })
+        .build();
+    let html = "<title>Doc</title><p>A <a href=\"/a\">link</a></p>";
+    let source_map = converter.convert_with_source_map(html).unwrap();
+    assert_eq!(converter.convert(html).unwrap(), source_map.markdown);
+    assert_eq!(vec![("p", Some(1), "A [link][1]")], blocks(&source_map));
+}
+
+#[test]
+fn blocks_mapped_as_a_whole() {
+    let converter = HtmlToMarkdown::builder()
+        .add_handler(
+            vec!["aside"],
+            |handlers: &dyn Handlers, element: Element| {
+                let content = handlers.walk_children(element.node).content;
+                // The handler sees the Markdown of the children unchanged.
+                assert_eq!("\n\nNote\n\n", content);
+                Some(format!("> [!NOTE]\n> {}", content.trim()).into())
+            },
+        )
+        .build();
+    let html = indoc!(
+        r#"
+        <table><tr><th><p>A</p></th></tr></table>
+        <h2><div>Heading</div></h2>
+        <aside><p>Note</p></aside>"#
+    );
+    let source_map = converter.convert_with_source_map(html).unwrap();
+    assert_eq!(converter.convert(html).unwrap(), source_map.markdown);
+    assert_eq!(
+        vec![
+            ("table", Some(1), "| A |\n| - |"),
+            ("h2", Some(2), "## Heading"),
+            ("aside", Some(3), "> [!NOTE]\n> Note"),
+        ],
+        blocks(&source_map)
+    );
+}
+
+#[test]
+fn tree_without_lines() {
+    let converter = HtmlToMarkdown::new();
+    let tree = converter.html_to_tree("<p>One</p><p>Two</p>").unwrap();
+    let source_map = converter.tree_to_markdown_with_source_map(&tree).unwrap();
+    assert_eq!(
+        vec![("p", None, "One"), ("p", None, "Two")],
+        blocks(&source_map)
+    );
+}
+
+#[test]
+fn private_use_characters() {
+    let converter = HtmlToMarkdown::new();
+    let html = "<p>\u{F0000}\u{F0000}hi\u{100000}\u{100000}</p><p>\u{F0000}\u{F0001}</p>";
+    let source_map = converter.convert_with_source_map(html).unwrap();
+    assert_eq!(converter.convert(html).unwrap(), source_map.markdown);
+    assert_eq!(
+        vec![
+            ("p", Some(1), "\u{F0000}\u{F0000}hi\u{100000}\u{100000}"),
+            ("p", Some(1), "\u{F0000}\u{F0001}"),
+        ],
+        blocks(&source_map)
+    );
+}
+
+#[test]
+fn changed_lines() {
+    let converter = HtmlToMarkdown::new();
+    let html = indoc!(
+        "
+        <blockquote><p>One<br>two</p></blockquote>
+        <ol><li><p>Three<br>four</p><p>Five</p></li></ol>"
+    );
+    let source_map = converter.convert_with_source_map(html).unwrap();
+    assert_eq!(
+        vec![
+            ("blockquote", Some(1), "> One  \n> two"),
+            ("p", Some(1), "One  \n> two"),
+            ("ol", Some(2), "1.  Three\n    four\n\n    Five"),
+            ("li", Some(2), "1.  Three\n    four\n\n    Five"),
+            ("p", Some(2), "Three\n    four"),
+            ("p", Some(2), "Five"),
+        ],
+        blocks(&source_map)
+    );
+}
@@ -4,6 +4,10 @@ use common::convert;
 #[cfg(test)]
 mod table_tests_1 {
     use super::convert;
+    use htmd::{
+        HtmlToMarkdown,
+        options::{Options, TableSpanStyle},
+    };
     use indoc::indoc;
     use pretty_assertions::assert_eq;
 
@@ -440,4 +444,228 @@ Sample Table
 
         assert_eq!(expected, markdown);
     }
+
+    fn pure_converter(span_style: TableSpanStyle) -> HtmlToMarkdown {
+        HtmlToMarkdown::builder()
+            .options(Options {
+                table_span_style: span_style,
+                ..Default::default()
+            })
+            .build()
+    }
+
+    const SPANNED_TABLE: &str = r#"
+        <table>
+            <tr>
+                <th rowspan="2">Region</th>
+                <th colspan="2">Sales</th>
+            </tr>
+            <tr>
+                <th>Q1</th>
+                <th>Q2</th>
+            </tr>
+            <tr>
+                <td>North</td>
+                <td>10</td>
+                <td>20</td>
+            </tr>
+            <tr>
+                <td rowspan="2">South</td>
+                <td colspan="2">n/a</td>
+            </tr>
+            <tr>
+                <td>30</td>
+                <td>40</td>
+            </tr>
+        </table>
+        "#;
+
+    #[test]
+    fn test_spans_with_empty_cells() {
+        let expected = indoc!(
+            r#"
+            | Region | Sales |    |
+            | ------ | ----- | -- |
+            |        | Q1    | Q2 |
+            | North  | 10    | 20 |
+            | South  | n/a   |    |
+            |        | 30    | 40 |
+            "#
+        )
+        .trim();
+        let markdown = pure_converter(TableSpanStyle::Empty)
+            .convert(SPANNED_TABLE)
+            .unwrap();
+        assert_eq!(expected, markdown);
+    }
+
+    #[test]
+    fn test_spans_with_repeated_cells() {
+        let expected = indoc!(
+            r#"
+            | Region | Sales | Sales |
+            | ------ | ----- | ----- |
+            | Region | Q1    | Q2    |
+            | North  | 10    | 20    |
+            | South  | n/a   | n/a   |
+            | South  | 30    | 40    |
+            "#
+        )
+        .trim();
+        let markdown = pure_converter(TableSpanStyle::Repeat)
+            .convert(SPANNED_TABLE)
+            .unwrap();
+        assert_eq!(expected, markdown);
+    }
+
+    #[test]
+    fn test_rowspan_in_last_column() {
+        let html = r#"
+        <table>
+            <tr><th>A</th><th>B</th></tr>
+            <tr><td>1</td><td rowspan="0">All</td></tr>
+            <tr><td>2</td></tr>
+            <tr><td>3</td></tr>
+        </table>
+        "#;
+        let expected = indoc!(
+            r#"
+            | A | B   |
+            | - | --- |
+            | 1 | All |
+            | 2 | All |
+            | 3 | All |
+            "#
+        )
+        .trim();
+        let markdown = pure_converter(TableSpanStyle::Repeat)
+            .convert(html)
+            .unwrap();
+        assert_eq!(expected, markdown);
+    }
+
+    #[test]
+    fn test_spans_in_faithful_mode() {
+        let html = r#"<table><tbody><tr><th colspan="2">A</th></tr><tr><td>1</td><td>2</td></tr></tbody></table>"#;
+        assert_eq!(html, convert(html).unwrap());
+    }
+
+    #[test]
+    fn test_column_alignment() {
+        let html = r#"
+        <table>
+            <thead>
+                <tr>
+                    <th align="left">Item</th>
+                    <th style="text-align: center">Qty</th>
+                    <th align="right">Price</th>
+                    <th>Note</th>
+                </tr>
+            </thead>
+            <tbody>
+                <tr><td>Apple</td><td>3</td><td>1.50</td><td>Fresh</td></tr>
+                <tr><td>Watermelon</td><td>12</td><td>120.00</td><td></td></tr>
+            </tbody>
+        </table>
+        "#;
+        let expected = indoc!(
+            r#"
+            | Item       | Qty |  Price | Note  |
+            | :--------- | :-: | -----: | ----- |
+            | Apple      |  3  |   1.50 | Fresh |
+            | Watermelon | 12  | 120.00 |       |
+            "#
+        )
+        .trim();
+        assert_eq!(expected, htmd::convert(html).unwrap());
+    }
+
+    #[test]
+    fn test_column_alignment_from_col_and_body_cells() {
+        let html = r#"
+        <table>
+            <colgroup>
+                <col>
+                <col align="right">
+            </colgroup>
+            <tr><th>Name</th><th>Amount</th><th>Total</th></tr>
+            <tr><td>A</td><td>1</td><td style="text-align:right">10</td></tr>
+            <tr><td>B</td><td>200</td><td style="text-align:right">2000</td></tr>
+        </table>
+        "#;
+        let expected = indoc!(
+            r#"
+            | Name | Amount | Total |
+            | ---- | -----: | ----: |
+            | A    |      1 |    10 |
+            | B    |    200 |  2000 |
+            "#
+        )
+        .trim();
+        assert_eq!(expected, htmd::convert(html).unwrap());
+    }
+
+    #[test]
+    fn test_narrow_aligned_column() {
+        let html = r#"
+        <table>
+            <tr><th align="center">A</th><th align="right">B</th></tr>
+            <tr><td>1</td><td>2</td></tr>
+        </table>
+        "#;
+        let expected = indoc!(
+            r#"
+            |  A  |  B |
+            | :-: | -: |
+            |  1  |  2 |
+            "#
+        )
+        .trim();
+        assert_eq!(expected, htmd::convert(html).unwrap());
+    }
+
+    #[test]
+    fn test_wide_characters() {
+        let html = r#"
+        <table>
+            <tr><th>Name</th><th>Note</th></tr>
+            <tr><td>日本語</td><td>👍</td></tr>
+            <tr><td>Cafe&#x301;</td><td>👨&#x200d;👩&#x200d;👧 family</td></tr>
+        </table>
+        "#;
+        let expected = "| Name   | Note      |\n\
+                        | ------ | --------- |\n\
+                        | 日本語 | 👍        |\n\
+                        | Cafe\u{301}   | 👨\u{200d}👩\u{200d}👧 family |";
+        assert_eq!(expected, htmd::convert(html).unwrap());
+    }
+
+    #[test]
+    fn test_without_padding() {
+        let html = r#"
+        <table>
+            <tr><th>Name</th><th align="right">Amount</th></tr>
+            <tr><td>A</td><td>1</td></tr>
+            <tr><td>Longer name</td><td>200</td></tr>
+        </table>
+        "#;
+        let expected = indoc!(
+            "
+            | Name | Amount |
+            | --- | --: |
+            | A | 1 |
+            | Longer name | 200 |
+            "
+        )
+        .trim();
+        let markdown = HtmlToMarkdown::builder()
+            .options(Options {
+                pad_table_cells: false,
+                ..Default::default()
+            })
+            .build()
+            .convert(html)
+            .unwrap();
+        assert_eq!(expected, markdown);
+    }
 }
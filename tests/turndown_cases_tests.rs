@@ -19,97 +19,8 @@ struct TestCase {
 fn run_cases() {
     let cases = load_test_cases();
     for (index, case) in cases.iter().enumerate() {
-        let opt = case.data_options.as_ref();
-
-        let is_atx_heading = opt.is_some_and(|opt| opt == r#"{"headingStyle":"atx"}"#);
-        let heading_style = if is_atx_heading {
-            HeadingStyle::Atx
-        } else {
-            HeadingStyle::Setex
-        };
-
-        let is_dashes_hr = opt.is_some_and(|opt| opt == r#"{"hr": "- - -"}"#);
-        let hr_style = if is_dashes_hr {
-            HrStyle::Dashes
-        } else {
-            HrStyle::Asterisks
-        };
-
-        let is_referenced_link =
-            opt.is_some_and(|opt| opt.starts_with(r#"{"linkStyle": "referenced""#));
-        let link_style = if is_referenced_link {
-            LinkStyle::Referenced
-        } else {
-            LinkStyle::Inlined
-        };
-
-        let link_reference_style = match opt.map(|opt| opt.as_str()) {
-            Some(r#"{"linkStyle": "referenced", "linkReferenceStyle": "collapsed"}"#) => {
-                LinkReferenceStyle::Collapsed
-            }
-            Some(r#"{"linkStyle": "referenced", "linkReferenceStyle": "shortcut"}"#) => {
-                LinkReferenceStyle::Shortcut
-            }
-            _ => LinkReferenceStyle::Full,
-        };
-
-        let is_backslash_br = opt.is_some_and(|opt| opt == r#"{"br": "\\"}"#);
-        let br_style = if is_backslash_br {
-            BrStyle::Backslash
-        } else {
-            BrStyle::TwoSpaces
-        };
-
-        let is_fenced_code_block =
-            opt.is_some_and(|opt| opt.starts_with(r#"{"codeBlockStyle": "fenced""#));
-        let code_block_style = if is_fenced_code_block {
-            CodeBlockStyle::Fenced
-        } else {
-            CodeBlockStyle::Indented
-        };
-
-        let is_tildes_fence = opt.is_some_and(|opt| opt.contains(r#""fence": "~~~""#));
-        let code_block_fence = if is_tildes_fence {
-            CodeBlockFence::Tildes
-        } else {
-            CodeBlockFence::Backticks
-        };
-
-        let is_dash_bullet_list_marker =
-            opt.is_some_and(|opt| opt == r#"{"bulletListMarker": "-"}"#);
-        let bullet_list_marker = if is_dash_bullet_list_marker {
-            BulletListMarker::Dash
-        } else {
-            BulletListMarker::Asterisk
-        };
-
-        let ul_bullet_spacing = 3;
-        let ol_number_spacing = 2;
-
-        let preformatted_code = opt.is_some_and(|opt| opt == r#"{"preformattedCode": true}"#);
-
-        let translation_mode =
-            if opt.is_some_and(|opt| opt.contains(r#""translationMode": "Pure""#)) {
-                TranslationMode::Pure
-            } else {
-                TranslationMode::Faithful
-            };
-
         let converter = HtmlToMarkdown::builder()
-            .options(Options {
-                heading_style,
-                hr_style,
-                br_style,
-                link_style,
-                link_reference_style,
-                code_block_style,
-                code_block_fence,
-                bullet_list_marker,
-                ul_bullet_spacing,
-                ol_number_spacing,
-                preformatted_code,
-                translation_mode,
-            })
+            .options(case_options(case.data_options.as_ref()))
             .build();
 
         let md = converter.convert(&case.html).unwrap();
@@ -130,6 +41,118 @@ fn run_cases() {
     }
 }
 
+#[test]
+fn mdast_round_trips_cases() {
+    let cases = load_test_cases();
+    for (index, case) in cases.iter().enumerate() {
+        let options = case_options(case.data_options.as_ref());
+        let converter = HtmlToMarkdown::builder()
+            .options(case_options(case.data_options.as_ref()))
+            .build();
+        let md = converter.convert(&case.html).unwrap();
+        let tree = converter.convert_to_mdast(&case.html).unwrap();
+
+        assert_eq!(
+            md,
+            tree.to_markdown(&options),
+            "Failed on test case '{}' ({}/{})",
+            case.name,
+            index + 1,
+            cases.len()
+        );
+    }
+}
+
+fn case_options(opt: Option<&String>) -> Options {
+    let is_atx_heading = opt.is_some_and(|opt| opt == r#"{"headingStyle":"atx"}"#);
+    let heading_style = if is_atx_heading {
+        HeadingStyle::Atx
+    } else {
+        HeadingStyle::Setex
+    };
+
+    let is_dashes_hr = opt.is_some_and(|opt| opt == r#"{"hr": "- - -"}"#);
+    let hr_style = if is_dashes_hr {
+        HrStyle::Dashes
+    } else {
+        HrStyle::Asterisks
+    };
+
+    let is_referenced_link =
+        opt.is_some_and(|opt| opt.starts_with(r#"{"linkStyle": "referenced""#));
+    let link_style = if is_referenced_link {
+        LinkStyle::Referenced
+    } else {
+        LinkStyle::Inlined
+    };
+
+    let link_reference_style = match opt.map(|opt| opt.as_str()) {
+        Some(r#"{"linkStyle": "referenced", "linkReferenceStyle": "collapsed"}"#) => {
+            LinkReferenceStyle::Collapsed
+        }
+        Some(r#"{"linkStyle": "referenced", "linkReferenceStyle": "shortcut"}"#) => {
+            LinkReferenceStyle::Shortcut
+        }
+        _ => LinkReferenceStyle::Full,
+    };
+
+    let is_backslash_br = opt.is_some_and(|opt| opt == r#"{"br": "\\"}"#);
+    let br_style = if is_backslash_br {
+        BrStyle::Backslash
+    } else {
+        BrStyle::TwoSpaces
+    };
+
+    let is_fenced_code_block =
+        opt.is_some_and(|opt| opt.starts_with(r#"{"codeBlockStyle": "fenced""#));
+    let code_block_style = if is_fenced_code_block {
+        CodeBlockStyle::Fenced
+    } else {
+        CodeBlockStyle::Indented
+    };
+
+    let is_tildes_fence = opt.is_some_and(|opt| opt.contains(r#""fence": "~~~""#));
+    let code_block_fence = if is_tildes_fence {
+        CodeBlockFence::Tildes
+    } else {
+        CodeBlockFence::Backticks
+    };
+
+    let is_dash_bullet_list_marker = opt.is_some_and(|opt| opt == r#"{"bulletListMarker": "-"}"#);
+    let bullet_list_marker = if is_dash_bullet_list_marker {
+        BulletListMarker::Dash
+    } else {
+        BulletListMarker::Asterisk
+    };
+
+    let ul_bullet_spacing = 3;
+    let ol_number_spacing = 2;
+
+    let preformatted_code = opt.is_some_and(|opt| opt == r#"{"preformattedCode": true}"#);
+
+    let translation_mode = if opt.is_some_and(|opt| opt.contains(r#""translationMode": "Pure""#)) {
+        TranslationMode::Pure
+    } else {
+        TranslationMode::Faithful
+    };
+
+    Options {
+        heading_style,
+        hr_style,
+        br_style,
+        link_style,
+        link_reference_style,
+        code_block_style,
+        code_block_fence,
+        bullet_list_marker,
+        ul_bullet_spacing,
+        ol_number_spacing,
+        preformatted_code,
+        translation_mode,
+        ..Default::default()
+    }
+}
+
 fn load_test_cases() -> Vec<TestCase> {
     let mut cases = Vec::<TestCase>::new();
 